@@ -1,19 +1,24 @@
 //! Engine-specific game data and logic
 
+use std::cell::RefCell;
 use std::sync::{Arc, Once, Weak};
 use std::time::Duration;
 
 use audio::AudioAssets;
-use bevy::asset::AssetServer;
+use bevy::asset::{AssetPath, AssetServer, LoadState, UntypedHandle};
 use bevy::ecs::component::Component;
 use bevy::ecs::system::{EntityCommands, Resource};
 use bevy::math::Vec2;
 use bevy::prelude::*;
 
 pub mod animation;
+pub mod attract;
 pub mod audio;
 pub mod beam;
 pub mod border;
+pub mod camera;
+#[cfg(feature = "debug-overlay")]
+pub mod debug;
 pub mod focus;
 pub mod gui;
 pub mod input;
@@ -21,13 +26,14 @@ pub mod level;
 pub mod manipulator;
 pub mod particle;
 pub mod tile;
+pub mod tutorial;
 
 use crate::model::{BoardCoords, Direction};
 
 use self::beam::BeamAssets;
 use self::border::BorderAssets;
 use self::focus::FocusAssets;
-use self::gui::GuiAssets;
+use self::gui::{GuiAssets, IN_GAME_PANEL_WIDTH, WINDOW_HEIGHT, WINDOW_WIDTH};
 use self::manipulator::ManipulatorAssets;
 use self::particle::ParticleAssets;
 use self::tile::TileAssets;
@@ -36,6 +42,13 @@ const TILE_WIDTH: f32 = 45.0;
 const TILE_HEIGHT: f32 = 45.0;
 const COORDS_ORIGIN_OFFSET: Vec2 = Vec2 { x: 22.5, y: -22.5 };
 const MOVE_DURATION: Duration = Duration::from_millis(500);
+const IDLE_FRAME_RATE: f32 = 48.0;
+const LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+const PLAY_AREA_SIZE: Vec2 = Vec2::new(
+    (WINDOW_WIDTH - IN_GAME_PANEL_WIDTH) as f32,
+    WINDOW_HEIGHT as f32,
+);
 
 #[derive(States, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GameState {
@@ -43,6 +56,7 @@ pub enum GameState {
     Init,
     MainMenu,
     ClassicLevelSelect,
+    Briefing,
     Playing,
     GameOver,
 }
@@ -55,7 +69,7 @@ impl ComputedStates for InLevel {
 
     fn compute(sources: Self::SourceStates) -> Option<Self> {
         match sources {
-            GameState::Playing | GameState::GameOver => Some(Self),
+            GameState::Briefing | GameState::Playing | GameState::GameOver => Some(Self),
             _ => None,
         }
     }
@@ -75,9 +89,17 @@ pub struct BoardCoordsHolder(pub BoardCoords);
 
 pub struct AssetsPlugin;
 
+// NOTE: Every asset is loaded up front, and each load holds on to the barrier until it's done.
+// The handles are kept, so that the assets holding up the load can be reported.
+pub struct LoadBarrier {
+    guard: Arc<()>,
+    handles: RefCell<Vec<UntypedHandle>>,
+}
+
 #[derive(Resource)]
 pub struct GameAssets {
     load_barrier: Weak<()>,
+    load_handles: Vec<UntypedHandle>,
     event_trigger: Once,
     gui: GuiAssets,
     audio: AudioAssets,
@@ -92,38 +114,107 @@ pub struct GameAssets {
 #[derive(Event, Debug)]
 pub struct AssetsLoaded;
 
+// NOTE: Lists the assets that failed to load, or were still loading when the load timed out
+#[derive(Resource, Debug)]
+pub struct AssetLoadFailure(pub Vec<String>);
+
+impl LoadBarrier {
+    fn new() -> Self {
+        Self {
+            guard: Arc::new(()),
+            handles: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn load<'a, A: Asset>(
+        &self,
+        server: &AssetServer,
+        path: impl Into<AssetPath<'a>>,
+    ) -> Handle<A> {
+        let handle = server.load_acquire(path, Arc::clone(&self.guard));
+        self.handles.borrow_mut().push(handle.clone().untyped());
+        handle
+    }
+}
+
 impl GameAssets {
     pub fn load(server: &AssetServer) -> Self {
-        let load_barrier = Arc::new(());
+        let barrier = LoadBarrier::new();
+        let gui = GuiAssets::load(server, &barrier);
+        let audio = AudioAssets::load(server, &barrier);
+        let tiles = TileAssets::load(server, &barrier);
+        let borders = BorderAssets::load(server, &barrier);
+        let particles = ParticleAssets::load(server, &barrier);
+        let manipulators = ManipulatorAssets::load(server, &barrier);
+        let beams = BeamAssets::load(server, &barrier);
+        let focus = FocusAssets::load(server, &barrier);
         Self {
-            load_barrier: Arc::downgrade(&load_barrier),
+            load_barrier: Arc::downgrade(&barrier.guard),
+            load_handles: barrier.handles.into_inner(),
             event_trigger: Once::new(),
-            gui: GuiAssets::load(server, &load_barrier),
-            audio: AudioAssets::load(server, &load_barrier),
-            tiles: TileAssets::load(server, &load_barrier),
-            borders: BorderAssets::load(server, &load_barrier),
-            particles: ParticleAssets::load(server, &load_barrier),
-            manipulators: ManipulatorAssets::load(server, &load_barrier),
-            beams: BeamAssets::load(server, &load_barrier),
-            focus: FocusAssets::load(server, &load_barrier),
+            gui,
+            audio,
+            tiles,
+            borders,
+            particles,
+            manipulators,
+            beams,
+            focus,
         }
     }
 
     fn ready(&self) -> bool {
         self.load_barrier.strong_count() == 0
     }
+
+    fn unloaded(&self, server: &AssetServer) -> Vec<String> {
+        self.load_handles
+            .iter()
+            .filter_map(|handle| {
+                let state = server.get_load_state(handle.id());
+                if let Some(LoadState::Loaded) = state {
+                    return None;
+                }
+                let path = match handle.path() {
+                    Some(path) => path.to_string(),
+                    None => format!("{:?}", handle.id()),
+                };
+                Some(format!("{}: {:?}", path, state))
+            })
+            .collect()
+    }
 }
 
 fn load_assets(mut commands: Commands, server: Res<AssetServer>) {
     commands.insert_resource(GameAssets::load(&server));
 }
 
-fn monitor_load(assets: Res<GameAssets>, mut ev_loaded: EventWriter<AssetsLoaded>) {
-    if assets.ready() {
-        assets.event_trigger.call_once(|| {
-            ev_loaded.send(AssetsLoaded);
-        });
+// NOTE: A missing or broken asset fails the load, and so does a load that takes too long, rather
+// than leaving the game stuck on a blank screen
+fn monitor_load(
+    assets: Res<GameAssets>,
+    server: Res<AssetServer>,
+    time: Res<Time>,
+    mut ev_loaded: EventWriter<AssetsLoaded>,
+    mut commands: Commands,
+) {
+    let ready = assets.ready();
+    if !ready && (time.elapsed() < LOAD_TIMEOUT) {
+        return;
+    }
+    let unloaded = assets.unloaded(&server);
+    if unloaded.is_empty() {
+        if ready {
+            assets.event_trigger.call_once(|| {
+                ev_loaded.send(AssetsLoaded);
+            });
+        }
+        return;
+    }
+    for asset in unloaded.iter() {
+        error!("Asset not loaded: {}", asset);
     }
+    commands.insert_resource(AssetLoadFailure(unloaded));
 }
 
 #[derive(Debug, Default)]
@@ -131,16 +222,24 @@ pub struct SpriteSheet {
     texture: Handle<Image>,
     layout: Handle<TextureAtlasLayout>,
     frames: usize,
+    frame_rate: f32,
 }
 
 impl SpriteSheet {
-    fn new(texture: Handle<Image>, tile_size: UVec2, frames: usize, server: &AssetServer) -> Self {
+    fn new(
+        texture: Handle<Image>,
+        tile_size: UVec2,
+        frames: usize,
+        frame_rate: f32,
+        server: &AssetServer,
+    ) -> Self {
         let layout = TextureAtlasLayout::from_grid(tile_size, 1, frames as _, None, None);
         let layout = server.add(layout);
         Self {
             texture,
             layout,
             frames,
+            frame_rate,
         }
     }
 }
@@ -207,6 +306,11 @@ impl Plugin for AssetsPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<AssetsLoaded>()
             .add_systems(Startup, load_assets)
-            .add_systems(PreUpdate, monitor_load.run_if(in_state(GameState::Init)));
+            .add_systems(
+                PreUpdate,
+                monitor_load.run_if(
+                    in_state(GameState::Init).and_then(not(resource_exists::<AssetLoadFailure>)),
+                ),
+            );
     }
 }
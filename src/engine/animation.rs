@@ -7,6 +7,9 @@ use interpolation::Ease;
 
 use crate::model::{Direction, GridSet};
 
+use super::beam::BeamSet;
+use super::focus::{Focus, UpdateFocusEvent};
+use super::level::Level;
 use super::{
     BoardCoordsHolder, EngineCoords, EngineDirection, GameplaySet, InLevelSet, SpriteSheet,
     MOVE_DURATION,
@@ -27,7 +30,13 @@ pub struct AnimationSet;
 pub struct IdleAnimationSet;
 
 #[derive(Resource, Debug, Default)]
-struct AnimationStateHolder(Option<AnimationState>);
+pub struct AnimationStateHolder(Option<AnimationState>);
+
+#[derive(Resource, Debug, Default)]
+pub struct ReduceMotion(pub bool);
+
+#[derive(Resource, Debug, Default)]
+pub(crate) struct FadeInState(Option<Duration>);
 
 #[derive(Debug)]
 struct AnimationState {
@@ -55,6 +64,11 @@ pub struct FadeOutAnimator {
     is_fading: bool,
 }
 
+#[derive(Component)]
+struct FadeInAnimator {
+    alpha: f32,
+}
+
 #[derive(Bundle, Default)]
 pub struct AnimationBundle {
     mover: MovementAnimator,
@@ -69,7 +83,27 @@ pub struct AnimatedSpriteBundle {
 }
 
 #[derive(Component, Debug)]
-pub struct IdleAnimation(usize);
+pub struct IdleAnimation {
+    frames: usize,
+    frame_rate: f32,
+}
+
+impl AnimationStateHolder {
+    pub fn is_idle(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+impl ReduceMotion {
+    // NOTE: With reduced motion, an animation is played through in a single tick, so the pieces
+    // snap into place while everything waiting on it still sees it finish.
+    pub fn delta(&self, time: &Time, total_duration: Duration) -> Duration {
+        match self.0 {
+            false => time.delta(),
+            true => total_duration,
+        }
+    }
+}
 
 impl AnimationState {
     fn progress(&self) -> f32 {
@@ -100,7 +134,10 @@ impl AnimatedSpriteBundle {
                 layout: sheet.layout.clone(),
                 index: 0,
             },
-            animation: IdleAnimation(sheet.frames),
+            animation: IdleAnimation {
+                frames: sheet.frames,
+                frame_rate: sheet.frame_rate,
+            },
         }
     }
 }
@@ -149,6 +186,7 @@ fn start_animation(
 fn animate_movement(
     mut ev_animation_finished: EventWriter<AnimationFinished>,
     time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
     mut state_holder: ResMut<AnimationStateHolder>,
     mut q_animator: Query<(&mut MovementAnimator, &mut Transform)>,
 ) {
@@ -159,7 +197,7 @@ fn animate_movement(
         return;
     };
 
-    state.tick(time.delta());
+    state.tick(reduce_motion.delta(&time, state.total_duration));
 
     for (mut animator, mut xform) in q_animator.iter_mut() {
         if !animator.is_moving {
@@ -182,6 +220,7 @@ fn animate_movement(
 fn animate_fade_out(
     mut ev_animation_finished: EventWriter<AnimationFinished>,
     time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
     mut state_holder: ResMut<AnimationStateHolder>,
     mut q_animator: Query<(&mut FadeOutAnimator, &mut Sprite)>,
 ) {
@@ -192,7 +231,7 @@ fn animate_fade_out(
         return;
     };
 
-    state.tick(time.delta());
+    state.tick(reduce_motion.delta(&time, state.total_duration));
 
     for (mut animator, mut sprite) in q_animator.iter_mut() {
         if !animator.is_fading {
@@ -209,16 +248,83 @@ fn animate_fade_out(
     }
 }
 
-fn animate_idle(mut q_effect: Query<(&mut TextureAtlas, &IdleAnimation)>, time: Res<Time>) {
-    let frame = (time.elapsed_seconds_wrapped().fract() * FRAME_RATE) as usize;
-    for (mut atlas, IdleAnimation(frame_count)) in q_effect.iter_mut() {
-        atlas.index = frame % frame_count;
+pub fn start_fade_in(
+    level: Res<Level>,
+    mut state: ResMut<FadeInState>,
+    q_children: Query<&Children>,
+    mut q_sprite: Query<&mut Sprite>,
+    mut commands: Commands,
+    mut ev_update_focus: EventWriter<UpdateFocusEvent>,
+) {
+    let Some(parent) = level.parent else {
+        return;
+    };
+    for entity in q_children.iter_descendants(parent) {
+        let Ok(mut sprite) = q_sprite.get_mut(entity) else {
+            continue;
+        };
+        commands.entity(entity).insert(FadeInAnimator {
+            alpha: sprite.color.alpha(),
+        });
+        sprite.color = sprite.color.with_alpha(0.0);
+    }
+    state.0 = Some(Duration::ZERO);
+    ev_update_focus.send(UpdateFocusEvent(Focus::Busy(None)));
+}
+
+fn animate_fade_in(
+    time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
+    mut state: ResMut<FadeInState>,
+    mut q_animator: Query<(Entity, &FadeInAnimator, &mut Sprite)>,
+    mut commands: Commands,
+    mut ev_update_focus: EventWriter<UpdateFocusEvent>,
+) {
+    let Some(played_duration) = state.0.as_mut() else {
+        return;
+    };
+    let delta = reduce_motion.delta(&time, FADE_IN_DURATION);
+    *played_duration = std::cmp::min(*played_duration + delta, FADE_IN_DURATION);
+    let progress = played_duration.as_secs_f32() / FADE_IN_DURATION.as_secs_f32();
+    let is_finished = *played_duration >= FADE_IN_DURATION;
+
+    // NOTE: Beams reset their own color, so this has to run after them, scaling whatever alpha
+    // the sprite had when the fade started.
+    for (entity, animator, mut sprite) in q_animator.iter_mut() {
+        let alpha = animator.alpha * progress.sine_in_out();
+        sprite.color = sprite.color.with_alpha(alpha);
+        if is_finished {
+            commands.entity(entity).remove::<FadeInAnimator>();
+        }
+    }
+
+    if is_finished {
+        state.0 = None;
+        ev_update_focus.send(UpdateFocusEvent(Focus::None));
+    }
+}
+
+fn animate_idle(
+    mut q_effect: Query<(&mut TextureAtlas, &IdleAnimation)>,
+    time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
+) {
+    let elapsed = time.elapsed_seconds_wrapped();
+    for (mut atlas, animation) in q_effect.iter_mut() {
+        if reduce_motion.0 {
+            atlas.index = 0;
+            continue;
+        }
+        let frame = (elapsed * animation.frame_rate) as usize;
+        atlas.index = frame % animation.frames;
     }
 }
 
 impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(AnimationStateHolder::default())
+            .init_resource::<ReduceMotion>()
+            .init_resource::<FadeInState>()
             .add_event::<StartAnimation>()
             .add_event::<AnimationFinished>()
             .configure_sets(FixedUpdate, AnimationSet.in_set(GameplaySet))
@@ -232,8 +338,12 @@ impl Plugin for AnimationPlugin {
                 FixedUpdate,
                 animate_fade_out.after(start_animation).in_set(AnimationSet),
             )
-            .add_systems(FixedUpdate, animate_idle.in_set(IdleAnimationSet));
+            .add_systems(FixedUpdate, animate_idle.in_set(IdleAnimationSet))
+            .add_systems(
+                FixedPostUpdate,
+                animate_fade_in.after(BeamSet).in_set(GameplaySet),
+            );
     }
 }
 
-const FRAME_RATE: f32 = 48.0;
+const FADE_IN_DURATION: Duration = Duration::from_millis(400);
@@ -0,0 +1,224 @@
+use std::time::Duration;
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+use bevy::window::CursorMoved;
+
+use crate::model::{next_index, LevelMetadata, Move};
+
+use super::focus::{get_focus, Focus};
+use super::gui::PlayLevel;
+use super::input::{InputSet, MoveManipulatorEvent, SelectManipulatorEvent};
+use super::level::Campaign;
+use super::{GameState, GameplaySet};
+
+pub struct AttractPlugin;
+
+// NOTE: While attract mode is active, the game keeps playing random campaign levels through
+// their solutions. The next level is solved while the current one plays, so it can start as
+// soon as the current one is over.
+#[derive(Resource)]
+pub struct AttractMode {
+    pub enabled: bool,
+    pub idle_delay: Duration,
+    active: bool,
+    idle: Duration,
+    solution: Vec<Move>,
+    step_idx: usize,
+    pending: Option<Task<Option<AttractLevel>>>,
+    ready: Option<AttractLevel>,
+}
+
+struct AttractLevel {
+    level_idx: usize,
+    solution: Vec<Move>,
+}
+
+impl Default for AttractMode {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            idle_delay: DEFAULT_IDLE_DELAY,
+            active: false,
+            idle: Duration::ZERO,
+            solution: Vec::new(),
+            step_idx: 0,
+            pending: None,
+            ready: None,
+        }
+    }
+}
+
+impl AttractMode {
+    fn stop(&mut self) {
+        self.active = false;
+        self.idle = Duration::ZERO;
+        self.solution.clear();
+        self.pending = None;
+        self.ready = None;
+    }
+}
+
+pub fn attract_mode_active(attract: Res<AttractMode>) -> bool {
+    attract.active
+}
+
+fn wait_for_idle(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut ev_cursor: EventReader<CursorMoved>,
+    mut attract: ResMut<AttractMode>,
+) {
+    let moved = ev_cursor.read().count() > 0;
+    let pressed = keys.get_pressed().next().is_some() || mouse.get_pressed().next().is_some();
+    if !attract.enabled || moved || pressed {
+        attract.idle = Duration::ZERO;
+        return;
+    }
+    if attract.active {
+        return;
+    }
+    attract.idle += time.delta();
+    if attract.idle >= attract.idle_delay {
+        attract.active = true;
+    }
+}
+
+fn stop_attract_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    state: Res<State<GameState>>,
+    mut attract: ResMut<AttractMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let pressed =
+        keys.get_just_pressed().next().is_some() || mouse.get_just_pressed().next().is_some();
+    if !pressed {
+        return;
+    }
+    attract.stop();
+    if *state.get() != GameState::MainMenu {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn solve_attract_level(
+    campaign: Res<Campaign>,
+    time: Res<Time>,
+    mut attract: ResMut<AttractMode>,
+    mut seed: Local<u64>,
+) {
+    if let Some(task) = attract.pending.as_mut() {
+        if !task.is_finished() {
+            return;
+        }
+        let task = attract.pending.take().unwrap();
+        attract.ready = block_on(task);
+    }
+    if attract.ready.is_some() || campaign.levels.is_empty() {
+        return;
+    }
+
+    // NOTE: The solver gives up on some of the larger levels, in which case another one is picked
+    if *seed == 0 {
+        *seed = time.elapsed().as_nanos() as u64 | 1;
+    }
+    let level_idx = next_index(&mut seed, campaign.levels.len());
+    let board = campaign.levels[level_idx].board.clone();
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let solution = board.solve()?;
+        Some(AttractLevel {
+            level_idx,
+            solution,
+        })
+    });
+    attract.pending = Some(task);
+}
+
+fn start_attract_level(
+    campaign: Res<Campaign>,
+    mut attract: ResMut<AttractMode>,
+    mut ev_play: EventWriter<PlayLevel>,
+) {
+    let Some(next) = attract.ready.take() else {
+        return;
+    };
+    let level = &campaign.levels[next.level_idx];
+    // NOTE: Without an id, the demo doesn't show the briefing or record any progress
+    let metadata = LevelMetadata {
+        name: Some(level.name.clone()),
+        goals: level.goals.clone(),
+        ..Default::default()
+    };
+    attract.solution = next.solution;
+    attract.step_idx = 0;
+    ev_play.send(PlayLevel(level.board.clone(), metadata));
+}
+
+fn play_attract_moves(
+    In(focus): In<Focus>,
+    time: Res<Time>,
+    mut attract: ResMut<AttractMode>,
+    mut delay: Local<Duration>,
+    mut ev_select_manipulator: EventWriter<SelectManipulatorEvent>,
+    mut ev_move_manipulator: EventWriter<MoveManipulatorEvent>,
+) {
+    if let Focus::Busy(_) = focus {
+        *delay = Duration::ZERO;
+        return;
+    }
+    *delay += time.delta();
+    if *delay < MOVE_DELAY {
+        return;
+    }
+    let Some(&step) = attract.solution.get(attract.step_idx) else {
+        return;
+    };
+    *delay = Duration::ZERO;
+    match focus {
+        Focus::Selected(coords, _) if coords == step.coords => {
+            ev_move_manipulator.send(MoveManipulatorEvent(step.direction));
+            attract.step_idx += 1;
+        }
+        _ => {
+            ev_select_manipulator.send(SelectManipulatorEvent::AtCoords(step.coords));
+        }
+    }
+}
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AttractMode>()
+            .add_systems(
+                PreUpdate,
+                stop_attract_mode
+                    .after(InputSystem)
+                    .run_if(attract_mode_active),
+            )
+            .add_systems(Update, wait_for_idle.run_if(in_state(GameState::MainMenu)))
+            .add_systems(
+                Update,
+                (
+                    solve_attract_level,
+                    start_attract_level.run_if(
+                        in_state(GameState::MainMenu).or_else(in_state(GameState::GameOver)),
+                    ),
+                )
+                    .chain()
+                    .run_if(attract_mode_active),
+            )
+            .add_systems(
+                FixedPreUpdate,
+                get_focus
+                    .pipe(play_attract_moves)
+                    .in_set(GameplaySet)
+                    .after(InputSet)
+                    .run_if(attract_mode_active),
+            );
+    }
+}
+
+const DEFAULT_IDLE_DELAY: Duration = Duration::from_secs(30);
+const MOVE_DELAY: Duration = Duration::from_millis(300);
@@ -1,11 +1,12 @@
-use std::sync::Arc;
+use std::time::Duration;
 
+use bevy::audio::Volume;
 use bevy::prelude::*;
 use enum_map::{Enum, EnumMap};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-use super::GameAssets;
+use super::{GameAssets, LoadBarrier};
 
 pub struct AudioPlugin;
 
@@ -32,16 +33,18 @@ pub enum PlayTune {
 }
 
 #[derive(Component)]
-struct TuneHolder(Option<PlayTune>);
+struct TuneHolder(PlayTune);
 
-#[derive(Bundle)]
-struct TuneHolderBundle {
-    holder: TuneHolder,
-    settings: PlaybackSettings,
+// NOTE: A tune that is being replaced loses its holder and fades out to silence, at which point
+// it is despawned, while the replacement fades in.
+#[derive(Component)]
+struct TuneFade {
+    volume: f32,
+    target: f32,
 }
 
 impl AudioAssets {
-    pub fn load(server: &AssetServer, barrier: &Arc<()>) -> Self {
+    pub fn load(server: &AssetServer, barrier: &LoadBarrier) -> Self {
         let mut sfx = EnumMap::default();
         for effect in PlaySfx::iter() {
             let suffix = match effect {
@@ -52,7 +55,7 @@ impl AudioAssets {
                 PlaySfx::Lose => "lose",
             };
             let path = format!("sfx-{}.ogg", suffix);
-            sfx[effect] = server.load_acquire(path, Arc::clone(&barrier));
+            sfx[effect] = barrier.load(server, path);
         }
 
         let mut tunes = EnumMap::default();
@@ -64,20 +67,13 @@ impl AudioAssets {
                 PlayTune::Hard => "hard",
             };
             let path = format!("tune-{}.ogg", suffix);
-            tunes[tune] = server.load_acquire(path, Arc::clone(&barrier));
+            tunes[tune] = barrier.load(server, path);
         }
 
         Self { sfx, tunes }
     }
 }
 
-fn spawn_tune_holder(mut commands: Commands) {
-    commands.spawn(TuneHolderBundle {
-        holder: TuneHolder(None),
-        settings: PlaybackSettings::LOOP,
-    });
-}
-
 fn play_sfx(mut ev_sfx: EventReader<PlaySfx>, assets: Res<GameAssets>, mut commands: Commands) {
     for &effect in ev_sfx.read() {
         commands.spawn(AudioBundle {
@@ -90,7 +86,7 @@ fn play_sfx(mut ev_sfx: EventReader<PlaySfx>, assets: Res<GameAssets>, mut comma
 
 fn play_tune(
     mut ev_tune: EventReader<PlayTune>,
-    mut q_holder: Query<(Entity, &mut TuneHolder)>,
+    mut q_holder: Query<(Entity, &TuneHolder, Option<&mut TuneFade>)>,
     assets: Res<GameAssets>,
     mut commands: Commands,
 ) {
@@ -98,24 +94,73 @@ fn play_tune(
         return;
     };
 
-    let (entity, mut holder) = q_holder.single_mut();
-    if holder.0 == Some(tune) {
-        return;
+    if let Ok((entity, holder, fade)) = q_holder.get_single_mut() {
+        if holder.0 == tune {
+            return;
+        }
+        commands.entity(entity).remove::<TuneHolder>();
+        match fade {
+            Some(mut fade) => fade.target = 0.0,
+            None => {
+                commands.entity(entity).insert(TuneFade {
+                    volume: 1.0,
+                    target: 0.0,
+                });
+            }
+        }
+    }
+    commands.spawn((
+        TuneHolder(tune),
+        TuneFade {
+            volume: 0.0,
+            target: 1.0,
+        },
+        AudioBundle {
+            source: assets.audio.tunes[tune].clone(),
+            settings: PlaybackSettings::LOOP.with_volume(Volume::new(0.0)),
+            ..Default::default()
+        },
+    ));
+}
+
+fn fade_tunes(
+    time: Res<Time>,
+    mut q_fade: Query<(Entity, &mut TuneFade, Option<&AudioSink>)>,
+    mut commands: Commands,
+) {
+    let step = time.delta_seconds() / TUNE_CROSSFADE.as_secs_f32();
+    for (entity, mut fade, sink) in q_fade.iter_mut() {
+        // NOTE: The sink only shows up once playback starts, so the fade waits for it
+        let Some(sink) = sink else {
+            if fade.target == 0.0 {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        };
+        fade.volume = match fade.volume < fade.target {
+            true => (fade.volume + step).min(fade.target),
+            false => (fade.volume - step).max(fade.target),
+        };
+        sink.set_volume(fade.volume);
+        if fade.volume != fade.target {
+            continue;
+        }
+        match fade.target == 0.0 {
+            true => commands.entity(entity).despawn(),
+            false => {
+                commands.entity(entity).remove::<TuneFade>();
+            }
+        }
     }
-    holder.0 = Some(tune);
-    commands
-        .entity(entity)
-        .remove::<AudioSink>()
-        .remove::<Handle<AudioSource>>()
-        .insert(assets.audio.tunes[tune].clone());
 }
 
 impl Plugin for AudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<PlaySfx>()
             .add_event::<PlayTune>()
-            .add_systems(Startup, spawn_tune_holder)
             .add_systems(PostUpdate, play_sfx)
-            .add_systems(PostUpdate, play_tune);
+            .add_systems(PostUpdate, (play_tune, fade_tunes).chain());
     }
 }
+
+const TUNE_CROSSFADE: Duration = Duration::from_secs(1);
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::collections::HashSet;
 use std::time::Duration;
 
 use bevy::app::{FixedPostUpdate, FixedUpdate, Plugin};
@@ -20,15 +20,16 @@ use interpolation::{Ease, Lerp};
 use strum::IntoEnumIterator;
 
 use crate::model::{
-    BeamTarget, BeamTargetKind, Board, BoardCoords, Direction, Emitters, GridSet, Orientation,
-    Piece, Tile, TileKind,
+    BeamTarget, BeamTargetKind, Board, BoardCoords, Direction, Emitters, GridMap, GridSet,
+    Orientation,
 };
 
-use super::animation::{AnimatedSpriteBundle, FadeOutAnimator};
-use super::border::{BORDER_OFFSET_X, BORDER_OFFSET_Y};
+use super::animation::{AnimatedSpriteBundle, FadeOutAnimator, ReduceMotion};
+use super::border::{BorderGlow, BORDER_OFFSET_X, BORDER_OFFSET_Y};
 use super::level::Level;
 use super::{
-    BoardCoordsHolder, GameplaySet, Mutable, SpriteSheet, MOVE_DURATION, TILE_HEIGHT, TILE_WIDTH,
+    BoardCoordsHolder, EngineCoords, GameplaySet, LoadBarrier, Mutable, SpriteSheet,
+    IDLE_FRAME_RATE, MOVE_DURATION, TILE_HEIGHT, TILE_WIDTH,
 };
 
 pub struct BeamPlugin;
@@ -40,6 +41,11 @@ pub struct BeamAssets {
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BeamSet;
 
+// NOTE: A debugging aid that shows the normally hidden future beams, faded out, next to the
+// present ones. While a move is in progress, they show where the beams end up once it's done.
+#[derive(Resource, Debug, Default)]
+pub struct FutureBeamPreview(pub bool);
+
 #[derive(Component, Debug)]
 pub struct Beam {
     direction: Direction,
@@ -93,6 +99,29 @@ pub struct ResetBeams;
 #[derive(Component)]
 pub struct Halo;
 
+// NOTE: Every cell has an overlay for each orientation, shown where more than one beam crosses the
+// cell, so that overlapping beams look brighter than a single one.
+#[derive(Component, Debug)]
+struct BeamOverlap(Orientation);
+
+#[derive(Bundle)]
+struct BeamOverlapBundle {
+    overlap: BeamOverlap,
+    coords: BoardCoordsHolder,
+    sprite: SpriteBundle,
+}
+
+#[derive(Component, Debug)]
+struct BeamEndpoint(Direction);
+
+#[derive(Bundle)]
+struct BeamEndpointBundle {
+    endpoint: BeamEndpoint,
+    coords: BoardCoordsHolder,
+    sprite: SpriteBundle,
+    fader: FadeOutAnimator,
+}
+
 #[derive(Bundle)]
 pub struct HaloBundle {
     halo: Halo,
@@ -102,15 +131,15 @@ pub struct HaloBundle {
 }
 
 impl BeamAssets {
-    pub fn load(server: &AssetServer, barrier: &Arc<()>) -> Self {
+    pub fn load(server: &AssetServer, barrier: &LoadBarrier) -> Self {
         let mut sheets = EnumMap::default();
         for orientation in Orientation::iter() {
             let (path, size) = match orientation {
                 Orientation::Horizontal => ("beam-horz.png", UVec2::new(1, 8)),
                 Orientation::Vertical => ("beam-vert.png", UVec2::new(8, 1)),
             };
-            let texture = server.load_acquire(path, Arc::clone(&barrier));
-            sheets[orientation] = SpriteSheet::new(texture, size, 48, server);
+            let texture = barrier.load(server, path);
+            sheets[orientation] = SpriteSheet::new(texture, size, 48, IDLE_FRAME_RATE, server);
         }
         Self { sheets }
     }
@@ -142,7 +171,7 @@ impl BeamBundle {
                 scale: beam_scale(origin, direction, target).extend(1.0),
                 ..Default::default()
             },
-            visibility: group.visibility(),
+            visibility: group.visibility(false),
             ..Default::default()
         };
 
@@ -159,11 +188,64 @@ impl BeamBundle {
     }
 }
 
+impl BeamEndpointBundle {
+    fn new(origin: BoardCoords, direction: Direction, target: BeamTarget) -> Self {
+        let size = match direction.orientation() {
+            Orientation::Vertical => ENDPOINT_SIZE,
+            Orientation::Horizontal => Vec2::new(ENDPOINT_SIZE.y, ENDPOINT_SIZE.x),
+        };
+        Self {
+            endpoint: BeamEndpoint(direction),
+            coords: BoardCoordsHolder(origin),
+            sprite: SpriteBundle {
+                sprite: Sprite {
+                    color: beam_color(1.0),
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform {
+                    translation: endpoint_offset(origin, direction, target).extend(REL_Z_LAYER),
+                    ..Default::default()
+                },
+                visibility: endpoint_visibility(target),
+                ..Default::default()
+            },
+            fader: FadeOutAnimator::default(),
+        }
+    }
+}
+
+impl BeamOverlapBundle {
+    fn new(coords: BoardCoords, orientation: Orientation) -> Self {
+        let size = match orientation {
+            Orientation::Horizontal => Vec2::new(TILE_WIDTH, OVERLAP_THICKNESS),
+            Orientation::Vertical => Vec2::new(OVERLAP_THICKNESS, TILE_HEIGHT),
+        };
+        Self {
+            overlap: BeamOverlap(orientation),
+            coords: BoardCoordsHolder(coords),
+            sprite: SpriteBundle {
+                sprite: Sprite {
+                    color: beam_color(0.0),
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform {
+                    translation: coords.to_xy().extend(OVERLAP_Z_LAYER),
+                    ..Default::default()
+                },
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        }
+    }
+}
+
 impl BeamGroup {
-    fn visibility(self) -> Visibility {
-        match self {
-            Self::Present => Visibility::Inherited,
-            Self::Future => Visibility::Hidden,
+    fn visibility(self, preview: bool) -> Visibility {
+        match (self, preview) {
+            (Self::Present, _) | (Self::Future, true) => Visibility::Inherited,
+            (Self::Future, false) => Visibility::Hidden,
         }
     }
 
@@ -173,6 +255,13 @@ impl BeamGroup {
             Self::Future => 0.0,
         }
     }
+
+    fn resting_alpha(self, preview: bool) -> f32 {
+        match (self, preview) {
+            (Self::Future, true) => FUTURE_PREVIEW_ALPHA,
+            _ => self.alpha(),
+        }
+    }
 }
 
 impl BeamAnimator {
@@ -228,6 +317,20 @@ pub fn spawn_beams(
         assets,
         mutator,
     );
+
+    let manipulator = board.pieces.get(origin).unwrap().as_manipulator().unwrap();
+    for direction in emitters.directions() {
+        let target = manipulator.target(direction).unwrap();
+        anchor
+            .spawn(BeamEndpointBundle::new(origin, direction, target))
+            .mutate(mutator);
+    }
+}
+
+pub fn spawn_beam_overlaps(parent: &mut ChildBuilder, coords: BoardCoords) {
+    for orientation in Orientation::iter() {
+        parent.spawn(BeamOverlapBundle::new(coords, orientation));
+    }
 }
 
 fn spawn_beam_group(
@@ -251,6 +354,7 @@ fn spawn_beam_group(
 fn move_beams(
     mut events: EventReader<MoveBeams>,
     level: Res<Level>,
+    preview: Res<FutureBeamPreview>,
     q_children: Query<&Children>,
     mut q_beam: Query<(
         &Beam,
@@ -259,6 +363,7 @@ fn move_beams(
         &mut Sprite,
         &mut BeamAnimator,
     )>,
+    mut q_endpoint: Query<&mut Visibility, (With<BeamEndpoint>, Without<Beam>)>,
 ) {
     enum BeamChange {
         None,
@@ -269,10 +374,10 @@ fn move_beams(
     let Some(event) = events.read().last() else {
         return;
     };
-    for (coords, piece) in level.present.pieces.iter() {
-        let Piece::Manipulator(_) = piece else {
-            continue;
-        };
+    for mut visibility in q_endpoint.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+    for (coords, _) in level.present.manipulators() {
         let anchor = *level.pieces.get(coords).unwrap();
         let future_origin = match event.move_set.contains(coords) {
             false => coords,
@@ -306,14 +411,16 @@ fn move_beams(
 
             match beam_change {
                 BeamChange::None => (),
-                BeamChange::Resize => {
-                    if let BeamGroup::Present = beam.group {
+                BeamChange::Resize => match beam.group {
+                    BeamGroup::Present => {
                         animator.start_animation(BeamAnimation::Resize {
                             start: present_scale,
                             end: future_scale,
                         });
                     }
-                }
+                    BeamGroup::Future if preview.0 => xform.scale = future_scale.extend(1.0),
+                    BeamGroup::Future => (),
+                },
                 BeamChange::Crossfade => {
                     let present_len = xform.scale.truncate().length_squared();
                     let future_len = future_scale.length_squared();
@@ -339,19 +446,20 @@ fn move_beams(
 
 fn animate_beams(
     time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
     mut q_beam: Query<(&mut BeamAnimator, &mut Transform, &mut Sprite)>,
 ) {
     for (mut animator, mut xform, mut sprite) in q_beam.iter_mut() {
         if let BeamAnimation::None = animator.animation {
             continue;
         }
-        animator.played_duration += time.delta();
-        let finished = animator.played_duration >= animator.total_duration;
+        let total = animator.total_duration;
+        animator.played_duration += reduce_motion.delta(&time, total);
+        let finished = animator.played_duration >= total;
         if finished {
-            animator.played_duration = animator.total_duration;
+            animator.played_duration = total;
         }
-        let progress =
-            animator.played_duration.as_secs_f32() / animator.total_duration.as_secs_f32();
+        let progress = animator.played_duration.as_secs_f32() / total.as_secs_f32();
         match &animator.animation {
             BeamAnimation::None => unreachable!(),
             BeamAnimation::Resize { start, end } => {
@@ -372,6 +480,7 @@ fn animate_beams(
 fn reset_beams(
     mut events: EventReader<ResetBeams>,
     level: Res<Level>,
+    preview: Res<FutureBeamPreview>,
     mut q_beam: Query<
         (
             &Beam,
@@ -383,6 +492,15 @@ fn reset_beams(
         Without<Halo>,
     >,
     mut q_halo: Query<(&BoardCoordsHolder, &mut Visibility), With<Halo>>,
+    mut q_endpoint: Query<
+        (
+            &BeamEndpoint,
+            &BoardCoordsHolder,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        (Without<Beam>, Without<Halo>),
+    >,
 ) {
     if events.is_empty() {
         return;
@@ -403,25 +521,13 @@ fn reset_beams(
             .target(beam.direction)
             .unwrap();
 
-        if target.kind == BeamTargetKind::Piece {
-            let mut has_halo = true;
-            if let Some(Piece::Particle(_)) = level.present.pieces.get(target.coords) {
-                if let Some(Tile {
-                    kind: TileKind::Collector,
-                    ..
-                }) = level.present.tiles.get(target.coords)
-                {
-                    has_halo = false;
-                }
-            }
-            if has_halo {
-                halos.insert(target.coords);
-            }
+        if (target.kind == BeamTargetKind::Piece) && !level.present.is_collected(target.coords) {
+            halos.insert(target.coords);
         }
 
         xform.scale = beam_scale(origin, beam.direction, target).extend(1.0);
-        *visibility = beam.group.visibility();
-        sprite.color = beam_color(beam.group.alpha());
+        *visibility = beam.group.visibility(preview.0);
+        sprite.color = beam_color(beam.group.resting_alpha(preview.0));
     }
 
     for (coords, mut visibility) in q_halo.iter_mut() {
@@ -430,6 +536,124 @@ fn reset_beams(
             true => Visibility::Inherited,
         }
     }
+
+    for (endpoint, coords, mut xform, mut visibility) in q_endpoint.iter_mut() {
+        let origin = coords.0;
+        let target = level
+            .present
+            .pieces
+            .get(origin)
+            .unwrap()
+            .as_manipulator()
+            .unwrap()
+            .target(endpoint.0)
+            .unwrap();
+        xform.translation = endpoint_offset(origin, endpoint.0, target).extend(REL_Z_LAYER);
+        *visibility = endpoint_visibility(target);
+    }
+}
+
+// NOTE: Glows are cleared as soon as the beams start moving, and relit where the beams end up
+// once they're reset. Several beams can end at the same border, which then glows just once.
+fn glow_borders(
+    mut ev_move: EventReader<MoveBeams>,
+    mut ev_reset: EventReader<ResetBeams>,
+    level: Res<Level>,
+    mut q_glow: Query<(&Parent, &mut Visibility), With<BorderGlow>>,
+) {
+    if ev_move.read().count() > 0 {
+        for (_, mut visibility) in q_glow.iter_mut() {
+            *visibility = Visibility::Hidden;
+        }
+    }
+    if ev_reset.read().count() == 0 {
+        return;
+    }
+
+    let mut glowing = HashSet::new();
+    for (_, manipulator) in level.present.manipulators() {
+        for direction in manipulator.emitters.directions() {
+            let Some(target) = manipulator.target(direction) else {
+                continue;
+            };
+            if target.kind != BeamTargetKind::Border {
+                continue;
+            }
+            let borders = match direction.orientation() {
+                Orientation::Vertical => &level.horz_borders,
+                Orientation::Horizontal => &level.vert_borders,
+            };
+            if let Some(&border) = borders.get(target.coords) {
+                glowing.insert(border);
+            }
+        }
+    }
+
+    for (parent, mut visibility) in q_glow.iter_mut() {
+        *visibility = match glowing.contains(&parent.get()) {
+            false => Visibility::Hidden,
+            true => Visibility::Inherited,
+        };
+    }
+}
+
+fn brighten_beam_overlaps(
+    mut ev_move: EventReader<MoveBeams>,
+    mut ev_reset: EventReader<ResetBeams>,
+    level: Res<Level>,
+    mut q_overlap: Query<(
+        &BeamOverlap,
+        &BoardCoordsHolder,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+) {
+    if ev_move.read().count() > 0 {
+        for (_, _, _, mut visibility) in q_overlap.iter_mut() {
+            *visibility = Visibility::Hidden;
+        }
+    }
+    if ev_reset.read().count() == 0 {
+        return;
+    }
+
+    let coverage: EnumMap<Orientation, GridMap<u8>> =
+        EnumMap::from_fn(|orientation| level.present.beam_coverage(orientation));
+    let count = |orientation: Orientation, coords: BoardCoords| {
+        coverage[orientation]
+            .get(coords)
+            .copied()
+            .unwrap_or_default()
+    };
+    for (overlap, coords, mut sprite, mut visibility) in q_overlap.iter_mut() {
+        let total: u8 = Orientation::iter()
+            .map(|orientation| count(orientation, coords.0))
+            .sum();
+        if (count(overlap.0, coords.0) == 0) || (total < 2) {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        let alpha = (OVERLAP_ALPHA_STEP * (total - 1) as f32).min(MAX_OVERLAP_ALPHA);
+        sprite.color = beam_color(alpha);
+        *visibility = Visibility::Inherited;
+    }
+}
+
+// NOTE: Beams that are in the middle of an animation are left alone, since the reset at the end
+// of the move brings them in line with the preview anyway.
+fn apply_future_beam_preview(
+    preview: Res<FutureBeamPreview>,
+    mut q_beam: Query<(&Beam, &BeamAnimator, &mut Visibility, &mut Sprite)>,
+) {
+    for (beam, animator, mut visibility, mut sprite) in q_beam.iter_mut() {
+        if beam.group != BeamGroup::Future {
+            continue;
+        }
+        if let BeamAnimation::None = animator.animation {
+            *visibility = beam.group.visibility(preview.0);
+            sprite.color = beam_color(beam.group.resting_alpha(preview.0));
+        }
+    }
 }
 
 fn beam_scale(origin: BoardCoords, direction: Direction, target: BeamTarget) -> Vec2 {
@@ -453,13 +677,31 @@ fn beam_scale(origin: BoardCoords, direction: Direction, target: BeamTarget) ->
     }
 }
 
+fn endpoint_offset(origin: BoardCoords, direction: Direction, target: BeamTarget) -> Vec2 {
+    let scale = beam_scale(origin, direction, target);
+    match direction {
+        Direction::Up => Vec2::new(0.0, scale.y),
+        Direction::Left => Vec2::new(-scale.x, 0.0),
+        Direction::Down => Vec2::new(0.0, -scale.y),
+        Direction::Right => Vec2::new(scale.x, 0.0),
+    }
+}
+
+fn endpoint_visibility(target: BeamTarget) -> Visibility {
+    match target.kind {
+        BeamTargetKind::Piece => Visibility::Hidden,
+        BeamTargetKind::Border => Visibility::Inherited,
+    }
+}
+
 fn beam_color(alpha: f32) -> Color {
     Color::WHITE.with_alpha(alpha)
 }
 
 impl Plugin for BeamPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_event::<MoveBeams>()
+        app.init_resource::<FutureBeamPreview>()
+            .add_event::<MoveBeams>()
             .add_event::<ResetBeams>()
             .configure_sets(FixedUpdate, BeamSet.in_set(GameplaySet))
             .configure_sets(FixedPostUpdate, BeamSet.in_set(GameplaySet))
@@ -467,8 +709,23 @@ impl Plugin for BeamPlugin {
                 FixedUpdate,
                 (move_beams, animate_beams).chain().in_set(BeamSet),
             )
-            .add_systems(FixedPostUpdate, reset_beams.in_set(BeamSet));
+            .add_systems(
+                FixedPostUpdate,
+                (reset_beams, glow_borders, brighten_beam_overlaps)
+                    .chain()
+                    .in_set(BeamSet),
+            )
+            .add_systems(
+                Update,
+                apply_future_beam_preview.run_if(resource_changed::<FutureBeamPreview>),
+            );
     }
 }
 
 const REL_Z_LAYER: f32 = -1.0;
+const ENDPOINT_SIZE: Vec2 = Vec2::new(12.0, 3.0);
+const FUTURE_PREVIEW_ALPHA: f32 = 0.35;
+const OVERLAP_THICKNESS: f32 = 4.0;
+const OVERLAP_Z_LAYER: f32 = 1.5;
+const OVERLAP_ALPHA_STEP: f32 = 0.3;
+const MAX_OVERLAP_ALPHA: f32 = 0.8;
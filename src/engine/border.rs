@@ -1,20 +1,22 @@
 use std::collections::HashMap;
-use std::sync::Arc;
 
 use bevy::asset::{AssetServer, Handle};
+use bevy::color::Color;
 use bevy::ecs::bundle::Bundle;
+use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
 use bevy::ecs::system::EntityCommands;
-use bevy::hierarchy::ChildBuilder;
+use bevy::hierarchy::{BuildChildren, ChildBuilder};
 use bevy::math::{Quat, Vec2};
 use bevy::render::texture::Image;
-use bevy::sprite::SpriteBundle;
+use bevy::render::view::Visibility;
+use bevy::sprite::{Sprite, SpriteBundle};
 use bevy::transform::components::Transform;
 use strum::IntoEnumIterator;
 
 use crate::model::{BoardCoords, Border, Orientation};
 
-use super::{BoardCoordsHolder, EngineCoords, Mutable};
+use super::{BoardCoordsHolder, EngineCoords, LoadBarrier, Mutable};
 
 pub struct BorderAssets {
     textures: HashMap<Border, Handle<Image>>,
@@ -26,8 +28,19 @@ struct BorderBundle {
     sprite: SpriteBundle,
 }
 
+// NOTE: Shown where a beam ends at the border. It's a child of the border, so it takes on the
+// border's orientation.
+#[derive(Component)]
+pub struct BorderGlow;
+
+#[derive(Bundle)]
+struct BorderGlowBundle {
+    glow: BorderGlow,
+    sprite: SpriteBundle,
+}
+
 impl Orientation {
-    fn offset(self) -> Vec2 {
+    pub(super) fn offset(self) -> Vec2 {
         match self {
             Self::Horizontal => Vec2::new(0.0, -BORDER_OFFSET_Y),
             Self::Vertical => Vec2::new(BORDER_OFFSET_X, 0.0),
@@ -43,14 +56,14 @@ impl Orientation {
 }
 
 impl BorderAssets {
-    pub fn load(server: &AssetServer, barrier: &Arc<()>) -> Self {
+    pub fn load(server: &AssetServer, barrier: &LoadBarrier) -> Self {
         let mut textures = HashMap::new();
         for kind in Border::iter() {
             let path = match kind {
                 Border::Wall => "wall.png",
                 Border::Window => "window.png",
             };
-            textures.insert(kind, server.load_acquire(path, Arc::clone(&barrier)));
+            textures.insert(kind, barrier.load(server, path));
         }
         Self { textures }
     }
@@ -80,42 +93,106 @@ impl BorderBundle {
     }
 }
 
-pub fn spawn_horz_border(
+impl BorderGlowBundle {
+    fn new() -> Self {
+        Self {
+            glow: BorderGlow,
+            sprite: SpriteBundle {
+                sprite: Sprite {
+                    color: GLOW_COLOR,
+                    custom_size: Some(GLOW_SIZE),
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, GLOW_REL_Z_LAYER),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+pub fn spawn_corner_border(
     parent: &mut ChildBuilder,
     border: &Border,
     coords: BoardCoords,
     assets: &BorderAssets,
     mutator: &impl Fn(&mut EntityCommands),
 ) -> Entity {
+    let texture = assets.textures[border].clone();
+    let coords = BoardCoordsHolder(coords);
+    // NOTE: The corner is the top left corner of the cell with the same coordinates
+    let offset = Vec2::new(-BORDER_OFFSET_X, BORDER_OFFSET_Y);
     parent
-        .spawn(BorderBundle::new(
-            border,
+        .spawn(BorderBundle {
             coords,
-            Orientation::Horizontal,
-            assets,
-        ))
+            sprite: SpriteBundle {
+                texture,
+                transform: Transform {
+                    translation: (coords.to_xy() + offset).extend(Z_LAYER),
+                    rotation: Quat::from_rotation_z(f32::to_radians(45.0)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        })
         .mutate(mutator)
         .id()
 }
 
+pub fn spawn_horz_border(
+    parent: &mut ChildBuilder,
+    border: &Border,
+    coords: BoardCoords,
+    assets: &BorderAssets,
+    mutator: &impl Fn(&mut EntityCommands),
+) -> Entity {
+    spawn_edge_border(
+        parent,
+        border,
+        coords,
+        Orientation::Horizontal,
+        assets,
+        mutator,
+    )
+}
+
 pub fn spawn_vert_border(
     parent: &mut ChildBuilder,
     border: &Border,
     coords: BoardCoords,
     assets: &BorderAssets,
     mutator: &impl Fn(&mut EntityCommands),
+) -> Entity {
+    spawn_edge_border(
+        parent,
+        border,
+        coords,
+        Orientation::Vertical,
+        assets,
+        mutator,
+    )
+}
+
+fn spawn_edge_border(
+    parent: &mut ChildBuilder,
+    border: &Border,
+    coords: BoardCoords,
+    orientation: Orientation,
+    assets: &BorderAssets,
+    mutator: &impl Fn(&mut EntityCommands),
 ) -> Entity {
     parent
-        .spawn(BorderBundle::new(
-            border,
-            coords,
-            Orientation::Vertical,
-            assets,
-        ))
+        .spawn(BorderBundle::new(border, coords, orientation, assets))
         .mutate(mutator)
+        .with_children(|border| {
+            border.spawn(BorderGlowBundle::new()).mutate(mutator);
+        })
         .id()
 }
 
 pub const BORDER_OFFSET_X: f32 = 22.0;
 pub const BORDER_OFFSET_Y: f32 = 22.0;
 const Z_LAYER: f32 = 2.0;
+const GLOW_REL_Z_LAYER: f32 = 0.5;
+const GLOW_SIZE: Vec2 = Vec2::new(6.0, 16.0);
+const GLOW_COLOR: Color = Color::srgba(1.0, 0.95, 0.8, 0.8);
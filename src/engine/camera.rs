@@ -0,0 +1,115 @@
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use super::gui::IN_GAME_PANEL_WIDTH;
+use super::level::{board_size, Level};
+use super::{InLevel, MainCamera, PLAY_AREA_SIZE};
+
+pub struct CameraPlugin;
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Deref)]
+pub struct PlayArea(pub Vec2);
+
+// NOTE: Pans the camera to the given position, relative to the center of the board
+#[derive(Event, Debug)]
+pub struct CenterCamera(pub Vec2);
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    #[default]
+    Fit,
+    Pan,
+}
+
+impl Default for PlayArea {
+    fn default() -> Self {
+        Self(PLAY_AREA_SIZE)
+    }
+}
+
+fn update_play_area(
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut play_area: ResMut<PlayArea>,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+    // NOTE: The window has a minimum size, but it can still report a zero size while minimized
+    let size = Vec2::new(window.width() - IN_GAME_PANEL_WIDTH as f32, window.height());
+    play_area.set_if_neq(PlayArea(size.max(PLAY_AREA_SIZE)));
+}
+
+fn update_camera(
+    level: Res<Level>,
+    play_area: Res<PlayArea>,
+    mode: Res<CameraMode>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut ev_motion: EventReader<MouseMotion>,
+    mut ev_center: EventReader<CenterCamera>,
+    mut last_parent: Local<Option<Entity>>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    let (mut xform, mut projection) = q_camera.single_mut();
+    let board_size = board_size(&level.present);
+
+    if (level.parent != *last_parent) || mode.is_changed() || play_area.is_changed() {
+        *last_parent = level.parent;
+        ev_motion.clear();
+        let scale = match *mode {
+            CameraMode::Fit => (board_size / play_area.0).max_element().max(1.0),
+            CameraMode::Pan => 1.0,
+        };
+        // NOTE: The viewport origin is the top left corner, so the camera has to be shifted to
+        // keep the play area centered on the board when zoomed out.
+        let offset = play_area.0 * (1.0 - scale) / 2.0;
+        projection.scale = scale;
+        xform.translation = Vec3::new(offset.x, -offset.y, xform.translation.z);
+        return;
+    }
+
+    let max_offset = (board_size - play_area.0).max(Vec2::ZERO) / 2.0;
+    let center = ev_center
+        .read()
+        .last()
+        .map(|&CenterCamera(position)| position);
+    if let (CameraMode::Pan, Some(position)) = (*mode, center) {
+        ev_motion.clear();
+        let position = position.clamp(-max_offset, max_offset);
+        xform.translation = position.extend(xform.translation.z);
+        return;
+    }
+
+    if (*mode != CameraMode::Pan) || !mouse_input.pressed(MouseButton::Middle) {
+        ev_motion.clear();
+        return;
+    }
+
+    let delta: Vec2 = ev_motion.read().map(|motion| motion.delta).sum();
+    let position = xform.translation.truncate() + Vec2::new(-delta.x, delta.y);
+    let position = position.clamp(-max_offset, max_offset);
+    xform.translation = position.extend(xform.translation.z);
+}
+
+fn reset_camera(
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    let (mut xform, mut projection) = q_camera.single_mut();
+    projection.scale = 1.0;
+    xform.translation = Vec3::new(0.0, 0.0, xform.translation.z);
+}
+
+pub fn board_overflows(level: &Level, play_area: &PlayArea) -> bool {
+    board_size(&level.present).cmpgt(play_area.0).any()
+}
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CenterCamera>()
+            .init_resource::<CameraMode>()
+            .init_resource::<PlayArea>()
+            .add_systems(PreUpdate, update_play_area)
+            .add_systems(Update, update_camera.run_if(in_state(InLevel)))
+            .add_systems(OnExit(InLevel), reset_camera);
+    }
+}
@@ -0,0 +1,250 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::model::{BeamTargetKind, Move, Orientation};
+
+use super::beam::FutureBeamPreview;
+use super::focus::{get_focus, Focus};
+use super::input::{InputSet, MoveManipulatorEvent, SelectManipulatorEvent};
+use super::level::Level;
+use super::{EngineCoords, EngineDirection, GameplaySet, InLevel};
+
+pub struct DebugOverlayPlugin;
+
+pub struct SolveStepPlugin;
+
+pub struct BeamGizmoPlugin;
+
+#[derive(Resource, Debug, Default)]
+pub struct DebugOverlay(pub bool);
+
+#[derive(Resource, Debug, Default)]
+pub struct BeamGizmos(pub bool);
+
+#[derive(Component)]
+struct DebugOverlayText;
+
+fn toggle_debug_overlay(keys: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<DebugOverlay>) {
+    if keys.just_pressed(KeyCode::F3) {
+        overlay.0 = !overlay.0;
+    }
+}
+
+fn update_debug_overlay(
+    overlay: Res<DebugOverlay>,
+    level: Res<Level>,
+    q_text: Query<Entity, With<DebugOverlayText>>,
+    mut commands: Commands,
+) {
+    if !overlay.is_changed() && !level.is_changed() {
+        return;
+    }
+    for entity in q_text.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(parent) = level.parent else {
+        return;
+    };
+    if !overlay.0 {
+        return;
+    }
+
+    let board = &level.present;
+    commands.entity(parent).with_children(|parent| {
+        for coords in board.dims.iter() {
+            spawn_text(parent, format!("{:?}", coords), coords.to_xy(), CELL_COLOR);
+        }
+        for orientation in Orientation::iter() {
+            for (coords, _) in board.borders(orientation).iter() {
+                let pos = coords.to_xy() - orientation.offset();
+                spawn_text(parent, format!("{:?}", coords), pos, BORDER_COLOR);
+            }
+        }
+        for (coords, manipulator) in board.manipulators() {
+            for direction in manipulator.emitters.directions() {
+                let Some(target) = manipulator.target(direction) else {
+                    continue;
+                };
+                let kind = match target.kind {
+                    BeamTargetKind::Piece => "P",
+                    BeamTargetKind::Border => "B",
+                };
+                let pos = coords.to_xy() + direction.delta() * TARGET_OFFSET_FACTOR;
+                spawn_text(
+                    parent,
+                    format!("{}{:?}", kind, target.coords),
+                    pos,
+                    TARGET_COLOR,
+                );
+            }
+        }
+    });
+}
+
+fn toggle_beam_gizmos(keys: Res<ButtonInput<KeyCode>>, mut beam_gizmos: ResMut<BeamGizmos>) {
+    if keys.just_pressed(KeyCode::F4) {
+        beam_gizmos.0 = !beam_gizmos.0;
+    }
+}
+
+fn toggle_future_beam_preview(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut preview: ResMut<FutureBeamPreview>,
+) {
+    if keys.just_pressed(KeyCode::F6) {
+        preview.0 = !preview.0;
+    }
+}
+
+fn draw_beam_gizmos(
+    beam_gizmos: Res<BeamGizmos>,
+    level: Res<Level>,
+    q_xform: Query<&GlobalTransform>,
+    mut gizmos: Gizmos,
+) {
+    if !beam_gizmos.0 {
+        return;
+    }
+    let Some(xform) = level.parent.and_then(|parent| q_xform.get(parent).ok()) else {
+        return;
+    };
+    let to_world = |pos: Vec2| xform.transform_point(pos.extend(0.0)).truncate();
+
+    for (coords, manipulator) in level.present.manipulators() {
+        for direction in manipulator.emitters.directions() {
+            let Some(target) = manipulator.target(direction) else {
+                continue;
+            };
+            let (end, color) = match target.kind {
+                BeamTargetKind::Piece => (target.coords.to_xy(), PIECE_GIZMO_COLOR),
+                BeamTargetKind::Border => {
+                    let orientation = direction.orientation().flip();
+                    let end = target.coords.to_xy() - orientation.offset();
+                    (end, BORDER_GIZMO_COLOR)
+                }
+            };
+            gizmos.line_2d(to_world(coords.to_xy()), to_world(end), color);
+        }
+    }
+}
+
+fn solve_step(
+    In(focus): In<Focus>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut keyboard_input: Local<ButtonInput<KeyCode>>,
+    mut pending: Local<Option<Move>>,
+    level: Res<Level>,
+    mut ev_select_manipulator: EventWriter<SelectManipulatorEvent>,
+    mut ev_move_manipulator: EventWriter<MoveManipulatorEvent>,
+) {
+    keyboard_input.clear();
+    for event in keyboard_events.read() {
+        match event.state {
+            ButtonState::Pressed => keyboard_input.press(event.key_code),
+            ButtonState::Released => keyboard_input.release(event.key_code),
+        }
+    }
+
+    if let Focus::Busy(_) = focus {
+        return;
+    }
+
+    // NOTE: Selection only takes effect on the next tick, so the move is sent once the focus
+    // lands on the manipulator that the solver picked.
+    if let Some(step) = pending.take() {
+        if let Focus::Selected(coords, _) = focus {
+            if coords == step.coords {
+                ev_move_manipulator.send(MoveManipulatorEvent(step.direction));
+            }
+        }
+        return;
+    }
+
+    if !keyboard_input.just_pressed(KeyCode::F5) {
+        return;
+    }
+    let Some(step) = level
+        .present
+        .solve()
+        .and_then(|solution| solution.first().copied())
+    else {
+        warn!("No solution found from the current state");
+        return;
+    };
+    match focus {
+        Focus::Selected(coords, _) if coords == step.coords => {
+            ev_move_manipulator.send(MoveManipulatorEvent(step.direction));
+        }
+        _ => {
+            ev_select_manipulator.send(SelectManipulatorEvent::AtCoords(step.coords));
+            *pending = Some(step);
+        }
+    }
+}
+
+fn spawn_text(parent: &mut ChildBuilder, text: String, pos: Vec2, color: Color) {
+    parent.spawn((
+        DebugOverlayText,
+        Text2dBundle {
+            text: Text::from_section(
+                text,
+                TextStyle {
+                    font_size: FONT_SIZE,
+                    color,
+                    ..Default::default()
+                },
+            ),
+            transform: Transform::from_translation(pos.extend(Z_LAYER)),
+            ..Default::default()
+        },
+    ));
+}
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlay>().add_systems(
+            Update,
+            (toggle_debug_overlay, update_debug_overlay)
+                .chain()
+                .run_if(in_state(InLevel)),
+        );
+    }
+}
+
+impl Plugin for BeamGizmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BeamGizmos>().add_systems(
+            Update,
+            (
+                toggle_beam_gizmos,
+                toggle_future_beam_preview,
+                draw_beam_gizmos,
+            )
+                .chain()
+                .run_if(in_state(InLevel)),
+        );
+    }
+}
+
+impl Plugin for SolveStepPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedPreUpdate,
+            get_focus
+                .pipe(solve_step)
+                .in_set(GameplaySet)
+                .after(InputSet),
+        );
+    }
+}
+
+const FONT_SIZE: f32 = 10.0;
+const TARGET_OFFSET_FACTOR: f32 = 0.3;
+const CELL_COLOR: Color = Color::WHITE;
+const BORDER_COLOR: Color = Color::srgb(1.0, 0.6, 0.0);
+const TARGET_COLOR: Color = Color::srgb(0.0, 1.0, 1.0);
+const PIECE_GIZMO_COLOR: Color = Color::srgb(0.0, 1.0, 0.0);
+const BORDER_GIZMO_COLOR: Color = Color::srgb(1.0, 0.0, 1.0);
+const Z_LAYER: f32 = 10.0;
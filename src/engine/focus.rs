@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::time::Duration;
 
 use bevy::app::Plugin;
 use bevy::asset::{AssetServer, Handle};
@@ -15,12 +15,17 @@ use bevy::render::texture::Image;
 use bevy::render::view::Visibility;
 use bevy::sprite::SpriteBundle;
 use bevy::transform::components::Transform;
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
 use enumset::EnumSet;
+use interpolation::Ease;
 use strum::IntoEnumIterator;
 
-use crate::model::{BoardCoords, Direction};
+use crate::model::{BoardCoords, Direction, MoveBlockReason};
 
-use super::{EngineCoords, GameplaySet};
+use super::animation::ReduceMotion;
+use super::level::Level;
+use super::{EngineCoords, GameState, GameplaySet, LoadBarrier, MainCamera};
 
 pub struct FocusPlugin;
 
@@ -40,6 +45,22 @@ pub struct UpdateFocusEvent(pub Focus);
 #[derive(Component)]
 pub struct FocusArrow(Direction);
 
+// NOTE: When enabled, a manipulator that can no longer move after a move hands the selection over
+// to the next one that can.
+#[derive(Resource, Debug, Default)]
+pub struct AutoSelectMovable(pub bool);
+
+// NOTE: Tiles that keep the selected manipulator from moving because of a tint mismatch get a
+// marker of their own, so the player can tell why the arrow is missing.
+#[derive(Resource, Debug, Default)]
+struct TintBlockers(Vec<BoardCoords>);
+
+#[derive(Component, Debug, Default)]
+struct FocusPulse {
+    is_pulsing: bool,
+    played_duration: Duration,
+}
+
 pub struct FocusAssets {
     texture: Handle<Image>,
     arrow_textures: HashMap<Direction, Handle<Image>>,
@@ -48,6 +69,7 @@ pub struct FocusAssets {
 #[derive(Bundle)]
 struct FocusBundle {
     focus: Focus,
+    pulse: FocusPulse,
     sprite: SpriteBundle,
 }
 
@@ -61,7 +83,7 @@ impl Focus {
     pub fn coords(&self, include_busy: bool) -> Option<BoardCoords> {
         match self {
             Focus::Selected(coords, _) => Some(*coords),
-            Focus::Busy(coords) if include_busy => coords.clone(),
+            Focus::Busy(coords) if include_busy => *coords,
             _ => None,
         }
     }
@@ -74,9 +96,26 @@ impl Focus {
     }
 }
 
+impl FocusPulse {
+    fn start(&mut self) {
+        self.is_pulsing = true;
+        self.played_duration = Duration::ZERO;
+    }
+
+    fn stop(&mut self, xform: &mut Transform, sprite: &mut Sprite) {
+        self.is_pulsing = false;
+        xform.scale = Vec3::ONE;
+        sprite.color = sprite.color.with_alpha(1.0);
+    }
+
+    fn progress(&self) -> f32 {
+        self.played_duration.as_secs_f32() / PULSE_DURATION.as_secs_f32()
+    }
+}
+
 impl FocusAssets {
-    pub fn load(server: &AssetServer, barrier: &Arc<()>) -> Self {
-        let texture = server.load("focus.png");
+    pub fn load(server: &AssetServer, barrier: &LoadBarrier) -> Self {
+        let texture = barrier.load(server, "focus.png");
         let mut arrow_textures = HashMap::new();
         for direction in Direction::iter() {
             let path = match direction {
@@ -85,7 +124,7 @@ impl FocusAssets {
                 Direction::Down => "focus-d.png",
                 Direction::Right => "focus-r.png",
             };
-            arrow_textures.insert(direction, server.load_acquire(path, Arc::clone(&barrier)));
+            arrow_textures.insert(direction, barrier.load(server, path));
         }
         Self {
             texture,
@@ -98,6 +137,7 @@ impl FocusBundle {
     fn new(assets: &FocusAssets) -> Self {
         Self {
             focus: Focus::None,
+            pulse: FocusPulse::default(),
             sprite: SpriteBundle {
                 texture: assets.texture.clone(),
                 visibility: Visibility::Hidden,
@@ -139,15 +179,37 @@ pub fn get_focus(query: Query<&Focus>) -> Focus {
 
 pub fn update_focus(
     mut events: EventReader<UpdateFocusEvent>,
-    mut q_focus: Query<(&mut Focus, &mut Transform, &mut Visibility, &Children)>,
+    mut q_focus: Query<(
+        &mut Focus,
+        &mut FocusPulse,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+        &Children,
+    )>,
     mut q_arrow: Query<(&FocusArrow, &mut Visibility), Without<Focus>>,
+    mut tint_blockers: ResMut<TintBlockers>,
+    level: Res<Level>,
 ) {
     let Some(event) = events.read().last() else {
         return;
     };
     let value = event.0.clone();
-    let (mut focus, mut xform, mut visibility, children) = q_focus.single_mut();
+    let (mut focus, mut pulse, mut xform, mut sprite, mut visibility, children) =
+        q_focus.single_mut();
+    pulse.stop(&mut xform, &mut sprite);
+    tint_blockers.0.clear();
     if let Focus::Selected(coords, directions) = &value {
+        let reasons = level.present.compute_allowed_moves_with_reasons(*coords);
+        for (_, reason) in reasons {
+            if let MoveBlockReason::TintMismatch(tile_coords) = reason {
+                tint_blockers.0.push(tile_coords);
+            }
+        }
+        // NOTE: Reselecting the leader after a move is not a new selection, so it doesn't pulse
+        if !matches!(*focus, Focus::Busy(_)) {
+            pulse.start();
+        }
         xform.translation = coords.to_xy().extend(Z_LAYER);
         *visibility = Visibility::Inherited;
         for &child in children {
@@ -163,6 +225,115 @@ pub fn update_focus(
     *focus = value;
 }
 
+fn animate_focus_pulse(
+    time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
+    mut q_focus: Query<(&mut FocusPulse, &mut Transform, &mut Sprite)>,
+) {
+    for (mut pulse, mut xform, mut sprite) in q_focus.iter_mut() {
+        if !pulse.is_pulsing {
+            continue;
+        }
+        pulse.played_duration += reduce_motion.delta(&time, PULSE_DURATION);
+        if pulse.played_duration >= PULSE_DURATION {
+            pulse.stop(&mut xform, &mut sprite);
+            continue;
+        }
+        let progress = pulse.progress();
+        let swell = 1.0 - (2.0 * progress - 1.0).abs();
+        let swell = swell.sine_in_out();
+        xform.scale = Vec3::splat(1.0.lerp(PULSE_SCALE, swell));
+        sprite.color = sprite.color.with_alpha(1.0.lerp(PULSE_ALPHA, swell));
+    }
+}
+
+fn draw_tint_blockers(
+    tint_blockers: Res<TintBlockers>,
+    level: Res<Level>,
+    q_xform: Query<&GlobalTransform>,
+    mut gizmos: Gizmos,
+) {
+    let Some(xform) = level.parent.and_then(|parent| q_xform.get(parent).ok()) else {
+        return;
+    };
+    let to_world = |pos: Vec2| xform.transform_point(pos.extend(0.0)).truncate();
+    for coords in tint_blockers.0.iter() {
+        let center = coords.to_xy();
+        for corner in [BLOCKER_HALF_SIZE, BLOCKER_HALF_SIZE * Vec2::new(1.0, -1.0)] {
+            gizmos.line_2d(
+                to_world(center - corner),
+                to_world(center + corner),
+                BLOCKER_COLOR,
+            );
+        }
+    }
+}
+
+// NOTE: Hovering over one of the focus arrows previews the move, by marking the pieces that would
+// be left unsupported, and fade out, if it were made. Gizmos only last a frame, so the marks go
+// away as soon as the cursor leaves the arrow.
+fn draw_orphan_warnings(
+    In(focus): In<Focus>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    level: Res<Level>,
+    q_xform: Query<&Transform>,
+    q_global_xform: Query<&GlobalTransform>,
+    mut egui_ctx: EguiContexts,
+    mut gizmos: Gizmos,
+) {
+    let Focus::Selected(focus_coords, directions) = focus else {
+        return;
+    };
+    if egui_ctx.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    let (camera, camera_xform) = camera.single();
+    let Some(pos) = window
+        .single()
+        .cursor_position()
+        .and_then(|pos| camera.viewport_to_world_2d(camera_xform, pos))
+    else {
+        return;
+    };
+    let Some((coords, offset)) = level.coords_at_pos(pos, &q_xform) else {
+        return;
+    };
+    if coords != focus_coords {
+        return;
+    }
+    let Some(direction) =
+        focus_direction_for_offset(offset).filter(|&dir| directions.contains(dir))
+    else {
+        return;
+    };
+    let Some(move_set) = level.present.compute_checked_move_set(coords, direction) else {
+        return;
+    };
+    let mut future = level.present.clone();
+    future.move_pieces(&move_set, direction);
+    future.retarget_beams();
+
+    let Some(xform) = level
+        .parent
+        .and_then(|parent| q_global_xform.get(parent).ok())
+    else {
+        return;
+    };
+    let to_world = |pos: Vec2| xform.transform_point(pos.extend(0.0)).truncate();
+    for coords in future.unsupported_pieces().iter() {
+        let center = coords.to_xy();
+        let corners = [
+            Vec2::new(-1.0, -1.0),
+            Vec2::new(1.0, -1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(-1.0, 1.0),
+        ]
+        .map(|corner| to_world(center + corner * ORPHAN_HALF_SIZE));
+        gizmos.linestrip_2d(corners.into_iter().chain([corners[0]]), ORPHAN_COLOR);
+    }
+}
+
 pub fn focus_direction_for_offset(offset: Vec2) -> Option<Direction> {
     for direction in Direction::iter() {
         if (offset - direction_offset(direction))
@@ -188,10 +359,27 @@ fn direction_offset(direction: Direction) -> Vec2 {
 impl Plugin for FocusPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<UpdateFocusEvent>()
+            .init_resource::<AutoSelectMovable>()
+            .init_resource::<TintBlockers>()
             .configure_sets(FixedPostUpdate, FocusSet.in_set(GameplaySet))
-            .add_systems(FixedPostUpdate, update_focus.in_set(FocusSet));
+            .add_systems(
+                FixedPostUpdate,
+                (update_focus, animate_focus_pulse).chain().in_set(FocusSet),
+            )
+            .add_systems(
+                Update,
+                (draw_tint_blockers, get_focus.pipe(draw_orphan_warnings))
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
 const ARROW_HALF_SIZE: Vec2 = Vec2::new(7.0, 7.0);
 const Z_LAYER: f32 = 3.0;
+const PULSE_DURATION: Duration = Duration::from_millis(300);
+const PULSE_SCALE: f32 = 1.15;
+const PULSE_ALPHA: f32 = 0.6;
+const BLOCKER_HALF_SIZE: Vec2 = Vec2::new(12.0, 12.0);
+const BLOCKER_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+const ORPHAN_HALF_SIZE: f32 = 18.0;
+const ORPHAN_COLOR: Color = Color::srgb(1.0, 0.1, 0.1);
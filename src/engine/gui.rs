@@ -1,26 +1,42 @@
-use std::sync::Arc;
-
 use bevy::prelude::*;
 use bevy_egui::egui::FontFamily;
 use bevy_egui::{egui, EguiContexts};
-use classic_campaign::{clean_up_level_preview, init_level_preview};
+use classic_campaign::{
+    clean_up_level_preview, init_level_preview, render_level_thumbnail, solve_previewed_level,
+    LevelSelectCursor,
+};
 
-use crate::model::{Board, LevelMetadata};
+use crate::model::{Board, LevelMetadata, MAX_STARS};
 
+use super::attract::attract_mode_active;
 use super::focus::get_focus;
-use super::{AssetsLoaded, GameAssets, GameState, InLevel};
+use super::{AssetLoadFailure, AssetsLoaded, GameAssets, GameState, InLevel, LoadBarrier};
 
+mod briefing;
 mod classic_campaign;
+mod confirm_quit;
+mod confirm_reset;
 mod font;
 mod game_over;
 mod in_game;
+mod load_failure;
 mod main_menu;
+mod reset_progress;
+mod tooltip;
 
+use self::briefing::briefing_ui;
 use self::classic_campaign::classic_level_select_ui;
+use self::confirm_quit::{confirm_quit_ui, dismiss_quit_confirmation, ConfirmQuit};
+use self::confirm_reset::{confirm_reset_ui, dismiss_reset_confirmation, ConfirmReset};
 use self::font::{EguiFontAsset, EguiFontAssetLoader};
 use self::game_over::game_over_ui;
 use self::in_game::in_game_ui;
-use self::main_menu::main_menu_ui;
+use self::load_failure::load_failure_ui;
+use self::main_menu::{cancel_daily_puzzle, main_menu_ui, play_daily_puzzle, DailyPuzzle};
+use self::reset_progress::{
+    confirm_progress_reset_ui, dismiss_progress_reset_confirmation, ConfirmProgressReset,
+};
+use self::tooltip::manipulator_tooltip_ui;
 
 pub struct GuiPlugin;
 
@@ -36,17 +52,61 @@ pub struct PlayLevel(pub Board, pub LevelMetadata);
 pub enum UndoMoves {
     Last,
     All,
+    ToCheckpoint,
+    Redo,
 }
 
 impl GuiAssets {
-    pub fn load(server: &AssetServer, barrier: &Arc<()>) -> Self {
+    pub fn load(server: &AssetServer, barrier: &LoadBarrier) -> Self {
         Self {
-            main_font: server.load_acquire("space-age.ttf", Arc::clone(&barrier)),
-            msg_font: server.load_acquire("hall-fetica-decompose.ttf", Arc::clone(&barrier)),
+            main_font: barrier.load(server, "space-age.ttf"),
+            msg_font: barrier.load(server, "hall-fetica-decompose.ttf"),
         }
     }
 }
 
+fn add_button(ui: &mut egui::Ui, text: &str) -> egui::Response {
+    ui.vertical_centered(|ui| ui.add(egui::Button::new(text).min_size(egui::Vec2::new(100.0, 0.0))))
+        .inner
+}
+
+// NOTE: The fonts don't have a star glyph, so the stars are painted as shapes instead
+fn add_stars(ui: &mut egui::Ui, stars: u8, size: f32) -> egui::Response {
+    let spacing = size * STAR_SPACING;
+    let width = MAX_STARS as f32 * size + (MAX_STARS - 1) as f32 * spacing;
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, size), egui::Sense::hover());
+    for idx in 0..MAX_STARS {
+        let center =
+            rect.left_center() + egui::vec2(size / 2.0 + idx as f32 * (size + spacing), 0.0);
+        let color = match idx < stars {
+            true => STAR_COLOR,
+            false => STAR_EMPTY_COLOR,
+        };
+        paint_star(ui.painter(), center, size / 2.0, color);
+    }
+    response
+}
+
+// NOTE: A star isn't convex, so it's painted as a pentagon with a triangle on each side
+fn paint_star(painter: &egui::Painter, center: egui::Pos2, radius: f32, color: egui::Color32) {
+    let point = |idx: usize, radius: f32| {
+        let angle = std::f32::consts::TAU * idx as f32 / 10.0 - std::f32::consts::FRAC_PI_2;
+        center + radius * egui::vec2(angle.cos(), angle.sin())
+    };
+    let inner: Vec<_> = (0..5)
+        .map(|idx| point(idx * 2 + 1, radius * STAR_INNER_RADIUS))
+        .collect();
+    painter.add(egui::Shape::convex_polygon(
+        inner.clone(),
+        color,
+        egui::Stroke::NONE,
+    ));
+    for (idx, &after) in inner.iter().enumerate() {
+        let tip = vec![point(idx * 2, radius), after, inner[(idx + 4) % 5]];
+        painter.add(egui::Shape::convex_polygon(tip, color, egui::Stroke::NONE));
+    }
+}
+
 fn setup_gui_ctx(
     mut ev_loaded: EventReader<AssetsLoaded>,
     assets: Res<GameAssets>,
@@ -115,15 +175,62 @@ impl Plugin for GuiPlugin {
             .init_asset_loader::<EguiFontAssetLoader>()
             .add_event::<PlayLevel>()
             .add_event::<UndoMoves>()
+            .init_resource::<LevelSelectCursor>()
+            .init_resource::<ConfirmReset>()
+            .init_resource::<ConfirmQuit>()
+            .init_resource::<ConfirmProgressReset>()
+            .init_resource::<DailyPuzzle>()
             .add_systems(Startup, init_level_preview)
             .add_systems(Update, setup_gui_ctx.run_if(in_state(GameState::Init)))
-            .add_systems(Update, main_menu_ui.run_if(in_state(GameState::MainMenu)))
             .add_systems(
                 Update,
-                classic_level_select_ui.run_if(in_state(GameState::ClassicLevelSelect)),
+                load_failure_ui.run_if(resource_exists::<AssetLoadFailure>),
+            )
+            .add_systems(
+                Update,
+                (
+                    main_menu_ui,
+                    play_daily_puzzle,
+                    confirm_quit_ui,
+                    confirm_progress_reset_ui,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(
+                OnExit(GameState::MainMenu),
+                (
+                    dismiss_quit_confirmation,
+                    dismiss_progress_reset_confirmation,
+                    cancel_daily_puzzle,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    render_level_thumbnail,
+                    solve_previewed_level,
+                    classic_level_select_ui,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::ClassicLevelSelect)),
             )
             .add_systems(Update, get_focus.pipe(in_game_ui).run_if(in_state(InLevel)))
-            .add_systems(Update, game_over_ui.run_if(in_state(GameState::GameOver)))
+            .add_systems(
+                Update,
+                get_focus
+                    .pipe(manipulator_tooltip_ui)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Update, briefing_ui.run_if(in_state(GameState::Briefing)))
+            .add_systems(
+                Update,
+                game_over_ui
+                    .run_if(in_state(GameState::GameOver))
+                    .run_if(not(attract_mode_active)),
+            )
+            .add_systems(Update, confirm_reset_ui.run_if(in_state(InLevel)))
+            .add_systems(OnExit(InLevel), dismiss_reset_confirmation)
             .add_systems(
                 OnExit(GameState::ClassicLevelSelect),
                 clean_up_level_preview,
@@ -131,6 +238,11 @@ impl Plugin for GuiPlugin {
     }
 }
 
+const STAR_SPACING: f32 = 0.2;
+const STAR_INNER_RADIUS: f32 = 0.4;
+const STAR_COLOR: egui::Color32 = egui::Color32::from_rgb(0xfe, 0xd8, 0x00);
+const STAR_EMPTY_COLOR: egui::Color32 = egui::Color32::from_gray(0x40);
+
 pub const WINDOW_WIDTH: u32 = 800;
 pub const WINDOW_HEIGHT: u32 = 600;
 pub use in_game::IN_GAME_PANEL_WIDTH;
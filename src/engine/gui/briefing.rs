@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::engine::level::Level;
+use crate::engine::GameState;
+
+pub(super) fn briefing_ui(
+    mut egui_ctx: EguiContexts,
+    level: Res<Level>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(briefing) = level.metadata.briefing.as_ref() else {
+        next_state.set(GameState::Playing);
+        return;
+    };
+
+    egui::Window::new("briefing")
+        .title_bar(false)
+        .resizable(false)
+        .movable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::splat(0.0))
+        .min_width(360.0)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.vertical_centered(|ui| {
+                let message = egui::RichText::new(briefing).text_style(egui::TextStyle::Small);
+                ui.label(message);
+            });
+        });
+
+    if keys.get_just_pressed().next().is_some() || mouse_buttons.get_just_pressed().next().is_some()
+    {
+        next_state.set(GameState::Playing);
+    }
+}
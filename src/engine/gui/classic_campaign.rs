@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
 use bevy::render::camera::RenderTarget;
@@ -6,93 +8,155 @@ use bevy::render::render_resource::{
 };
 use bevy::render::texture::BevyDefault;
 use bevy::render::view::RenderLayers;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
 use bevy_egui::{egui, EguiContexts, EguiUserTextures};
 
-use crate::engine::border::{spawn_horz_border, spawn_vert_border};
-use crate::engine::level::{spawn_board, Campaign};
+use crate::engine::border::{spawn_corner_border, spawn_horz_border, spawn_vert_border};
+use crate::engine::level::{spawn_board, Campaign, Progress};
 use crate::engine::manipulator::spawn_manipulator;
 use crate::engine::particle::spawn_particle;
 use crate::engine::tile::spawn_tile;
-use crate::engine::GameAssets;
-use crate::model::{Board, Piece};
+use crate::engine::{GameAssets, GameState};
+use crate::model::{Board, LevelCampaign, Piece};
 
-use super::{PlayLevel, WINDOW_WIDTH};
+use super::{add_stars, PlayLevel, WINDOW_WIDTH};
 
 #[derive(Resource)]
 pub struct LevelPreview {
-    level_idx: Option<usize>,
-    board: Entity,
-    image: Handle<Image>,
+    camera: Entity,
+    thumbnails: HashMap<usize, Handle<Image>>,
+    pending_board: Option<Entity>,
+    solution_lengths: HashMap<usize, Option<usize>>,
+    pending_solutions: HashMap<usize, Task<Option<usize>>>,
 }
 
-pub(super) fn init_level_preview(
-    assets: Res<AssetServer>,
-    mut commands: Commands,
-    mut egui_user_textures: ResMut<EguiUserTextures>,
-) {
-    let size = Extent3d {
-        width: PREVIEW_WIDTH,
-        height: PREVIEW_HEIGHT,
-        ..Default::default()
-    };
-    let mut image = Image {
-        texture_descriptor: TextureDescriptor {
-            label: None,
-            size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: TextureDimension::D2,
-            format: TextureFormat::bevy_default(),
-            usage: TextureUsages::TEXTURE_BINDING
-                | TextureUsages::COPY_DST
-                | TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &[],
-        },
-        ..Default::default()
-    };
-    image.resize(size);
-    let image = assets.add(image);
-
-    commands.insert_resource(LevelPreview {
-        level_idx: None,
-        board: Entity::PLACEHOLDER,
-        image: image.clone(),
-    });
-    egui_user_textures.add_image(image.clone_weak());
+#[derive(Resource, Default)]
+pub struct LevelSelectCursor(Option<usize>);
 
+pub(super) fn init_level_preview(mut commands: Commands) {
     let layer = RenderLayers::layer(1);
     let mut camera = Camera2dBundle {
         camera: Camera {
             order: -1,
-            target: RenderTarget::Image(image.clone_weak()),
+            is_active: false,
             ..Default::default()
         },
         ..Default::default()
     };
     camera.projection.viewport_origin = Vec2::new(0.0, 1.0);
     camera.projection.scale = PREVIEW_SCALE_FACTOR;
-    commands.spawn(camera).insert(layer);
+    let camera = commands.spawn(camera).insert(layer).id();
+
+    commands.insert_resource(LevelPreview {
+        camera,
+        thumbnails: HashMap::new(),
+        pending_board: None,
+        solution_lengths: HashMap::new(),
+        pending_solutions: HashMap::new(),
+    });
 }
 
-pub(super) fn classic_level_select_ui(
-    mut egui_ctx: EguiContexts,
+pub(super) fn render_level_thumbnail(
     campaign: Res<Campaign>,
+    cursor: Res<LevelSelectCursor>,
     assets: Res<GameAssets>,
+    server: Res<AssetServer>,
     mut preview: ResMut<LevelPreview>,
+    mut egui_user_textures: ResMut<EguiUserTextures>,
+    mut q_camera: Query<&mut Camera>,
     mut commands: Commands,
+) {
+    let mut camera = q_camera.get_mut(preview.camera).unwrap();
+
+    // NOTE: The board is rendered into the thumbnail at the end of the frame it was spawned in,
+    // so it can be removed on the next one. Turning off the camera keeps the image intact.
+    if let Some(board) = preview.pending_board.take() {
+        commands.entity(board).despawn_recursive();
+        camera.is_active = false;
+    }
+
+    let Some(level_idx) = cursor.0 else {
+        return;
+    };
+    if preview.thumbnails.contains_key(&level_idx) {
+        return;
+    }
+
+    let image = server.add(thumbnail_image());
+    egui_user_textures.add_image(image.clone_weak());
+    camera.target = RenderTarget::Image(image.clone_weak());
+    camera.is_active = true;
+
+    let board = &campaign.levels[level_idx].board;
+    preview.pending_board = Some(spawn_preview(board, &assets, &mut commands));
+    preview.thumbnails.insert(level_idx, image);
+}
+
+// NOTE: Solving can take a while on larger levels, so it runs in the background and the result
+// is kept for as long as the game is running.
+pub(super) fn solve_previewed_level(
+    campaign: Res<Campaign>,
+    cursor: Res<LevelSelectCursor>,
+    mut preview: ResMut<LevelPreview>,
+) {
+    let finished: Vec<usize> = preview
+        .pending_solutions
+        .iter()
+        .filter(|(_, task)| task.is_finished())
+        .map(|(&level_idx, _)| level_idx)
+        .collect();
+    for level_idx in finished {
+        let task = preview.pending_solutions.remove(&level_idx).unwrap();
+        preview.solution_lengths.insert(level_idx, block_on(task));
+    }
+
+    let Some(level_idx) = cursor.0 else {
+        return;
+    };
+    if preview.solution_lengths.contains_key(&level_idx)
+        || preview.pending_solutions.contains_key(&level_idx)
+    {
+        return;
+    }
+    let board = campaign.levels[level_idx].board.clone();
+    let task = AsyncComputeTaskPool::get()
+        .spawn(async move { board.solve().map(|solution| solution.len()) });
+    preview.pending_solutions.insert(level_idx, task);
+}
+
+pub(super) fn classic_level_select_ui(
+    mut egui_ctx: EguiContexts,
+    campaign: Res<Campaign>,
+    progress: Res<Progress>,
+    preview: Res<LevelPreview>,
+    mut cursor: ResMut<LevelSelectCursor>,
+    keys: Res<ButtonInput<KeyCode>>,
     mut ev_play: EventWriter<PlayLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
-    fn add_button(ui: &mut egui::Ui, idx: usize) -> egui::Response {
+    fn add_button(ui: &mut egui::Ui, idx: usize, selected: bool) -> egui::Response {
         ui.vertical_centered(|ui| {
-            ui.add(egui::Button::new((idx + 1).to_string()).min_size(egui::Vec2::new(60.0, 0.0)))
+            ui.add(
+                egui::Button::new((idx + 1).to_string())
+                    .selected(selected)
+                    .min_size(egui::Vec2::new(60.0, 0.0)),
+            )
         })
         .inner
     }
 
-    let preview_image_id = egui_ctx.image_id(&preview.image).unwrap();
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::MainMenu);
+        return;
+    }
+
+    cursor.0 = move_cursor(&campaign, cursor.0, &keys);
 
-    let mut preview_level = None;
+    let mut hovered_level = None;
     let mut selected_level = None;
+    if keys.just_pressed(KeyCode::Enter) {
+        selected_level = cursor.0;
+    }
 
     egui::SidePanel::left("selection")
         .exact_width(SELECTION_PANEL_WIDTH as _)
@@ -100,18 +164,35 @@ pub(super) fn classic_level_select_ui(
         .show(egui_ctx.ctx_mut(), |ui| {
             ui.vertical_centered(|ui| {
                 ui.heading("SeLeCT A LeVeL");
+                ui.label(format!(
+                    "{} / {} SOLVeD",
+                    progress.solved_count(0..campaign.levels.len()),
+                    campaign.levels.len()
+                ));
+                ui.add_space(10.0);
                 for tier in campaign.tiers.iter() {
                     ui.group(|ui| {
-                        ui.label(&tier.name);
+                        ui.label(format!(
+                            "{} {}/{}",
+                            tier.name,
+                            progress.solved_count(tier.levels.iter().copied()),
+                            tier.levels.len()
+                        ));
                         ui.columns(tier.levels.len(), |ui| {
                             for (col, &level_idx) in tier.levels.iter().enumerate() {
-                                let btn_state = add_button(&mut ui[col], level_idx);
+                                let is_cursor = cursor.0 == Some(level_idx);
+                                let btn_state = add_button(&mut ui[col], level_idx, is_cursor);
                                 if btn_state.hovered() {
-                                    preview_level = Some(level_idx);
+                                    hovered_level = Some(level_idx);
                                 }
                                 if btn_state.clicked() {
                                     selected_level = Some(level_idx);
                                 }
+                                if let Some(stars) = progress.best_stars(level_idx) {
+                                    ui[col].vertical_centered(|ui| {
+                                        add_stars(ui, stars, LEVEL_SELECT_STAR_SIZE)
+                                    });
+                                }
                             }
                         })
                     });
@@ -120,18 +201,13 @@ pub(super) fn classic_level_select_ui(
             });
         });
 
-    if preview.level_idx != preview_level {
-        if preview.level_idx.is_some() {
-            commands.entity(preview.board).despawn_recursive();
-        }
-        if let Some(level_idx) = preview_level {
-            let board = &campaign.levels[level_idx].board;
-            preview.board = spawn_preview(board, &assets, &mut commands);
-        } else {
-            preview.board = Entity::PLACEHOLDER;
-        }
-        preview.level_idx = preview_level;
+    if hovered_level.is_some() {
+        cursor.0 = hovered_level;
     }
+    let preview_level = cursor.0;
+    let preview_image_id = preview_level
+        .and_then(|level_idx| preview.thumbnails.get(&level_idx))
+        .and_then(|image| egui_ctx.image_id(image));
 
     egui::SidePanel::right("preview")
         .resizable(false)
@@ -141,11 +217,25 @@ pub(super) fn classic_level_select_ui(
             if let Some(level_idx) = preview_level {
                 ui.vertical_centered(|ui| {
                     ui.label(&campaign.levels[level_idx].name);
+                    if let Some(par) = campaign.levels[level_idx].par {
+                        ui.label(format!("pAr: {}", par));
+                    }
+                    if let Some(best) = progress.best_moves(level_idx) {
+                        ui.label(format!("BeST: {}", best));
+                    }
+                    let shortest = match preview.solution_lengths.get(&level_idx) {
+                        Some(Some(len)) => len.to_string(),
+                        Some(None) => "?".to_string(),
+                        None => "...".to_string(),
+                    };
+                    ui.label(format!("SHOrTeST: {}", shortest));
                     ui.add_space(30.0);
-                    ui.image(egui::load::SizedTexture::new(
-                        preview_image_id,
-                        egui::vec2(PREVIEW_WIDTH as _, PREVIEW_HEIGHT as _),
-                    ));
+                    if let Some(image_id) = preview_image_id {
+                        ui.image(egui::load::SizedTexture::new(
+                            image_id,
+                            egui::vec2(PREVIEW_WIDTH as _, PREVIEW_HEIGHT as _),
+                        ));
+                    }
                 });
             }
         });
@@ -157,11 +247,86 @@ pub(super) fn classic_level_select_ui(
     }
 }
 
-pub(super) fn clean_up_level_preview(mut preview: ResMut<LevelPreview>, mut commands: Commands) {
-    if preview.level_idx.take().is_some() {
-        commands.entity(preview.board).despawn_recursive();
-        preview.board = Entity::PLACEHOLDER;
+pub(super) fn clean_up_level_preview(
+    mut preview: ResMut<LevelPreview>,
+    mut q_camera: Query<&mut Camera>,
+    mut commands: Commands,
+) {
+    if let Some(board) = preview.pending_board.take() {
+        commands.entity(board).despawn_recursive();
+        q_camera.get_mut(preview.camera).unwrap().is_active = false;
+    }
+}
+
+fn move_cursor(
+    campaign: &LevelCampaign,
+    cursor: Option<usize>,
+    keys: &ButtonInput<KeyCode>,
+) -> Option<usize> {
+    let up = keys.any_just_pressed([KeyCode::KeyW, KeyCode::ArrowUp]);
+    let left = keys.any_just_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]);
+    let down = keys.any_just_pressed([KeyCode::KeyS, KeyCode::ArrowDown]);
+    let right = keys.any_just_pressed([KeyCode::KeyD, KeyCode::ArrowRight]);
+    if !(up || left || down || right) {
+        return cursor;
+    }
+
+    let Some(level_idx) = cursor else {
+        return (!campaign.levels.is_empty()).then_some(0);
+    };
+    let Some((tier_idx, col)) = campaign
+        .tiers
+        .iter()
+        .enumerate()
+        .find_map(|(tier_idx, tier)| {
+            let col = tier.levels.iter().position(|&idx| idx == level_idx)?;
+            Some((tier_idx, col))
+        })
+    else {
+        return cursor;
+    };
+
+    let mut tier_idx = tier_idx;
+    let mut col = col;
+    if up {
+        tier_idx = tier_idx.saturating_sub(1);
+    } else if down {
+        tier_idx = std::cmp::min(tier_idx + 1, campaign.tiers.len().saturating_sub(1));
+    } else if left {
+        col = col.saturating_sub(1);
+    } else if right {
+        col += 1;
     }
+    let levels = &campaign.tiers[tier_idx].levels;
+    levels
+        .get(std::cmp::min(col, levels.len().saturating_sub(1)))
+        .copied()
+        .or(cursor)
+}
+
+fn thumbnail_image() -> Image {
+    let size = Extent3d {
+        width: PREVIEW_WIDTH,
+        height: PREVIEW_HEIGHT,
+        ..Default::default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::bevy_default(),
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..Default::default()
+    };
+    image.resize(size);
+    image
 }
 
 fn spawn_preview(board: &Board, assets: &GameAssets, commands: &mut Commands) -> Entity {
@@ -183,14 +348,31 @@ fn spawn_preview(board: &Board, assets: &GameAssets, commands: &mut Commands) ->
         for (coords, border) in board.vert_borders.iter() {
             spawn_vert_border(parent, border, coords, &assets.borders, &mutator);
         }
+        for (coords, border) in board.corner_borders.iter() {
+            spawn_corner_border(parent, border, coords, &assets.borders, &mutator);
+        }
         for (coords, piece) in board.pieces.iter() {
             match piece {
                 Piece::Particle(particle) => {
-                    spawn_particle(parent, particle, coords, &assets.particles, &mutator)
-                }
-                Piece::Manipulator(manipulator) => {
-                    spawn_manipulator(parent, manipulator, coords, &board, &assets, &mutator)
+                    let collected = board.is_collected(coords);
+                    spawn_particle(
+                        parent,
+                        particle,
+                        coords,
+                        collected,
+                        &assets.particles,
+                        &mutator,
+                    )
                 }
+                Piece::Manipulator(manipulator) => spawn_manipulator(
+                    parent,
+                    manipulator,
+                    coords,
+                    &board,
+                    &assets.manipulators,
+                    &assets.beams,
+                    &mutator,
+                ),
             };
         }
     });
@@ -207,3 +389,4 @@ const PREVIEW_AREA_SIZE: Vec2 = Vec2::new(
 );
 const PREVIEW_PANEL_WIDTH: u32 = 300;
 const SELECTION_PANEL_WIDTH: u32 = WINDOW_WIDTH - PREVIEW_PANEL_WIDTH;
+const LEVEL_SELECT_STAR_SIZE: f32 = 10.0;
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use super::add_button;
+
+// NOTE: Quitting from the menu asks for confirmation first, unless the player turned it off
+#[derive(Resource, Debug)]
+pub struct ConfirmQuit {
+    pub enabled: bool,
+    pub pending: bool,
+}
+
+impl Default for ConfirmQuit {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pending: false,
+        }
+    }
+}
+
+pub(super) fn confirm_quit_ui(
+    mut egui_ctx: EguiContexts,
+    mut confirm_quit: ResMut<ConfirmQuit>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if !confirm_quit.pending {
+        return;
+    }
+
+    let title = egui::RichText::new("QUIT?").text_style(egui::TextStyle::Body);
+
+    egui::Window::new(title)
+        .resizable(false)
+        .movable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::splat(0.0))
+        .min_width(360.0)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.vertical_centered(|ui| {
+                let message = egui::RichText::new("Your progress is saved")
+                    .text_style(egui::TextStyle::Small);
+                ui.label(message);
+                ui.columns(2, |ui| {
+                    if add_button(&mut ui[0], "QUIT").clicked() {
+                        exit.send(AppExit::Success);
+                        confirm_quit.pending = false;
+                    }
+                    if add_button(&mut ui[1], "CANCeL").clicked() {
+                        confirm_quit.pending = false;
+                    }
+                });
+            });
+        });
+}
+
+pub(super) fn dismiss_quit_confirmation(mut confirm_quit: ResMut<ConfirmQuit>) {
+    confirm_quit.pending = false;
+}
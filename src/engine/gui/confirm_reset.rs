@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::engine::GameState;
+
+use super::{add_button, UndoMoves};
+
+#[derive(Resource, Debug, Default)]
+pub struct ConfirmReset(pub bool);
+
+pub(super) fn confirm_reset_ui(
+    mut egui_ctx: EguiContexts,
+    state: Res<State<GameState>>,
+    mut confirm_reset: ResMut<ConfirmReset>,
+    mut ev_undo: EventWriter<UndoMoves>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !confirm_reset.0 {
+        return;
+    }
+
+    let title = egui::RichText::new("reSeT LeVeL?").text_style(egui::TextStyle::Body);
+
+    egui::Window::new(title)
+        .resizable(false)
+        .movable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::splat(0.0))
+        .min_width(360.0)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.vertical_centered(|ui| {
+                let message = egui::RichText::new("All of your moves will be lost")
+                    .text_style(egui::TextStyle::Small);
+                ui.label(message);
+                ui.columns(2, |ui| {
+                    if add_button(&mut ui[0], "reSeT").clicked() {
+                        ev_undo.send(UndoMoves::All);
+                        if let GameState::GameOver = state.get() {
+                            next_state.set(GameState::Playing);
+                        }
+                        confirm_reset.0 = false;
+                    }
+                    if add_button(&mut ui[1], "CANCeL").clicked() {
+                        confirm_reset.0 = false;
+                    }
+                });
+            });
+        });
+}
+
+pub(super) fn dismiss_reset_confirmation(mut confirm_reset: ResMut<ConfirmReset>) {
+    confirm_reset.0 = false;
+}
@@ -2,28 +2,31 @@ use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
 
 use crate::engine::gui::UndoMoves;
-use crate::engine::level::{Campaign, Level};
+use crate::engine::level::{Campaign, Level, Progress};
 use crate::engine::GameState;
-use crate::model::LevelOutcome;
+use crate::model::{star_rating, LevelOutcome};
 
-use super::PlayLevel;
+use super::confirm_reset::ConfirmReset;
+use super::{add_button, add_stars, PlayLevel};
 
 pub(super) fn game_over_ui(
     mut egui_ctx: EguiContexts,
     level: Res<Level>,
     campaign: Res<Campaign>,
+    progress: Res<Progress>,
+    mut confirm_reset: ResMut<ConfirmReset>,
     mut ev_undo: EventWriter<UndoMoves>,
     mut ev_play: EventWriter<PlayLevel>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    fn add_button(ui: &mut egui::Ui, text: &str) -> egui::Response {
-        ui.vertical_centered(|ui| {
-            ui.add(egui::Button::new(text).min_size(egui::Vec2::new(100.0, 0.0)))
-        })
-        .inner
-    }
-
     let outcome = level.progress.outcome.unwrap();
+    let next_unsolved = match outcome {
+        LevelOutcome::Victory => level
+            .metadata
+            .id
+            .map(|level_idx| progress.next_unsolved(level_idx, campaign.levels.len())),
+        _ => None,
+    };
 
     let (title, color) = match outcome {
         LevelOutcome::Victory => ("LeVeL pASSed", egui::Color32::from_rgb(0x00, 0x98, 0xfe)),
@@ -40,14 +43,41 @@ pub(super) fn game_over_ui(
         .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::splat(0.0))
         .min_width(360.0)
         .show(egui_ctx.ctx_mut(), |ui| {
+            if confirm_reset.0 {
+                ui.disable();
+            }
             ui.vertical_centered(|ui| {
                 let message = match outcome {
                     LevelOutcome::NoManipulatorsLeft => "You have no manipulators left",
                     LevelOutcome::ParticleLost => "You lost one of the particles",
+                    LevelOutcome::Victory if next_unsolved == Some(None) => "Campaign complete!",
                     LevelOutcome::Victory => "Congratulations!",
                 };
                 let message = egui::RichText::new(message).text_style(egui::TextStyle::Small);
                 ui.label(message);
+                if let LevelOutcome::Victory = outcome {
+                    let best = level.metadata.id.and_then(|idx| progress.best_moves(idx));
+                    let moves = format!("{} moves", level.move_count());
+                    let moves = match level.metadata.par {
+                        Some(par) => format!("{}, par: {}", moves, par),
+                        None => moves,
+                    };
+                    let moves = match best {
+                        Some(best) => format!("{}, best: {}", moves, best),
+                        None => moves,
+                    };
+                    ui.label(egui::RichText::new(moves).text_style(egui::TextStyle::Small));
+                    if let Some(par) = level.metadata.par {
+                        add_stars(
+                            ui,
+                            star_rating(level.move_count(), par),
+                            GAME_OVER_STAR_SIZE,
+                        );
+                    }
+                    if level.new_best {
+                        ui.label(egui::RichText::new("neW BeST!").color(color));
+                    }
+                }
                 let columns = match outcome {
                     LevelOutcome::Victory if level.metadata.next.is_none() => 2,
                     _ => 3,
@@ -69,13 +99,27 @@ pub(super) fn game_over_ui(
                         }
                     }
                     if add_button(&mut ui[col_iter.next().unwrap()], "repLAy").clicked() {
-                        ev_undo.send(UndoMoves::All);
-                        next_state.set(GameState::Playing);
+                        if level.is_reset_costly() {
+                            confirm_reset.0 = true;
+                        } else {
+                            ev_undo.send(UndoMoves::All);
+                            next_state.set(GameState::Playing);
+                        }
                     }
                     if add_button(&mut ui[col_iter.next().unwrap()], "MenU").clicked() {
                         next_state.set(GameState::MainMenu);
                     }
                 });
+                if let Some(Some(level_idx)) = next_unsolved {
+                    ui.add_space(10.0);
+                    if add_button(ui, "nexT UnSOLVed").clicked() {
+                        let board = campaign.levels[level_idx].board.clone();
+                        let metadata = campaign.metadata(level_idx);
+                        ev_play.send(PlayLevel(board, metadata));
+                    }
+                }
             });
         });
 }
+
+const GAME_OVER_STAR_SIZE: f32 = 24.0;
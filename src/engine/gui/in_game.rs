@@ -1,30 +1,60 @@
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
 
-use crate::engine::focus::Focus;
-use crate::engine::level::Level;
-use crate::engine::GameState;
+use crate::engine::animation::ReduceMotion;
+use crate::engine::camera::{board_overflows, CameraMode, CenterCamera, PlayArea};
+use crate::engine::focus::{AutoSelectMovable, Focus};
+use crate::engine::input::{CycleRepeat, MouseMoveGesture, MoveBuffer, CYCLE_INTERVAL_RANGE_MS};
+use crate::engine::level::{Level, SandboxMode};
+use crate::engine::manipulator::HighlightMovable;
+use crate::engine::tutorial::Tutorial;
+use crate::engine::{GameState, TILE_HEIGHT, TILE_WIDTH};
+use crate::model::{
+    BeamTargetKind, Board, BoardCoords, Border, Orientation, Piece, TileKind, Tint,
+};
 
+use super::confirm_reset::ConfirmReset;
 use super::UndoMoves;
 
+#[derive(SystemParam)]
+pub(super) struct InputSettings<'w> {
+    move_buffer: ResMut<'w, MoveBuffer>,
+    cycle_repeat: ResMut<'w, CycleRepeat>,
+    mouse_gesture: ResMut<'w, MouseMoveGesture>,
+}
+
 pub(super) fn in_game_ui(
     focus: In<Focus>,
     state: Res<State<GameState>>,
-    level: Res<Level>,
+    mut level: ResMut<Level>,
+    play_area: Res<PlayArea>,
+    mut camera_mode: ResMut<CameraMode>,
+    mut input: InputSettings,
+    mut sandbox: ResMut<SandboxMode>,
+    mut reduce_motion: ResMut<ReduceMotion>,
+    mut highlight: ResMut<HighlightMovable>,
+    mut auto_select: ResMut<AutoSelectMovable>,
+    tutorial: Option<ResMut<Tutorial>>,
+    mut confirm_reset: ResMut<ConfirmReset>,
     mut egui_ctx: EguiContexts,
     mut ev_undo: EventWriter<UndoMoves>,
+    mut ev_center: EventWriter<CenterCamera>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     let enabled = match state.get() {
-        GameState::Playing => true,
+        GameState::Playing => !confirm_reset.0,
         _ => false,
     };
-    let undo_enabled = enabled
-        && level.can_undo()
+    let idle = enabled
         && match &*focus {
             Focus::Busy(_) => false,
             _ => true,
         };
+    let undo_enabled = idle && level.can_undo();
+    let redo_enabled = idle && level.can_redo();
     egui::SidePanel::right("in_game_ui")
         .resizable(false)
         .exact_width(IN_GAME_PANEL_WIDTH as _)
@@ -35,23 +65,257 @@ pub(super) fn in_game_ui(
                     ui.label(name);
                     ui.add_space(20.0);
                 }
+                let status = format!(
+                    "{} particles to collect\n{} manipulators left",
+                    level.progress.uncollected_particles(),
+                    level.progress.manipulators_left()
+                );
+                ui.label(egui::RichText::new(status).text_style(egui::TextStyle::Small));
+                let goals_left = level.progress.goals_left();
+                if !goals_left.is_empty() {
+                    let goals = goals_left
+                        .iter()
+                        .map(|coords| {
+                            format!("\nrow {}, column {}", coords.row + 1, coords.col + 1)
+                        })
+                        .collect::<String>();
+                    let goals = format!("{} goals left:{}", goals_left.len(), goals);
+                    ui.label(egui::RichText::new(goals).text_style(egui::TextStyle::Small));
+                }
+                if let Some(mut tutorial) = tutorial {
+                    if let Some(step) = tutorial.current() {
+                        ui.add_space(20.0);
+                        ui.label(
+                            egui::RichText::new(step.hint()).text_style(egui::TextStyle::Small),
+                        );
+                        if ui.button("SKIp").clicked() {
+                            tutorial.skip();
+                        }
+                    }
+                }
+                ui.add_space(20.0);
                 if ui
                     .add_enabled(undo_enabled, egui::Button::new("UndO"))
                     .clicked()
                 {
                     ev_undo.send(UndoMoves::Last);
                 }
+                if ui
+                    .add_enabled(redo_enabled, egui::Button::new("redO"))
+                    .clicked()
+                {
+                    ev_undo.send(UndoMoves::Redo);
+                }
                 if ui
                     .add_enabled(undo_enabled, egui::Button::new("reSeT"))
                     .clicked()
                 {
-                    ev_undo.send(UndoMoves::All);
+                    if level.is_reset_costly() {
+                        confirm_reset.0 = true;
+                    } else {
+                        ev_undo.send(UndoMoves::All);
+                    }
+                }
+                if ui
+                    .add_enabled(idle, egui::Button::new("CHeCKpOInT"))
+                    .clicked()
+                {
+                    level.set_checkpoint();
+                }
+                if ui
+                    .add_enabled(
+                        idle && level.checkpoint.is_some(),
+                        egui::Button::new("reSTOre"),
+                    )
+                    .clicked()
+                {
+                    ev_undo.send(UndoMoves::ToCheckpoint);
                 }
+                // NOTE: This copies the board as it is now, so it can be used to share a position
+                // partway through the level
+                if ui
+                    .add_enabled(idle, egui::Button::new("COpY COde"))
+                    .clicked()
+                {
+                    let message = match level.present.to_pbc1() {
+                        Ok(code) => {
+                            ui.output_mut(|output| output.copied_text = code);
+                            "Level code copied".to_string()
+                        }
+                        Err(err) => format!("Failed to encode the level: {}", err),
+                    };
+                    show_toast(ui, message);
+                }
+                add_toast(ui);
                 if ui.add_enabled(enabled, egui::Button::new("MenU")).clicked() {
                     next_state.set(GameState::MainMenu);
                 }
+                ui.add_space(20.0);
+                ui.checkbox(&mut input.move_buffer.enabled, "bUFFer");
+                ui.checkbox(&mut input.cycle_repeat.enabled, "repeAT");
+                if input.cycle_repeat.enabled {
+                    let mut interval = input.cycle_repeat.interval.as_millis() as u64;
+                    let slider =
+                        egui::Slider::new(&mut interval, CYCLE_INTERVAL_RANGE_MS).suffix(" MS");
+                    if ui.add(slider).changed() {
+                        input.cycle_repeat.interval = Duration::from_millis(interval);
+                    }
+                }
+                let mut click_neighbor = *input.mouse_gesture == MouseMoveGesture::ClickNeighbor;
+                if ui.checkbox(&mut click_neighbor, "CLICK neIGHbOr").changed() {
+                    *input.mouse_gesture = match click_neighbor {
+                        false => MouseMoveGesture::ClickArrow,
+                        true => MouseMoveGesture::ClickNeighbor,
+                    };
+                }
+                ui.add_enabled(enabled, egui::Checkbox::new(&mut sandbox.0, "prACTICe"));
+                ui.checkbox(&mut reduce_motion.0, "redUCe MOTIOn");
+                ui.checkbox(&mut highlight.0, "HInTS");
+                ui.checkbox(&mut auto_select.0, "AUTO SeLeCT");
+                if board_overflows(&level, &play_area) {
+                    ui.add_space(20.0);
+                    let mut pan = *camera_mode == CameraMode::Pan;
+                    if ui.checkbox(&mut pan, "pAN").changed() {
+                        *camera_mode = match pan {
+                            false => CameraMode::Fit,
+                            true => CameraMode::Pan,
+                        };
+                    }
+                    ui.add_space(10.0);
+                    let clicked = add_minimap(ui, &level.present, focus.coords(true));
+                    if let (CameraMode::Pan, Some(position)) = (*camera_mode, clicked) {
+                        ev_center.send(CenterCamera(position));
+                    }
+                }
             });
         });
 }
 
+// NOTE: Returns the clicked position, relative to the center of the board
+fn add_minimap(ui: &mut egui::Ui, board: &Board, focus: Option<BoardCoords>) -> Option<Vec2> {
+    let cell = f32::min(
+        MINIMAP_MAX_SIZE.x / board.dims.cols as f32,
+        MINIMAP_MAX_SIZE.y / board.dims.rows as f32,
+    );
+    let size = egui::vec2(board.dims.cols as f32, board.dims.rows as f32) * cell;
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+    let painter = ui.painter_at(rect);
+    let point = |col: usize, row: usize| rect.min + egui::vec2(col as f32, row as f32) * cell;
+    let cell_rect = |coords: BoardCoords| {
+        egui::Rect::from_min_size(point(coords.col, coords.row), egui::Vec2::splat(cell))
+    };
+
+    painter.rect_filled(rect, 0.0, MINIMAP_BACKGROUND_COLOR);
+    for (coords, tile) in board.tiles.iter() {
+        let color = tint_color(tile.tint);
+        let rect = cell_rect(coords);
+        painter.rect_filled(
+            rect.shrink(MINIMAP_TILE_GAP),
+            0.0,
+            color.gamma_multiply(0.35),
+        );
+        if tile.kind == TileKind::Collector {
+            painter.rect_stroke(rect.shrink(cell * 0.2), 0.0, (1.0, color));
+        }
+    }
+
+    for (coords, manipulator) in board.manipulators() {
+        let start = cell_rect(coords).center();
+        for direction in manipulator.emitters.directions() {
+            let Some(target) = manipulator.target(direction) else {
+                continue;
+            };
+            let end = match (target.kind, direction.orientation()) {
+                (BeamTargetKind::Piece, _) => cell_rect(target.coords).center(),
+                (BeamTargetKind::Border, Orientation::Vertical) => {
+                    egui::pos2(start.x, point(0, target.coords.row).y)
+                }
+                (BeamTargetKind::Border, Orientation::Horizontal) => {
+                    egui::pos2(point(target.coords.col, 0).x, start.y)
+                }
+            };
+            painter.line_segment([start, end], (1.0, MINIMAP_BEAM_COLOR));
+        }
+    }
+
+    for (orientation, borders) in [
+        (Orientation::Horizontal, &board.horz_borders),
+        (Orientation::Vertical, &board.vert_borders),
+    ] {
+        for (coords, border) in borders.iter() {
+            let start = point(coords.col, coords.row);
+            let end = match orientation {
+                Orientation::Horizontal => start + egui::vec2(cell, 0.0),
+                Orientation::Vertical => start + egui::vec2(0.0, cell),
+            };
+            let color = match border {
+                Border::Wall => MINIMAP_WALL_COLOR,
+                Border::Window => MINIMAP_WINDOW_COLOR,
+            };
+            painter.line_segment([start, end], (MINIMAP_BORDER_WIDTH, color));
+        }
+    }
+
+    for (coords, piece) in board.pieces.iter() {
+        let rect = cell_rect(coords);
+        match piece {
+            Piece::Particle(particle) => {
+                painter.circle_filled(rect.center(), cell * 0.3, tint_color(particle.tint));
+            }
+            Piece::Manipulator(_) => {
+                painter.rect_filled(rect.shrink(cell * 0.25), 0.0, MINIMAP_MANIPULATOR_COLOR);
+            }
+        }
+    }
+    if let Some(coords) = focus {
+        painter.rect_stroke(cell_rect(coords), 0.0, (1.5, MINIMAP_FOCUS_COLOR));
+    }
+
+    if !response.clicked() {
+        return None;
+    }
+    let offset = (response.interact_pointer_pos()? - rect.center()) / cell;
+    Some(Vec2::new(offset.x * TILE_WIDTH, -offset.y * TILE_HEIGHT))
+}
+
+fn show_toast(ui: &mut egui::Ui, message: String) {
+    let expires_at = ui.input(|input| input.time) + TOAST_DURATION;
+    ui.data_mut(|data| data.insert_temp(toast_id(), (message, expires_at)));
+}
+
+fn add_toast(ui: &mut egui::Ui) {
+    let Some((message, expires_at)) = ui.data(|data| data.get_temp::<(String, f64)>(toast_id()))
+    else {
+        return;
+    };
+    if ui.input(|input| input.time) >= expires_at {
+        ui.data_mut(|data| data.remove::<(String, f64)>(toast_id()));
+        return;
+    }
+    ui.label(egui::RichText::new(message).text_style(egui::TextStyle::Small));
+}
+
+fn toast_id() -> egui::Id {
+    egui::Id::new("in_game_toast")
+}
+
+fn tint_color(tint: Tint) -> egui::Color32 {
+    match tint {
+        Tint::White => egui::Color32::from_gray(0xe0),
+        Tint::Green => egui::Color32::from_rgb(0x40, 0xd0, 0x40),
+        Tint::Yellow => egui::Color32::from_rgb(0xf0, 0xd0, 0x20),
+        Tint::Red => egui::Color32::from_rgb(0xf0, 0x40, 0x40),
+    }
+}
+
 pub const IN_GAME_PANEL_WIDTH: u32 = 200;
+const TOAST_DURATION: f64 = 2.0;
+const MINIMAP_MAX_SIZE: Vec2 = Vec2::new(180.0, 180.0);
+const MINIMAP_TILE_GAP: f32 = 0.5;
+const MINIMAP_BORDER_WIDTH: f32 = 2.0;
+const MINIMAP_BACKGROUND_COLOR: egui::Color32 = egui::Color32::from_gray(0x10);
+const MINIMAP_BEAM_COLOR: egui::Color32 = egui::Color32::WHITE;
+const MINIMAP_WALL_COLOR: egui::Color32 = egui::Color32::from_gray(0xa0);
+const MINIMAP_WINDOW_COLOR: egui::Color32 = egui::Color32::from_rgb(0x80, 0xc0, 0xff);
+const MINIMAP_MANIPULATOR_COLOR: egui::Color32 = egui::Color32::from_gray(0xf8);
+const MINIMAP_FOCUS_COLOR: egui::Color32 = egui::Color32::from_rgb(0xfe, 0xd8, 0x00);
@@ -0,0 +1,18 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::engine::AssetLoadFailure;
+
+// NOTE: The game fonts may well be among the assets that failed, so this sticks to egui's own
+pub(super) fn load_failure_ui(mut egui_ctx: EguiContexts, failure: Res<AssetLoadFailure>) {
+    egui::CentralPanel::default().show(egui_ctx.ctx_mut(), |ui| {
+        ui.heading("Failed to load the game assets");
+        ui.add_space(10.0);
+        ui.label("The following assets could not be loaded:");
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for asset in failure.0.iter() {
+                ui.monospace(asset);
+            }
+        });
+    });
+}
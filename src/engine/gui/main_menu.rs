@@ -1,23 +1,65 @@
 use bevy::prelude::*;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+use bevy::utils::SystemTime;
 use bevy_egui::{egui, EguiContexts};
 
+use crate::engine::attract::AttractMode;
+use crate::engine::level::{Campaign, Progress};
 use crate::engine::GameState;
+use crate::model::{generate, Board, LevelMetadata};
+
+use super::confirm_quit::ConfirmQuit;
+use super::reset_progress::ConfirmProgressReset;
+use super::PlayLevel;
+
+// NOTE: Generating the daily puzzle means solving candidate boards until one is worth playing,
+// so it runs in the background and the level starts once it's ready.
+#[derive(Resource, Default)]
+pub struct DailyPuzzle {
+    pending: Option<(u64, Task<Board>)>,
+}
 
 pub(super) fn main_menu_ui(
     mut egui_ctx: EguiContexts,
+    campaign: Res<Campaign>,
+    progress: Res<Progress>,
+    mut attract: ResMut<AttractMode>,
+    mut confirm_quit: ResMut<ConfirmQuit>,
+    mut confirm_progress_reset: ResMut<ConfirmProgressReset>,
+    mut daily: ResMut<DailyPuzzle>,
     mut next_state: ResMut<NextState<GameState>>,
     mut exit: EventWriter<AppExit>,
 ) {
     let mut play_clicked = false;
+    let mut daily_clicked = false;
     let mut quit_clicked = false;
 
     egui::CentralPanel::default()
         .frame(egui::Frame::none().inner_margin(10.0))
         .show(egui_ctx.ctx_mut(), |ui| {
+            if confirm_quit.pending || (*confirm_progress_reset != ConfirmProgressReset::None) {
+                ui.disable();
+            }
             ui.vertical_centered(|ui| {
                 ui.heading("pArTICLZ");
+                ui.label(format!(
+                    "{} / {} SOLVeD",
+                    progress.solved_count(0..campaign.levels.len()),
+                    campaign.levels.len()
+                ));
+                ui.add_space(20.0);
                 play_clicked = ui.button("pLAY").clicked();
+                daily_clicked = ui
+                    .add_enabled(daily.pending.is_none(), egui::Button::new("dAILY"))
+                    .clicked();
                 quit_clicked = ui.button("QUIT").clicked();
+                ui.add_space(20.0);
+                ui.checkbox(&mut attract.enabled, "deMO");
+                ui.checkbox(&mut confirm_quit.enabled, "COnFIrM QUIT");
+                ui.add_space(20.0);
+                if ui.button("reSeT prOGreSS").clicked() {
+                    *confirm_progress_reset = ConfirmProgressReset::First;
+                }
             });
         });
 
@@ -25,7 +67,50 @@ pub(super) fn main_menu_ui(
         next_state.set(GameState::ClassicLevelSelect);
     }
 
+    if daily_clicked {
+        let day = days_since_epoch();
+        let task = AsyncComputeTaskPool::get().spawn(async move { generate(day) });
+        daily.pending = Some((day, task));
+    }
+
     if quit_clicked {
-        exit.send(AppExit::Success);
+        if confirm_quit.enabled {
+            confirm_quit.pending = true;
+        } else {
+            exit.send(AppExit::Success);
+        }
+    }
+}
+
+pub(super) fn play_daily_puzzle(
+    mut daily: ResMut<DailyPuzzle>,
+    mut ev_play: EventWriter<PlayLevel>,
+) {
+    if !daily
+        .pending
+        .as_ref()
+        .is_some_and(|(_, task)| task.is_finished())
+    {
+        return;
     }
+    let (day, task) = daily.pending.take().unwrap();
+    let metadata = LevelMetadata {
+        name: Some(format!("Daily #{}", day)),
+        ..Default::default()
+    };
+    ev_play.send(PlayLevel(block_on(task), metadata));
+}
+
+pub(super) fn cancel_daily_puzzle(mut daily: ResMut<DailyPuzzle>) {
+    daily.pending = None;
 }
+
+// NOTE: Days are counted in UTC, so every player gets the same puzzle on the same day
+fn days_since_epoch() -> u64 {
+    let elapsed = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    elapsed.as_secs() / SECS_PER_DAY
+}
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
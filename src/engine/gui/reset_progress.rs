@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::engine::level::Progress;
+
+use super::add_button;
+
+// NOTE: Wiping progress can't be undone, so it takes two confirmations
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmProgressReset {
+    #[default]
+    None,
+    First,
+    Second,
+}
+
+pub(super) fn confirm_progress_reset_ui(
+    mut egui_ctx: EguiContexts,
+    mut confirm: ResMut<ConfirmProgressReset>,
+    mut progress: ResMut<Progress>,
+) {
+    let (title, message, action) = match *confirm {
+        ConfirmProgressReset::None => return,
+        ConfirmProgressReset::First => (
+            "reSeT prOGreSS?",
+            "Solved levels, best moves and stars will be lost",
+            "reSeT",
+        ),
+        ConfirmProgressReset::Second => ("ARe YOU SUre?", "This can't be undone", "YeS, reSeT"),
+    };
+    let title = egui::RichText::new(title).text_style(egui::TextStyle::Body);
+
+    egui::Window::new(title)
+        .id(egui::Id::new("confirm_progress_reset"))
+        .resizable(false)
+        .movable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::splat(0.0))
+        .min_width(360.0)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.vertical_centered(|ui| {
+                let message = egui::RichText::new(message).text_style(egui::TextStyle::Small);
+                ui.label(message);
+                ui.columns(2, |ui| {
+                    if add_button(&mut ui[0], action).clicked() {
+                        *confirm = match *confirm {
+                            ConfirmProgressReset::First => ConfirmProgressReset::Second,
+                            _ => {
+                                progress.clear();
+                                ConfirmProgressReset::None
+                            }
+                        };
+                    }
+                    if add_button(&mut ui[1], "CANCeL").clicked() {
+                        *confirm = ConfirmProgressReset::None;
+                    }
+                });
+            });
+        });
+}
+
+pub(super) fn dismiss_progress_reset_confirmation(mut confirm: ResMut<ConfirmProgressReset>) {
+    *confirm = ConfirmProgressReset::None;
+}
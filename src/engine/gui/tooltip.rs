@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::engine::focus::Focus;
+use crate::engine::level::Level;
+use crate::engine::MainCamera;
+use crate::model::{BeamTargetKind, Piece};
+
+pub(super) fn manipulator_tooltip_ui(
+    In(focus): In<Focus>,
+    mut egui_ctx: EguiContexts,
+    window: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    level: Res<Level>,
+    q_xform: Query<&Transform>,
+) {
+    if let Focus::Busy(_) = focus {
+        return;
+    }
+    let ctx = egui_ctx.ctx_mut();
+    if ctx.is_pointer_over_area() {
+        return;
+    }
+
+    let (camera, camera_xform) = camera.single();
+    let Some(pos) = window
+        .single()
+        .cursor_position()
+        .and_then(|pos| camera.viewport_to_world_2d(camera_xform, pos))
+    else {
+        return;
+    };
+    let Some((coords, _)) = level.coords_at_pos(pos, &q_xform) else {
+        return;
+    };
+    let Ok(Some(Piece::Manipulator(manipulator))) = level.present.pieces.get_checked(coords) else {
+        return;
+    };
+
+    let directions = manipulator.emitters.directions();
+    let emitters = directions
+        .iter()
+        .map(|direction| format!("{:?}", direction))
+        .collect::<Vec<_>>()
+        .join(" + ");
+    let supported = level.present.pieces_supported_by(coords).len();
+
+    egui::show_tooltip_at_pointer(
+        ctx,
+        egui::LayerId::background(),
+        egui::Id::new("manipulator_tooltip"),
+        |ui| {
+            ui.label(egui::RichText::new(emitters).text_style(egui::TextStyle::Small));
+            for direction in directions.iter() {
+                let Some(target) = manipulator.target(direction) else {
+                    continue;
+                };
+                let kind = match target.kind {
+                    BeamTargetKind::Piece => "piece",
+                    BeamTargetKind::Border => "border",
+                };
+                let text = format!("{:?}: {} at {:?}", direction, kind, target.coords);
+                ui.label(egui::RichText::new(text).text_style(egui::TextStyle::Small));
+            }
+            if supported > 0 {
+                let text = format!("supports {} pieces", supported);
+                ui.label(egui::RichText::new(text).text_style(egui::TextStyle::Small));
+            }
+        },
+    );
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use bevy::app::Plugin;
 use bevy::ecs::event::{Event, EventReader, EventWriter};
 use bevy::ecs::query::With;
@@ -10,10 +12,12 @@ use bevy::prelude::*;
 use bevy::render::camera::Camera;
 use bevy::transform::components::{GlobalTransform, Transform};
 use bevy::window::{PrimaryWindow, Window};
+use bevy_egui::EguiContexts;
 
-use crate::model::{BoardCoords, Direction, Piece};
+use crate::model::{Board, BoardCoords, Direction, Piece};
 
 use super::focus::{focus_direction_for_offset, get_focus, Focus};
+use super::gui::UndoMoves;
 use super::level::Level;
 use super::manipulator::is_offset_inside_manipulator;
 use super::{GameplaySet, MainCamera};
@@ -34,12 +38,105 @@ pub enum SelectManipulatorEvent {
 #[derive(Debug, Event)]
 pub struct MoveManipulatorEvent(pub Direction);
 
+#[derive(Resource, Debug, Default)]
+pub struct MoveBuffer {
+    pub enabled: bool,
+    direction: Option<Direction>,
+}
+
+// NOTE: Holding a cycling key only repeats when this is enabled, after the initial delay
+#[derive(Resource, Debug)]
+pub struct CycleRepeat {
+    pub enabled: bool,
+    pub delay: Duration,
+    pub interval: Duration,
+}
+
+// NOTE: Either way, the selected manipulator can also be dragged in the direction of the move
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MouseMoveGesture {
+    #[default]
+    ClickArrow,
+    ClickNeighbor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CycleDirection {
+    Previous,
+    Next,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistoryStep {
+    Undo,
+    Redo,
+}
+
+#[derive(Debug)]
+struct CycleHold {
+    direction: CycleDirection,
+    remaining: Duration,
+}
+
+impl MoveBuffer {
+    pub fn push(&mut self, direction: Direction) {
+        if self.enabled {
+            self.direction = Some(direction);
+        }
+    }
+
+    pub fn take(&mut self) -> Option<Direction> {
+        self.direction.take().filter(|_| self.enabled)
+    }
+}
+
+impl Default for CycleRepeat {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay: DEFAULT_CYCLE_DELAY,
+            interval: DEFAULT_CYCLE_INTERVAL,
+        }
+    }
+}
+
+impl HistoryStep {
+    fn keys(self) -> [KeyCode; 1] {
+        match self {
+            Self::Undo => [KeyCode::KeyZ],
+            Self::Redo => [KeyCode::KeyY],
+        }
+    }
+}
+
+impl CycleDirection {
+    fn keys(self) -> [KeyCode; 2] {
+        match self {
+            Self::Previous => [KeyCode::KeyQ, KeyCode::PageUp],
+            Self::Next => [KeyCode::KeyE, KeyCode::PageDown],
+        }
+    }
+
+    fn event(self) -> SelectManipulatorEvent {
+        match self {
+            Self::Previous => SelectManipulatorEvent::Previous,
+            Self::Next => SelectManipulatorEvent::Next,
+        }
+    }
+}
+
 fn process_keyboard_input(
     In(focus): In<Focus>,
     mut keyboard_events: EventReader<KeyboardInput>,
     mut keyboard_input: Local<ButtonInput<KeyCode>>,
     mut ev_select_manipulator: EventWriter<SelectManipulatorEvent>,
     mut ev_move_manipulator: EventWriter<MoveManipulatorEvent>,
+    mut ev_undo: EventWriter<UndoMoves>,
+    mut move_buffer: ResMut<MoveBuffer>,
+    level: Res<Level>,
+    cycle_repeat: Res<CycleRepeat>,
+    mut cycle_hold: Local<Option<CycleHold>>,
+    time: Res<Time>,
 ) {
     keyboard_input.clear();
     for event in keyboard_events.read() {
@@ -50,35 +147,78 @@ fn process_keyboard_input(
     }
 
     if let Focus::Busy(_) = focus {
+        if let Some(direction) = pressed_direction(&keyboard_input) {
+            move_buffer.push(direction);
+        }
         return;
     }
+    // NOTE: Anything still buffered at this point was pressed while there was nothing to move
+    move_buffer.take();
 
-    if keyboard_input.any_just_pressed([KeyCode::KeyQ, KeyCode::PageUp]) {
-        ev_select_manipulator.send(SelectManipulatorEvent::Previous);
-    } else if keyboard_input.any_just_pressed([KeyCode::KeyE, KeyCode::PageDown]) {
-        ev_select_manipulator.send(SelectManipulatorEvent::Next);
+    match pressed_history_step(&keyboard_input) {
+        Some(HistoryStep::Undo) if level.can_undo() => {
+            ev_undo.send(UndoMoves::Last);
+            return;
+        }
+        Some(HistoryStep::Redo) if level.can_redo() => {
+            ev_undo.send(UndoMoves::Redo);
+            return;
+        }
+        _ => (),
+    }
+
+    if let Some(direction) = pressed_cycle_direction(&keyboard_input) {
+        ev_select_manipulator.send(direction.event());
+        *cycle_hold = Some(CycleHold {
+            direction,
+            remaining: cycle_repeat.delay,
+        });
+    } else if let Some(mut hold) = cycle_hold.take() {
+        if cycle_repeat.enabled && keyboard_input.any_pressed(hold.direction.keys()) {
+            hold.remaining = hold.remaining.saturating_sub(time.delta());
+            if hold.remaining.is_zero() {
+                ev_select_manipulator.send(hold.direction.event());
+                hold.remaining = cycle_repeat.interval;
+            }
+            *cycle_hold = Some(hold);
+        }
     }
 
     let Focus::Selected(_, directions) = focus else {
         return;
     };
 
-    if keyboard_input.any_just_pressed([KeyCode::KeyW, KeyCode::ArrowUp]) {
-        if directions.contains(Direction::Up) {
-            ev_move_manipulator.send(MoveManipulatorEvent(Direction::Up));
+    if let Some(direction) = pressed_direction(&keyboard_input) {
+        if directions.contains(direction) {
+            ev_move_manipulator.send(MoveManipulatorEvent(direction));
         }
+    }
+}
+
+// NOTE: Modifiers are ignored, so the usual Ctrl+Z and Ctrl+Y work the same as the bare keys
+fn pressed_history_step(keyboard_input: &ButtonInput<KeyCode>) -> Option<HistoryStep> {
+    [HistoryStep::Undo, HistoryStep::Redo]
+        .into_iter()
+        .find(|step| keyboard_input.any_just_pressed(step.keys()))
+}
+
+fn pressed_cycle_direction(keyboard_input: &ButtonInput<KeyCode>) -> Option<CycleDirection> {
+    [CycleDirection::Previous, CycleDirection::Next]
+        .into_iter()
+        .find(|direction| keyboard_input.any_just_pressed(direction.keys()))
+}
+
+fn pressed_direction(keyboard_input: &ButtonInput<KeyCode>) -> Option<Direction> {
+    if keyboard_input.any_just_pressed([KeyCode::KeyW, KeyCode::ArrowUp]) {
+        Some(Direction::Up)
     } else if keyboard_input.any_just_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]) {
-        if directions.contains(Direction::Left) {
-            ev_move_manipulator.send(MoveManipulatorEvent(Direction::Left));
-        }
+        Some(Direction::Left)
     } else if keyboard_input.any_just_pressed([KeyCode::KeyS, KeyCode::ArrowDown]) {
-        if directions.contains(Direction::Down) {
-            ev_move_manipulator.send(MoveManipulatorEvent(Direction::Down));
-        }
+        Some(Direction::Down)
     } else if keyboard_input.any_just_pressed([KeyCode::KeyD, KeyCode::ArrowRight]) {
-        if directions.contains(Direction::Right) {
-            ev_move_manipulator.send(MoveManipulatorEvent(Direction::Right));
-        }
+        Some(Direction::Right)
+    } else {
+        None
     }
 }
 
@@ -86,10 +226,13 @@ fn process_mouse_input(
     In(focus): In<Focus>,
     mut mouse_events: EventReader<MouseButtonInput>,
     mut mouse_input: Local<ButtonInput<MouseButton>>,
+    mut drag_start: Local<Option<(BoardCoords, Vec2)>>,
     window: Query<&Window, With<PrimaryWindow>>,
     camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
     level: Res<Level>,
+    gesture: Res<MouseMoveGesture>,
     q_xform: Query<&Transform>,
+    mut egui_ctx: EguiContexts,
     mut ev_select_manipulator: EventWriter<SelectManipulatorEvent>,
     mut ev_move_manipulator: EventWriter<MoveManipulatorEvent>,
 ) {
@@ -102,42 +245,115 @@ fn process_mouse_input(
     }
 
     if let Focus::Busy(_) = focus {
+        *drag_start = None;
         return;
     }
 
-    if mouse_input.just_pressed(MouseButton::Left) {
+    let cursor_pos = || {
         let (camera, xform) = camera.single();
         let window = window.single();
-        let coords_and_offset = window
+        window
             .cursor_position()
             .and_then(|pos| camera.viewport_to_world_2d(xform, pos))
-            .and_then(|pos| level.coords_at_pos(pos, &q_xform));
+    };
+
+    if mouse_input.just_released(MouseButton::Left) {
+        let Some((start_coords, start_pos)) = drag_start.take() else {
+            return;
+        };
+        let Focus::Selected(focus_coords, directions) = focus else {
+            return;
+        };
+        let Some(end_pos) = cursor_pos() else {
+            return;
+        };
+        if start_coords != focus_coords {
+            return;
+        }
+        if let Some(direction) = drag_direction(end_pos - start_pos) {
+            if directions.contains(direction) {
+                ev_move_manipulator.send(MoveManipulatorEvent(direction));
+            }
+        }
+        return;
+    }
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        let Some(pos) = cursor_pos() else {
+            return;
+        };
+        let coords_and_offset = level.coords_at_pos(pos, &q_xform);
         if let Some((coords, offset)) = coords_and_offset {
             if let Focus::Selected(focus_coords, directions) = focus {
+                let direction = match *gesture {
+                    MouseMoveGesture::ClickArrow if coords == focus_coords => {
+                        focus_direction_for_offset(offset)
+                    }
+                    MouseMoveGesture::ClickArrow => None,
+                    MouseMoveGesture::ClickNeighbor => {
+                        neighbor_direction(&level.present, focus_coords, coords)
+                    }
+                };
+                if let Some(direction) = direction {
+                    // NOTE: Clicking a neighbor the manipulator can't move to selects it instead
+                    if directions.contains(direction) {
+                        ev_move_manipulator.send(MoveManipulatorEvent(direction));
+                        return;
+                    }
+                }
                 if coords == focus_coords {
-                    if let Some(direction) = focus_direction_for_offset(offset) {
-                        if directions.contains(direction) {
-                            ev_move_manipulator.send(MoveManipulatorEvent(direction));
-                        }
+                    if direction.is_none() && is_offset_inside_manipulator(offset) {
+                        *drag_start = Some((coords, pos));
                     }
                     return;
                 }
             }
-            if let Some(Piece::Manipulator(_)) = level.present.pieces.get(coords) {
+            if let Ok(Some(Piece::Manipulator(_))) = level.present.pieces.get_checked(coords) {
                 if is_offset_inside_manipulator(offset) {
                     ev_select_manipulator.send(SelectManipulatorEvent::AtCoords(coords));
+                    *drag_start = Some((coords, pos));
                 }
             } else {
                 ev_select_manipulator.send(SelectManipulatorEvent::Deselect);
             }
+        } else if !egui_ctx.ctx_mut().is_pointer_over_area() {
+            ev_select_manipulator.send(SelectManipulatorEvent::Deselect);
         }
     }
 }
 
+fn neighbor_direction(board: &Board, from: BoardCoords, to: BoardCoords) -> Option<Direction> {
+    board
+        .neighbors(from)
+        .find(|&(_, coords)| coords == to)
+        .map(|(direction, _)| direction)
+}
+
+fn drag_direction(delta: Vec2) -> Option<Direction> {
+    if delta.length_squared() < DRAG_THRESHOLD_SQUARED {
+        return None;
+    }
+    let direction = if delta.x.abs() > delta.y.abs() {
+        match delta.x > 0.0 {
+            false => Direction::Left,
+            true => Direction::Right,
+        }
+    } else {
+        match delta.y > 0.0 {
+            false => Direction::Down,
+            true => Direction::Up,
+        }
+    };
+    Some(direction)
+}
+
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SelectManipulatorEvent>()
             .add_event::<MoveManipulatorEvent>()
+            .init_resource::<MoveBuffer>()
+            .init_resource::<CycleRepeat>()
+            .init_resource::<MouseMoveGesture>()
             .configure_sets(FixedPreUpdate, InputSet.in_set(GameplaySet))
             .add_systems(
                 FixedPreUpdate,
@@ -149,3 +365,8 @@ impl Plugin for InputPlugin {
             );
     }
 }
+
+const DRAG_THRESHOLD_SQUARED: f32 = 256.0;
+const DEFAULT_CYCLE_DELAY: Duration = Duration::from_millis(400);
+const DEFAULT_CYCLE_INTERVAL: Duration = Duration::from_millis(150);
+pub const CYCLE_INTERVAL_RANGE_MS: std::ops::RangeInclusive<u64> = 50..=500;
@@ -7,11 +7,13 @@ use bevy::prelude::*;
 use bevy::transform::components::Transform;
 
 use crate::model::{
-    Board, BoardCoords, Direction, GridMap, GridSet, LevelCampaign, LevelMetadata, LevelProgress,
-    Piece, Tile, TileKind,
+    Board, BoardCoords, CampaignProgress, Direction, Emitters, GridMap, GridSet, LevelCampaign,
+    LevelMetadata, LevelProgress, Piece, Tint,
 };
 
-use super::border::{spawn_horz_border, spawn_vert_border};
+use super::beam::spawn_beam_overlaps;
+use super::border::{spawn_corner_border, spawn_horz_border, spawn_vert_border};
+use super::camera::PlayArea;
 use super::focus::spawn_focus;
 use super::manipulator::spawn_manipulator;
 use super::particle::spawn_particle;
@@ -24,12 +26,17 @@ pub struct Level {
     pub present: Board,
     pub future: Board,
     pub past: Vec<Board>,
+    pub undone: Vec<Board>,
+    pub checkpoint: Option<Board>,
+    checkpoint_past: Vec<Board>,
     pub parent: Option<Entity>,
     pub tiles: GridMap<Entity>,
     pub horz_borders: GridMap<Entity>,
     pub vert_borders: GridMap<Entity>,
+    pub corner_borders: GridMap<Entity>,
     pub pieces: GridMap<Entity>,
     pub progress: LevelProgress,
+    pub new_best: bool,
 }
 
 #[derive(Bundle, Default)]
@@ -40,6 +47,12 @@ struct BoardBundle {
 #[derive(Resource, Deref)]
 pub struct Campaign(pub LevelCampaign);
 
+#[derive(Resource, Deref, DerefMut, Default)]
+pub struct Progress(pub CampaignProgress);
+
+#[derive(Resource, Debug, Default)]
+pub struct SandboxMode(pub bool);
+
 impl Level {
     pub fn new(board: Board, metadata: LevelMetadata) -> Self {
         let present = board;
@@ -47,19 +60,25 @@ impl Level {
         let tiles = GridMap::like(&present.tiles);
         let horz_borders = GridMap::like(&present.horz_borders);
         let vert_borders = GridMap::like(&present.vert_borders);
+        let corner_borders = GridMap::like(&present.corner_borders);
         let pieces = GridMap::like(&present.pieces);
-        let progress = LevelProgress::new(&present);
+        let progress = LevelProgress::with_goals(&present, &metadata.goals);
         Self {
             metadata,
             present,
             future,
             past: vec![],
+            undone: vec![],
+            checkpoint: None,
+            checkpoint_past: vec![],
             parent: None,
             tiles,
             horz_borders,
             vert_borders,
+            corner_borders,
             pieces,
             progress,
+            new_best: false,
         }
     }
 
@@ -95,21 +114,21 @@ impl Level {
                 );
             }
 
+            self.corner_borders.clear();
+            for (coords, border) in self.present.corner_borders.iter() {
+                self.corner_borders.set(
+                    coords,
+                    spawn_corner_border(parent, border, coords, &assets.borders, &|_| ()),
+                );
+            }
+
+            for coords in self.present.dims.iter() {
+                spawn_beam_overlaps(parent, coords);
+            }
+
             self.pieces.clear();
             for (coords, piece) in self.present.pieces.iter() {
-                let entity = match piece {
-                    Piece::Particle(particle) => {
-                        spawn_particle(parent, particle, coords, &assets.particles, &|_| ())
-                    }
-                    Piece::Manipulator(manipulator) => spawn_manipulator(
-                        parent,
-                        manipulator,
-                        coords,
-                        &self.present,
-                        &assets,
-                        &|_| (),
-                    ),
-                };
+                let entity = spawn_piece(parent, piece, coords, &self.present, assets);
                 self.pieces.set(coords, entity);
             }
 
@@ -117,6 +136,52 @@ impl Level {
         });
     }
 
+    // NOTE: Rather than respawning the whole board, piece entities are kept where the piece didn't
+    // change, reused where an identical piece ended up elsewhere, and only the rest are despawned
+    // or spawned. This keeps idle animations going and makes deep undos cheap.
+    pub fn reconcile_pieces(
+        &mut self,
+        spawned: &Board,
+        commands: &mut Commands,
+        assets: &GameAssets,
+    ) {
+        let parent = self.parent.unwrap();
+        let mut stale = Vec::new();
+        for coords in spawned.dims.iter() {
+            let Some(look) = piece_look(spawned, coords) else {
+                continue;
+            };
+            if piece_look(&self.present, coords) != Some(look) {
+                stale.push((look, self.pieces.take(coords).unwrap()));
+            }
+        }
+
+        for (coords, piece) in self.present.pieces.iter() {
+            if self.pieces.get(coords).is_some() {
+                continue;
+            }
+            let look = piece_look(&self.present, coords);
+            let entity = match stale
+                .iter()
+                .position(|(stale_look, _)| Some(*stale_look) == look)
+            {
+                Some(idx) => stale.swap_remove(idx).1,
+                None => {
+                    let mut entity = None;
+                    commands.entity(parent).with_children(|parent| {
+                        entity = Some(spawn_piece(parent, piece, coords, &self.present, assets));
+                    });
+                    entity.unwrap()
+                }
+            };
+            self.pieces.set(coords, entity);
+        }
+
+        for (_, entity) in stale {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
     pub fn despawn(&mut self, commands: &mut Commands) {
         commands
             .entity(self.parent.take().unwrap())
@@ -129,15 +194,11 @@ impl Level {
         q_xform: &Query<&Transform>,
     ) -> Option<(BoardCoords, Vec2)> {
         let xform = q_xform.get(self.parent.unwrap()).unwrap();
-        let origin = xform.translation.truncate();
-        let pos = pos - origin;
+        let pos = board_local_pos(xform, pos);
         let coords = BoardCoords::from_xy(pos)?;
-        if self.present.dims.contains(coords) {
-            let center = coords.to_xy();
-            Some((coords, pos - center))
-        } else {
-            None
-        }
+        self.present.pieces.get_checked(coords).ok()?;
+        let center = coords.to_xy();
+        Some((coords, pos - center))
     }
 
     pub fn update_present(&mut self) {
@@ -148,39 +209,106 @@ impl Level {
         !self.past.is_empty()
     }
 
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    // NOTE: Undone moves don't count, only the ones that led to the current board
+    pub fn move_count(&self) -> usize {
+        self.past.len()
+    }
+
+    pub fn is_reset_costly(&self) -> bool {
+        // NOTE: Resetting after a single move is no different from undoing it
+        self.past.len() > 1
+    }
+
+    // NOTE: A board the level ended on can't be redone into, since its outcome is only decided when
+    // the move that led to it finishes
     pub fn undo(&mut self) {
         if let Some(board) = self.past.pop() {
+            match self.progress.outcome {
+                None => self.undone.push(self.present.clone()),
+                Some(_) => self.undone.clear(),
+            }
+            self.present.copy_state_from(&board);
+            self.future.copy_state_from(&self.present);
+            self.progress = LevelProgress::with_goals(&self.present, &self.metadata.goals);
+            self.new_best = false;
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(board) = self.undone.pop() {
+            self.past.push(self.present.clone());
             self.present.copy_state_from(&board);
             self.future.copy_state_from(&self.present);
-            self.progress = LevelProgress::new(&self.present);
+            self.progress = LevelProgress::with_goals(&self.present, &self.metadata.goals);
         }
     }
 
+    pub fn set_checkpoint(&mut self) {
+        self.checkpoint = Some(self.present.clone());
+        self.checkpoint_past = self.past.clone();
+    }
+
+    // NOTE: The undo history is restored along with the board, even if it was undone since the
+    // checkpoint was set, so the moves that led up to the checkpoint still count.
+    pub fn restore_checkpoint(&mut self) {
+        let Some(checkpoint) = self.checkpoint.as_ref() else {
+            return;
+        };
+        self.past.clone_from(&self.checkpoint_past);
+        self.undone.clear();
+        self.present.copy_state_from(checkpoint);
+        self.future.copy_state_from(&self.present);
+        self.progress = LevelProgress::with_goals(&self.present, &self.metadata.goals);
+    }
+
     pub fn reset(&mut self) {
         self.past.truncate(1);
         self.undo();
+        self.undone.clear();
     }
 
     pub fn prepare_move(&mut self, move_set: &GridSet, direction: Direction) {
         self.past.push(self.present.clone());
+        self.undone.clear();
         self.future.move_pieces(&move_set, direction);
         self.future.retarget_beams();
     }
 
     pub fn move_piece(&mut self, from_coords: BoardCoords, to_coords: BoardCoords) {
         let entity = self.pieces.take(from_coords).unwrap();
-        self.pieces.set(to_coords, entity);
-        if let Some(Piece::Particle(_)) = self.present.pieces.get(to_coords) {
-            if let Some(Tile {
-                kind: TileKind::Collector,
-                ..
-            }) = self.present.tiles.get(to_coords)
-            {
-                self.progress.particle_collected();
-            }
+        if let Err(err) = self.pieces.set_checked(to_coords, entity) {
+            warn!("Piece moved off the board: {}", err);
+        }
+        if self.present.is_collected(to_coords) {
+            self.progress.particle_collected(to_coords);
         }
     }
 
+    // NOTE: Used when a piece changes its look in place, like a particle recolored by the tile it
+    // landed on
+    pub fn respawn_piece(
+        &mut self,
+        coords: BoardCoords,
+        commands: &mut Commands,
+        assets: &GameAssets,
+    ) {
+        let piece = self.present.pieces.get(coords).unwrap();
+        if let Some(entity) = self.pieces.take(coords) {
+            commands.entity(entity).despawn_recursive();
+        }
+        let mut entity = None;
+        commands
+            .entity(self.parent.unwrap())
+            .with_children(|parent| {
+                entity = Some(spawn_piece(parent, piece, coords, &self.present, assets));
+            });
+        self.pieces.set(coords, entity.unwrap());
+    }
+
     pub fn remove_piece(&mut self, coords: BoardCoords, commands: &mut Commands) {
         let outcome = self
             .progress
@@ -199,25 +327,98 @@ impl Level {
     }
 }
 
+impl Progress {
+    pub fn load() -> Self {
+        match read_progress() {
+            Ok(Some(data)) => Self(CampaignProgress::decode(&data)),
+            Ok(None) => Self::default(),
+            Err(err) => {
+                warn!("Failed to load progress: {}", err);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = write_progress(&self.encode()) {
+            warn!("Failed to save progress: {}", err);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.0 = CampaignProgress::default();
+        if let Err(err) = delete_progress() {
+            warn!("Failed to delete progress: {}", err);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_progress() -> Result<Option<String>, String> {
+    match std::fs::read_to_string(PROGRESS_PATH) {
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_progress(data: &str) -> Result<(), String> {
+    std::fs::write(PROGRESS_PATH, data).map_err(|err| err.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn delete_progress() -> Result<(), String> {
+    match std::fs::remove_file(PROGRESS_PATH) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// NOTE: There is no file system on the web, so progress is kept in the browser's local storage
+// instead, under the same name as the file
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Result<web_sys::Storage, String> {
+    web_sys::window()
+        .ok_or("no browser window")?
+        .local_storage()
+        .map_err(|err| format!("{:?}", err))?
+        .ok_or_else(|| "no local storage".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_progress() -> Result<Option<String>, String> {
+    local_storage()?
+        .get_item(PROGRESS_PATH)
+        .map_err(|err| format!("{:?}", err))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_progress(data: &str) -> Result<(), String> {
+    local_storage()?
+        .set_item(PROGRESS_PATH, data)
+        .map_err(|err| format!("{:?}", err))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn delete_progress() -> Result<(), String> {
+    local_storage()?
+        .remove_item(PROGRESS_PATH)
+        .map_err(|err| format!("{:?}", err))
+}
+
 pub fn spawn_board<'c>(
     board: &Board,
     parent_area_size: Vec2,
     commands: &'c mut Commands,
     mutator: &impl Fn(&mut EntityCommands),
 ) -> EntityCommands<'c> {
-    let board_size = Vec2::new(
-        board.dims.cols as f32 * TILE_WIDTH,
-        board.dims.rows as f32 * TILE_HEIGHT,
-    )
-    .abs();
-    let mut board_origin = (parent_area_size - board_size) / 2.0;
-    board_origin.y = -board_origin.y;
-
     commands
         .spawn(BoardBundle {
             spatial: SpatialBundle {
                 transform: Transform {
-                    translation: board_origin.extend(0.0),
+                    translation: board_origin(board, parent_area_size).extend(0.0),
                     ..Default::default()
                 },
                 ..Default::default()
@@ -226,6 +427,84 @@ pub fn spawn_board<'c>(
         .mutate(mutator)
 }
 
+fn spawn_piece(
+    parent: &mut ChildBuilder,
+    piece: &Piece,
+    coords: BoardCoords,
+    board: &Board,
+    assets: &GameAssets,
+) -> Entity {
+    match piece {
+        Piece::Particle(particle) => {
+            let collected = board.is_collected(coords);
+            spawn_particle(
+                parent,
+                particle,
+                coords,
+                collected,
+                &assets.particles,
+                &|_| (),
+            )
+        }
+        Piece::Manipulator(manipulator) => spawn_manipulator(
+            parent,
+            manipulator,
+            coords,
+            board,
+            &assets.manipulators,
+            &assets.beams,
+            &|_| (),
+        ),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PieceLook {
+    Particle(Tint, bool),
+    Manipulator(Emitters),
+}
+
+// NOTE: An entity can stand in for any piece that looks the same, since it doesn't hold any other
+// state of its own. The coords and beams are updated after the pieces are reconciled.
+fn piece_look(board: &Board, coords: BoardCoords) -> Option<PieceLook> {
+    match board.pieces.get(coords)? {
+        Piece::Particle(particle) => Some(PieceLook::Particle(
+            particle.tint,
+            board.is_collected(coords),
+        )),
+        Piece::Manipulator(manipulator) => Some(PieceLook::Manipulator(manipulator.emitters)),
+    }
+}
+
+pub fn board_origin(board: &Board, parent_area_size: Vec2) -> Vec2 {
+    let mut origin = (parent_area_size - board_size(board)) / 2.0;
+    origin.y = -origin.y;
+    origin
+}
+
+pub fn board_size(board: &Board) -> Vec2 {
+    Vec2::new(
+        board.dims.cols as f32 * TILE_WIDTH,
+        board.dims.rows as f32 * TILE_HEIGHT,
+    )
+}
+
+pub fn update_board_layout(
+    level: Res<Level>,
+    play_area: Res<PlayArea>,
+    mut q_xform: Query<&mut Transform>,
+) {
+    if !play_area.is_changed() {
+        return;
+    }
+    let Some(parent) = level.parent else {
+        return;
+    };
+    if let Ok(mut xform) = q_xform.get_mut(parent) {
+        xform.translation = board_origin(&level.present, play_area.0).extend(0.0);
+    }
+}
+
 pub fn update_piece_coords(
     level: Res<Level>,
     mut q_coords: Query<&mut BoardCoordsHolder>,
@@ -249,3 +528,38 @@ pub fn update_piece_coords(
         }
     }
 }
+
+// NOTE: Undoes the board's translation and scale, so the result is in unscaled tile geometry
+fn board_local_pos(xform: &Transform, pos: Vec2) -> Vec2 {
+    (pos - xform.translation.truncate()) / xform.scale.truncate()
+}
+
+const PROGRESS_PATH: &str = "particlz-progress.txt";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_local_pos_scaled() {
+        let xform = Transform::from_xyz(100.0, -50.0, 0.0).with_scale(Vec3::new(2.0, 2.0, 1.0));
+        let coords = BoardCoords::new(1, 2);
+        let offset = Vec2::new(5.0, -3.0);
+        let pos = xform.translation.truncate() + (coords.to_xy() + offset) * 2.0;
+
+        let local = board_local_pos(&xform, pos);
+        assert_eq!(BoardCoords::from_xy(local), Some(coords));
+        assert!((local - coords.to_xy() - offset).length() < 1e-3);
+    }
+
+    #[test]
+    fn board_local_pos_unscaled() {
+        let xform = Transform::from_xyz(-20.0, 30.0, 0.0);
+        let coords = BoardCoords::new(3, 0);
+        let pos = xform.translation.truncate() + coords.to_xy();
+        assert_eq!(
+            BoardCoords::from_xy(board_local_pos(&xform, pos)),
+            Some(coords)
+        );
+    }
+}
@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use bevy::asset::{AssetServer, Handle};
 use bevy::ecs::bundle::Bundle;
 use bevy::ecs::entity::Entity;
@@ -12,11 +10,13 @@ use bevy::transform::components::Transform;
 use enum_map::EnumMap;
 use strum::IntoEnumIterator;
 
-use crate::model::{Board, BoardCoords, Emitters, Manipulator};
+use crate::model::{Board, BoardCoords, Emitters, GridMap, Manipulator};
 
 use super::animation::{AnimatedSpriteBundle, AnimationBundle, FadeOutAnimator};
-use super::beam::{spawn_beams, HaloBundle};
-use super::{BoardCoordsHolder, EngineCoords, GameAssets, Mutable, SpriteSheet};
+use super::beam::{spawn_beams, BeamAssets, HaloBundle, ResetBeams};
+use super::focus::Focus;
+use super::level::Level;
+use super::{BoardCoordsHolder, EngineCoords, LoadBarrier, Mutable, SpriteSheet, IDLE_FRAME_RATE};
 
 pub struct ManipulatorAssets {
     textures: EnumMap<Emitters, Handle<Image>>,
@@ -24,6 +24,9 @@ pub struct ManipulatorAssets {
     core: SpriteSheet,
 }
 
+#[derive(Resource, Debug, Default)]
+pub struct HighlightMovable(pub bool);
+
 #[derive(Bundle)]
 struct ManipulatorBundle {
     coords: BoardCoordsHolder,
@@ -32,7 +35,7 @@ struct ManipulatorBundle {
 }
 
 impl ManipulatorAssets {
-    pub fn load(server: &AssetServer, barrier: &Arc<()>) -> Self {
+    pub fn load(server: &AssetServer, barrier: &LoadBarrier) -> Self {
         let mut textures = EnumMap::default();
         let mut halos = EnumMap::default();
         for emitters in Emitters::iter() {
@@ -48,20 +51,21 @@ impl ManipulatorAssets {
                 Emitters::LeftRight => "manipulator-lr",
                 Emitters::UpDown => "manipulator-ud",
             };
-            textures[emitters] =
-                server.load_acquire(format!("{}.png", prefix), Arc::clone(&barrier));
+            textures[emitters] = barrier.load(server, format!("{}.png", prefix));
             halos[emitters] = SpriteSheet::new(
-                server.load_acquire(format!("{}-halo.png", prefix), Arc::clone(&barrier)),
+                barrier.load(server, format!("{}-halo.png", prefix)),
                 UVec2::splat(39),
                 48,
+                IDLE_FRAME_RATE,
                 server,
             );
         }
 
         let core = SpriteSheet::new(
-            server.load_acquire("manipulator-core.png", Arc::clone(&barrier)),
+            barrier.load(server, "manipulator-core.png"),
             UVec2::splat(14),
             48,
+            IDLE_FRAME_RATE,
             server,
         );
 
@@ -97,19 +101,16 @@ pub fn spawn_manipulator(
     manipulator: &Manipulator,
     coords: BoardCoords,
     board: &Board,
-    assets: &GameAssets,
+    assets: &ManipulatorAssets,
+    beam_assets: &BeamAssets,
     mutator: &impl Fn(&mut EntityCommands),
 ) -> Entity {
-    let mut anchor = parent.spawn(ManipulatorBundle::new(
-        coords,
-        manipulator,
-        &assets.manipulators,
-    ));
+    let mut anchor = parent.spawn(ManipulatorBundle::new(coords, manipulator, assets));
     anchor.with_children(|anchor| {
         anchor
             .spawn((
                 BoardCoordsHolder(coords),
-                AnimatedSpriteBundle::new(&assets.manipulators.core),
+                AnimatedSpriteBundle::new(&assets.core),
                 FadeOutAnimator::default(),
             ))
             .mutate(mutator);
@@ -117,7 +118,7 @@ pub fn spawn_manipulator(
         anchor
             .spawn(HaloBundle::new(
                 coords,
-                &assets.manipulators.halos[manipulator.emitters],
+                &assets.halos[manipulator.emitters],
                 REL_Z_LAYER_HALO,
             ))
             .mutate(mutator);
@@ -127,17 +128,54 @@ pub fn spawn_manipulator(
             coords,
             manipulator.emitters,
             board,
-            &assets.beams,
+            beam_assets,
             mutator,
         );
     });
     anchor.mutate(mutator).id()
 }
 
+pub fn highlight_movable_manipulators(
+    In(focus): In<Focus>,
+    highlight: Res<HighlightMovable>,
+    level: Res<Level>,
+    mut ev_reset: EventReader<ResetBeams>,
+    mut was_shown: Local<bool>,
+    mut q_sprite: Query<&mut Sprite>,
+) {
+    // NOTE: Resetting the beams means the board has changed, or has just been respawned
+    let board_changed = ev_reset.read().count() > 0;
+    let show = highlight.0 && matches!(focus, Focus::None);
+    if !board_changed && (show == *was_shown) {
+        return;
+    }
+    *was_shown = show;
+
+    let allowed = match show {
+        false => GridMap::like(&level.present.pieces),
+        true => level.present.all_allowed_moves(),
+    };
+    for (coords, _) in level.present.manipulators() {
+        let Some(mut sprite) = level
+            .pieces
+            .get(coords)
+            .and_then(|&entity| q_sprite.get_mut(entity).ok())
+        else {
+            continue;
+        };
+        let color = match allowed.get(coords) {
+            Some(directions) if !directions.is_empty() => HIGHLIGHT_COLOR,
+            _ => Color::WHITE,
+        };
+        sprite.color = color.with_alpha(sprite.color.alpha());
+    }
+}
+
 pub fn is_offset_inside_manipulator(offset: Vec2) -> bool {
     offset.length_squared() <= MANIPULATOR_SELECTION_RADIUS_SQUARED
 }
 
 const MANIPULATOR_SELECTION_RADIUS_SQUARED: f32 = 256.0;
+const HIGHLIGHT_COLOR: Color = Color::srgb(0.6, 1.0, 0.6);
 const Z_LAYER: f32 = 2.0;
 const REL_Z_LAYER_HALO: f32 = 1.0;
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::time::Duration;
 
 use bevy::asset::AssetServer;
 use bevy::ecs::bundle::Bundle;
@@ -7,13 +7,17 @@ use bevy::ecs::system::EntityCommands;
 use bevy::hierarchy::ChildBuilder;
 use bevy::prelude::*;
 use enum_map::EnumMap;
-use strum::IntoEnumIterator;
+use interpolation::Ease;
 
 use crate::model::{BoardCoords, Particle, Tint};
 
-use super::animation::{AnimatedSpriteBundle, AnimationBundle, FadeOutAnimator};
+use super::animation::{AnimatedSpriteBundle, AnimationBundle, FadeOutAnimator, ReduceMotion};
 use super::beam::HaloBundle;
-use super::{BoardCoordsHolder, EngineCoords, Mutable, SpriteSheet};
+use super::tile::tint_name;
+use super::{
+    BoardCoordsHolder, EngineCoords, LoadBarrier, Mutable, SpriteSheet, IDLE_FRAME_RATE,
+    MOVE_DURATION,
+};
 
 pub struct ParticleAssets {
     sheets: EnumMap<Tint, ParticleSheets>,
@@ -39,29 +43,31 @@ pub struct Corona;
 #[derive(Event)]
 pub struct ParticleCollected(pub Entity);
 
+// NOTE: A collected particle stays on the board, so its core is only absorbed part of the way
+// into the collector instead of vanishing, leaving something for beams to stop at.
+#[derive(Component, Default)]
+pub struct AbsorbAnimator {
+    played_duration: Duration,
+}
+
 impl ParticleAssets {
-    pub fn load(server: &AssetServer, barrier: &Arc<()>) -> Self {
+    pub fn load(server: &AssetServer, barrier: &LoadBarrier) -> Self {
         let mut sheets = EnumMap::default();
-        for tint in Tint::iter() {
-            let prefix = match tint {
-                Tint::White => continue,
-                Tint::Green => "particle-green",
-                Tint::Yellow => "particle-yellow",
-                Tint::Red => "particle-red",
-            };
-            let core = server.load_acquire(format!("{}-core.png", prefix), Arc::clone(&barrier));
-            let corona =
-                server.load_acquire(format!("{}-corona.png", prefix), Arc::clone(&barrier));
+        for tint in Tint::particle_tints() {
+            let prefix = format!("particle-{}", tint_name(tint));
+            let core = barrier.load(server, format!("{}-core.png", prefix));
+            let corona = barrier.load(server, format!("{}-corona.png", prefix));
             sheets[tint] = ParticleSheets {
-                core: SpriteSheet::new(core, UVec2::splat(34), 96, server),
-                corona: SpriteSheet::new(corona, UVec2::splat(34), 96, server),
+                core: SpriteSheet::new(core, UVec2::splat(34), 96, IDLE_FRAME_RATE, server),
+                corona: SpriteSheet::new(corona, UVec2::splat(34), 96, IDLE_FRAME_RATE, server),
             };
         }
 
         let halo = SpriteSheet::new(
-            server.load_acquire("particle-halo.png", Arc::clone(&barrier)),
+            barrier.load(server, "particle-halo.png"),
             UVec2::splat(37),
             48,
+            IDLE_FRAME_RATE,
             server,
         );
 
@@ -70,12 +76,26 @@ impl ParticleAssets {
 }
 
 impl ParticleBundle {
-    fn new(coords: BoardCoords, particle: &Particle, assets: &ParticleAssets) -> Self {
+    fn new(
+        coords: BoardCoords,
+        particle: &Particle,
+        collected: bool,
+        assets: &ParticleAssets,
+    ) -> Self {
         let coords = BoardCoordsHolder(coords);
         let sheets = &assets.sheets[particle.tint];
+        let (scale, alpha) = match collected {
+            false => (1.0, 1.0),
+            true => (ABSORBED_SCALE, ABSORBED_ALPHA),
+        };
         let sprite = SpriteBundle {
+            sprite: Sprite {
+                color: Color::WHITE.with_alpha(alpha),
+                ..Default::default()
+            },
             transform: Transform {
                 translation: coords.to_xy().extend(Z_LAYER),
+                scale: Vec3::new(scale, scale, 1.0),
                 ..Default::default()
             },
             ..Default::default()
@@ -92,16 +112,21 @@ pub fn spawn_particle(
     parent: &mut ChildBuilder,
     particle: &Particle,
     coords: BoardCoords,
+    collected: bool,
     assets: &ParticleAssets,
     mutator: &impl Fn(&mut EntityCommands),
 ) -> Entity {
-    let mut anchor = parent.spawn(ParticleBundle::new(coords, particle, assets));
+    let mut anchor = parent.spawn(ParticleBundle::new(coords, particle, collected, assets));
     anchor.with_children(|anchor| {
         let sprite = SpriteBundle {
             transform: Transform {
                 translation: Vec2::ZERO.extend(REL_Z_LAYER_CORONA),
                 ..Default::default()
             },
+            visibility: match collected {
+                false => Visibility::Inherited,
+                true => Visibility::Hidden,
+            },
             ..Default::default()
         };
         anchor
@@ -124,6 +149,7 @@ pub fn collect_particles(
     mut ev_collected: EventReader<ParticleCollected>,
     q_children: Query<&Children>,
     mut q_corona: Query<&mut Visibility, With<Corona>>,
+    mut commands: Commands,
 ) {
     for &ParticleCollected(anchor) in ev_collected.read() {
         for &child in q_children.get(anchor).unwrap().iter() {
@@ -131,9 +157,32 @@ pub fn collect_particles(
                 *visibility = Visibility::Hidden;
             }
         }
+        commands.entity(anchor).insert(AbsorbAnimator::default());
+    }
+}
+
+pub fn animate_absorb(
+    time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
+    mut q_animator: Query<(Entity, &mut AbsorbAnimator, &mut Transform, &mut Sprite)>,
+    mut commands: Commands,
+) {
+    let delta = reduce_motion.delta(&time, MOVE_DURATION);
+    for (entity, mut animator, mut xform, mut sprite) in q_animator.iter_mut() {
+        animator.played_duration = std::cmp::min(animator.played_duration + delta, MOVE_DURATION);
+        let progress = animator.played_duration.as_secs_f32() / MOVE_DURATION.as_secs_f32();
+        let progress = progress.sine_in_out();
+        let scale = 1.0.lerp(ABSORBED_SCALE, progress);
+        xform.scale = Vec3::new(scale, scale, 1.0);
+        sprite.color = sprite.color.with_alpha(1.0.lerp(ABSORBED_ALPHA, progress));
+        if animator.played_duration >= MOVE_DURATION {
+            commands.entity(entity).remove::<AbsorbAnimator>();
+        }
     }
 }
 
 const Z_LAYER: f32 = 2.0;
 const REL_Z_LAYER_CORONA: f32 = 1.0;
 const REL_Z_LAYER_HALO: f32 = 2.0;
+const ABSORBED_SCALE: f32 = 0.6;
+const ABSORBED_ALPHA: f32 = 0.6;
@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use bevy::asset::{AssetServer, Handle};
 use bevy::ecs::bundle::Bundle;
 use bevy::ecs::entity::Entity;
@@ -15,10 +13,11 @@ use strum::IntoEnumIterator;
 use crate::model::{BoardCoords, Tile, TileKind, Tint};
 
 use super::animation::AnimatedSpriteBundle;
-use super::{BoardCoordsHolder, EngineCoords, Mutable, SpriteSheet};
+use super::{BoardCoordsHolder, EngineCoords, LoadBarrier, Mutable, SpriteSheet, IDLE_FRAME_RATE};
 
 pub struct TileAssets {
     textures: EnumMap<TileKind, EnumMap<Tint, Handle<Image>>>,
+    platform_variants: EnumMap<Tint, Vec<Handle<Image>>>,
     collector_pulse: SpriteSheet,
 }
 
@@ -29,41 +28,64 @@ struct TileBundle {
 }
 
 impl TileAssets {
-    pub fn load(server: &AssetServer, barrier: &Arc<()>) -> Self {
+    pub fn load(server: &AssetServer, barrier: &LoadBarrier) -> Self {
         let mut textures = EnumMap::<TileKind, EnumMap<Tint, Handle<Image>>>::default();
+        let mut platform_variants = EnumMap::<Tint, Vec<Handle<Image>>>::default();
         for kind in TileKind::iter() {
             let kind_part = match kind {
                 TileKind::Platform => "platform",
                 TileKind::Collector => "collector",
+                // NOTE: Recolor tiles don't have artwork of their own yet, so they reuse the platform
+                // artwork, with a marker on top to tell them apart
+                TileKind::Recolor => "platform",
             };
             for tint in Tint::iter() {
-                let tint_part = match tint {
-                    Tint::White => "white",
-                    Tint::Green => "green",
-                    Tint::Yellow => "yellow",
-                    Tint::Red => "red",
-                };
-                textures[kind][tint] = server.load_acquire(
-                    format!("{}-{}.png", kind_part, tint_part),
-                    Arc::clone(&barrier),
-                );
+                let tint_part = tint_name(tint);
+                textures[kind][tint] =
+                    barrier.load(server, format!("{}-{}.png", kind_part, tint_part));
+                if kind == TileKind::Platform {
+                    platform_variants[tint] = (1..=PLATFORM_VARIANTS)
+                        .map(|variant| {
+                            barrier.load(
+                                server,
+                                format!("{}-{}-{}.png", kind_part, tint_part, variant),
+                            )
+                        })
+                        .collect();
+                }
             }
         }
 
-        let texture = server.load_acquire("collector-pulse.png", Arc::clone(&barrier));
-        let collector_pulse = SpriteSheet::new(texture, UVec2::splat(20), 48, server);
+        let texture = barrier.load(server, "collector-pulse.png");
+        let collector_pulse =
+            SpriteSheet::new(texture, UVec2::splat(20), 48, IDLE_FRAME_RATE, server);
 
         Self {
             textures,
+            platform_variants,
             collector_pulse,
         }
     }
+
+    // NOTE: Variant 0, as well as any variant without artwork of its own, uses the plain texture
+    fn texture(&self, tile: &Tile) -> Handle<Image> {
+        let variants = match tile.kind {
+            TileKind::Platform => &self.platform_variants[tile.tint][..],
+            TileKind::Collector | TileKind::Recolor => &[],
+        };
+        let variant = (tile.variant as usize)
+            .checked_sub(1)
+            .and_then(|idx| variants.get(idx));
+        variant
+            .unwrap_or(&self.textures[tile.kind][tile.tint])
+            .clone()
+    }
 }
 
 impl TileBundle {
     fn new(tile: &Tile, coords: BoardCoords, assets: &TileAssets) -> Self {
         let coords = BoardCoordsHolder(coords);
-        let texture = assets.textures[tile.kind][tile.tint].clone();
+        let texture = assets.texture(tile);
         Self {
             coords,
             sprite: SpriteBundle {
@@ -103,8 +125,41 @@ pub fn spawn_tile(
                 .mutate(mutator);
         });
     }
+    if tile.kind == TileKind::Recolor {
+        tile_entity.with_children(|parent| {
+            parent
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: RECOLOR_MARKER_COLOR,
+                        custom_size: Some(Vec2::splat(RECOLOR_MARKER_SIZE)),
+                        ..Default::default()
+                    },
+                    transform: Transform {
+                        translation: Vec2::ZERO.extend(REL_Z_LAYER_MARKER),
+                        rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_4),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .mutate(mutator);
+        });
+    }
     tile_entity.mutate(mutator).id()
 }
 
+// NOTE: Tinted artwork of every kind is named after the tint the same way
+pub fn tint_name(tint: Tint) -> &'static str {
+    match tint {
+        Tint::White => "white",
+        Tint::Green => "green",
+        Tint::Yellow => "yellow",
+        Tint::Red => "red",
+    }
+}
+
+const PLATFORM_VARIANTS: u8 = 2;
 const Z_LAYER: f32 = 0.0;
 const REL_Z_LAYER_PULSE: f32 = 1.0;
+const REL_Z_LAYER_MARKER: f32 = 1.0;
+const RECOLOR_MARKER_SIZE: f32 = 12.0;
+const RECOLOR_MARKER_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.6);
@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+
+use crate::model::{Board, BoardCoords, Direction, LevelMetadata};
+
+use super::focus::{get_focus, Focus};
+use super::gui::UndoMoves;
+use super::input::{InputSet, MoveManipulatorEvent};
+use super::level::Level;
+use super::{EngineCoords, EngineDirection, GameState, GameplaySet};
+
+pub struct TutorialPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TutorialStep {
+    Select(BoardCoords),
+    Move(BoardCoords, Direction),
+}
+
+#[derive(Debug, Clone)]
+pub struct TutorialScript {
+    steps: Vec<TutorialStep>,
+    step_idx: usize,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Tutorial {
+    script: Option<TutorialScript>,
+    stale: bool,
+    skipped: bool,
+}
+
+impl TutorialStep {
+    pub fn hint(self) -> String {
+        match self {
+            Self::Select(_) => "Select the marked manipulator".to_string(),
+            Self::Move(_, direction) => {
+                let direction = format!("{:?}", direction).to_lowercase();
+                format!("Move it {}", direction)
+            }
+        }
+    }
+}
+
+impl TutorialScript {
+    pub fn for_level(metadata: &LevelMetadata, board: &Board) -> Option<Self> {
+        if metadata.id != Some(TUTORIAL_LEVEL_ID) {
+            return None;
+        }
+        Self::for_board(board)
+    }
+
+    // NOTE: The script follows the solver rather than a hand-written list of steps, so it can
+    // pick up from wherever the player left the board.
+    fn for_board(board: &Board) -> Option<Self> {
+        let steps = board
+            .solve()?
+            .into_iter()
+            .flat_map(|step| {
+                [
+                    TutorialStep::Select(step.coords),
+                    TutorialStep::Move(step.coords, step.direction),
+                ]
+            })
+            .collect();
+        Some(Self { steps, step_idx: 0 })
+    }
+
+    pub fn current(&self) -> Option<TutorialStep> {
+        self.steps.get(self.step_idx).copied()
+    }
+
+    fn advance(&mut self) {
+        self.step_idx += 1;
+    }
+}
+
+impl Tutorial {
+    pub fn current(&self) -> Option<TutorialStep> {
+        self.script.as_ref()?.current()
+    }
+
+    pub fn skip(&mut self) {
+        self.script = None;
+        self.skipped = true;
+    }
+}
+
+fn start_tutorial(level: Res<Level>, mut tutorial: ResMut<Tutorial>) {
+    tutorial.stale = false;
+    tutorial.script = match tutorial.skipped {
+        true => None,
+        false => TutorialScript::for_level(&level.metadata, &level.present),
+    };
+}
+
+fn advance_tutorial(
+    In(focus): In<Focus>,
+    level: Res<Level>,
+    mut tutorial: ResMut<Tutorial>,
+    mut ev_move_manipulator: EventReader<MoveManipulatorEvent>,
+    mut ev_undo: EventReader<UndoMoves>,
+) {
+    let Some(script) = tutorial.script.as_mut() else {
+        ev_move_manipulator.clear();
+        ev_undo.clear();
+        return;
+    };
+
+    let mut stale = ev_undo.read().count() > 0;
+    for &MoveManipulatorEvent(direction) in ev_move_manipulator.read() {
+        match script.current() {
+            Some(TutorialStep::Move(coords, expected))
+                if (focus.coords(false) == Some(coords)) && (direction == expected) =>
+            {
+                script.advance()
+            }
+            _ => stale = true,
+        }
+    }
+    tutorial.stale |= stale;
+
+    // NOTE: Once the player strays from the script, it is rebuilt from the board they end up
+    // with, which is only settled after the pieces stop moving.
+    if let Focus::Busy(_) = focus {
+        return;
+    }
+    if tutorial.stale {
+        tutorial.stale = false;
+        tutorial.script = TutorialScript::for_board(&level.present);
+    }
+    let Some(script) = tutorial.script.as_mut() else {
+        return;
+    };
+    if let Some(TutorialStep::Select(coords)) = script.current() {
+        if focus.coords(false) == Some(coords) {
+            script.advance();
+        }
+    }
+}
+
+fn draw_tutorial_arrows(
+    tutorial: Res<Tutorial>,
+    level: Res<Level>,
+    q_xform: Query<&GlobalTransform>,
+    mut gizmos: Gizmos,
+) {
+    let Some(step) = tutorial.current() else {
+        return;
+    };
+    let Some(xform) = level.parent.and_then(|parent| q_xform.get(parent).ok()) else {
+        return;
+    };
+    let to_world = |pos: Vec2| xform.transform_point(pos.extend(0.0)).truncate();
+
+    let (coords, start, end) = match step {
+        TutorialStep::Select(coords) => {
+            let delta = Direction::Up.delta();
+            (coords, delta * ARROW_FAR, delta * ARROW_NEAR)
+        }
+        TutorialStep::Move(coords, direction) => {
+            let delta = direction.delta();
+            (coords, delta * ARROW_NEAR, delta * ARROW_FAR)
+        }
+    };
+    let center = coords.to_xy();
+    gizmos.arrow_2d(
+        to_world(center + start),
+        to_world(center + end),
+        ARROW_COLOR,
+    );
+}
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tutorial>()
+            .add_systems(OnEnter(GameState::Playing), start_tutorial)
+            .add_systems(
+                FixedPreUpdate,
+                get_focus
+                    .pipe(advance_tutorial)
+                    .in_set(GameplaySet)
+                    .after(InputSet),
+            )
+            .add_systems(
+                Update,
+                draw_tutorial_arrows.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+const TUTORIAL_LEVEL_ID: usize = 0;
+const ARROW_NEAR: f32 = 0.5;
+const ARROW_FAR: f32 = 1.3;
+const ARROW_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
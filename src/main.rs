@@ -1,43 +1,65 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Read;
+use std::time::Duration;
+
 use bevy::app::App;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::asset::io::file::FileAssetReader;
+use bevy::asset::AssetMetaCheck;
 use bevy::core_pipeline::core_2d::Camera2dBundle;
 use bevy::ecs::schedule::IntoSystemConfigs;
 use bevy::ecs::system::{Commands, Res, ResMut};
 use bevy::prelude::*;
-use bevy::window::{Window, WindowPlugin, WindowResolution};
+use bevy::window::{Window, WindowPlugin, WindowResizeConstraints, WindowResolution};
 use bevy::DefaultPlugins;
 use bevy_egui::EguiPlugin;
 use engine::audio::{AudioPlugin, PlaySfx, PlayTune};
 use model::LevelOutcome;
 
+// NOTE: The checks are compiled out in builds without the feature, while the arguments still get
+// type-checked and don't leave any variables unused.
+macro_rules! log_event {
+    ($($arg:tt)+) => {
+        if cfg!(feature = "event-log") {
+            bevy::log::info!(target: "particlz::events", $($arg)+);
+        }
+    };
+}
+
 mod engine;
 mod model;
 
 use self::engine::animation::{
-    Animation, AnimationFinished, AnimationPlugin, AnimationSet, StartAnimation,
+    start_fade_in, Animation, AnimationFinished, AnimationPlugin, AnimationSet,
+    AnimationStateHolder, StartAnimation,
 };
+use self::engine::attract::AttractPlugin;
 use self::engine::beam::{BeamPlugin, BeamSet, MoveBeams, ResetBeams};
-use self::engine::focus::{get_focus, Focus, FocusPlugin, UpdateFocusEvent};
-use self::engine::gui::{
-    GuiPlugin, PlayLevel, UndoMoves, IN_GAME_PANEL_WIDTH, WINDOW_HEIGHT, WINDOW_WIDTH,
+use self::engine::camera::{CameraPlugin, PlayArea};
+use self::engine::focus::{get_focus, AutoSelectMovable, Focus, FocusPlugin, UpdateFocusEvent};
+use self::engine::gui::{GuiPlugin, PlayLevel, UndoMoves, WINDOW_HEIGHT, WINDOW_WIDTH};
+use self::engine::input::{
+    InputPlugin, InputSet, MoveBuffer, MoveManipulatorEvent, SelectManipulatorEvent,
+};
+use self::engine::level::{
+    update_board_layout, update_piece_coords, Campaign, Level, Progress, SandboxMode,
+};
+use self::engine::manipulator::{highlight_movable_manipulators, HighlightMovable};
+use self::engine::particle::{
+    animate_absorb, collect_particles, AbsorbAnimator, ParticleCollected,
 };
-use self::engine::input::{InputPlugin, InputSet, MoveManipulatorEvent, SelectManipulatorEvent};
-use self::engine::level::{update_piece_coords, Campaign, Level};
-use self::engine::particle::{collect_particles, ParticleCollected};
+use self::engine::tutorial::TutorialPlugin;
 use self::engine::{
     AssetsLoaded, AssetsPlugin, GameAssets, GameState, GameplaySet, InLevel, InLevelSet, MainCamera,
 };
-use self::model::{Board, CampaignData, LevelCampaign, Piece, Tile, TileKind};
+use self::model::{
+    star_rating, Board, BoardCoords, GridSet, LevelCampaign, Piece, Tile, TileKind,
+    CLASSIC_CAMPAIGN_BRIEFINGS, CLASSIC_CAMPAIGN_DATA,
+};
 
 fn main() {
     App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Particlz".into(),
-                resolution: WindowResolution::new(WINDOW_WIDTH as _, WINDOW_HEIGHT as _),
-                ..Default::default()
-            }),
-            ..Default::default()
-        }))
+        .add_plugins(DefaultPlugins.set(window_plugin()).set(asset_plugin()))
         .init_state::<GameState>()
         .add_computed_state::<InLevel>()
         .add_plugins(EguiPlugin)
@@ -48,6 +70,12 @@ fn main() {
         .add_plugins(AnimationPlugin)
         .add_plugins(FocusPlugin)
         .add_plugins(BeamPlugin)
+        .add_plugins(CameraPlugin)
+        .add_plugins(TutorialPlugin)
+        .add_plugins(AttractPlugin)
+        .add_plugins(debug_plugins)
+        .init_resource::<SandboxMode>()
+        .init_resource::<HighlightMovable>()
         .add_event::<ParticleCollected>()
         .configure_sets(
             FixedPreUpdate,
@@ -70,7 +98,13 @@ fn main() {
             PostUpdate,
             start_level.run_if(not(in_state(GameState::Playing))),
         )
-        .add_systems(OnEnter(GameState::Playing), setup_board)
+        .add_systems(OnEnter(GameState::Briefing), setup_board)
+        .add_systems(
+            OnEnter(GameState::Playing),
+            (setup_board, start_fade_in)
+                .chain()
+                .run_if(not(entered_from_briefing)),
+        )
         .add_systems(
             FixedPreUpdate,
             undo_moves.in_set(InLevelSet).before(InputSet),
@@ -96,14 +130,70 @@ fn main() {
         .add_systems(
             FixedPostUpdate,
             (
-                check_game_over.in_set(GameplaySet),
-                collect_particles.in_set(GameplaySet),
+                get_focus
+                    .pipe(check_game_over)
+                    .after(animate_absorb)
+                    .in_set(GameplaySet),
+                (collect_particles, animate_absorb)
+                    .chain()
+                    .in_set(GameplaySet),
+                get_focus
+                    .pipe(highlight_movable_manipulators)
+                    .after(BeamSet)
+                    .in_set(GameplaySet),
             ),
         )
+        .add_systems(Update, update_board_layout.run_if(in_state(InLevel)))
         .add_systems(OnExit(InLevel), remove_level)
+        .add_systems(Last, save_on_exit)
         .run();
 }
 
+fn window_plugin() -> WindowPlugin {
+    WindowPlugin {
+        primary_window: Some(Window {
+            title: "Particlz".into(),
+            resolution: WindowResolution::new(WINDOW_WIDTH as _, WINDOW_HEIGHT as _),
+            resize_constraints: WindowResizeConstraints {
+                min_width: WINDOW_WIDTH as _,
+                min_height: WINDOW_HEIGHT as _,
+                ..Default::default()
+            },
+            #[cfg(target_arch = "wasm32")]
+            canvas: Some(WEB_CANVAS.into()),
+            #[cfg(target_arch = "wasm32")]
+            fit_canvas_to_parent: true,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn asset_plugin() -> AssetPlugin {
+    // NOTE: On the web, assets are fetched over HTTP, and servers tend to answer requests for the
+    // missing .meta files with errors or HTML pages
+    let meta_check = match cfg!(target_arch = "wasm32") {
+        false => AssetMetaCheck::Always,
+        true => AssetMetaCheck::Never,
+    };
+    AssetPlugin {
+        meta_check,
+        ..Default::default()
+    }
+}
+
+#[cfg(feature = "debug-overlay")]
+fn debug_plugins(app: &mut App) {
+    app.add_plugins((
+        engine::debug::DebugOverlayPlugin,
+        engine::debug::SolveStepPlugin,
+        engine::debug::BeamGizmoPlugin,
+    ));
+}
+
+#[cfg(not(feature = "debug-overlay"))]
+fn debug_plugins(_app: &mut App) {}
+
 fn finish_init(
     mut ev_loaded: EventReader<AssetsLoaded>,
     mut next_state: ResMut<NextState<GameState>>,
@@ -114,25 +204,109 @@ fn finish_init(
         return;
     }
 
-    let classic_campaign = LevelCampaign::from_static(CLASSIC_CAMPAIGN_DATA);
-    commands.insert_resource(Campaign(classic_campaign));
+    commands.insert_resource(Campaign(load_campaign()));
+    commands.insert_resource(Progress::load());
 
     let mut camera = Camera2dBundle::default();
     camera.projection.viewport_origin = Vec2::new(0.0, 1.0);
     commands.spawn((camera, MainCamera));
 
-    if let Some(code) = std::env::args().nth(1) {
-        match Board::from_pbc1(&code) {
-            Ok(board) => {
-                ev_play.send(PlayLevel(board, Default::default()));
-                return;
-            }
-            Err(err) => bevy::log::error!("Invalid custom level code: {}", err),
+    // NOTE: On the web there are no arguments to read the custom level from, so it's taken from
+    // the `level` query parameter instead, e.g. `?level=:PBC1:...`. The parameter is URL-decoded,
+    // which turns any unescaped `+` in a PBC1 code into a space, so those are turned back.
+    #[cfg(target_arch = "wasm32")]
+    let custom_level = read_web_custom_level();
+    #[cfg(not(target_arch = "wasm32"))]
+    let custom_level = read_custom_level();
+    match custom_level {
+        Ok(Some(board)) => {
+            ev_play.send(PlayLevel(board, Default::default()));
+            return;
         }
+        Ok(None) => (),
+        Err(err) => bevy::log::error!("Invalid custom level: {}", err),
     }
     next_state.set(GameState::MainMenu);
 }
 
+// NOTE: The campaign file sits next to the other assets, so it's found the same way Bevy finds
+// them, rather than relative to the working directory
+#[cfg(not(target_arch = "wasm32"))]
+fn load_campaign() -> LevelCampaign {
+    let path = FileAssetReader::get_base_path()
+        .join(asset_plugin().file_path)
+        .join(CAMPAIGN_FILE);
+    match LevelCampaign::from_file(path) {
+        Ok(campaign) => campaign,
+        Err(err) => {
+            bevy::log::error!("Falling back to the built-in campaign: {}", err);
+            LevelCampaign::from_static(CLASSIC_CAMPAIGN_DATA, CLASSIC_CAMPAIGN_BRIEFINGS)
+        }
+    }
+}
+
+// NOTE: There is no file system on the web, so the campaign is always the built-in one
+#[cfg(target_arch = "wasm32")]
+fn load_campaign() -> LevelCampaign {
+    LevelCampaign::from_static(CLASSIC_CAMPAIGN_DATA, CLASSIC_CAMPAIGN_BRIEFINGS)
+}
+
+// NOTE: A custom level is either given as a PBC1 code in the first argument, or read from a file
+// or the standard input, which can also hold a text board.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_custom_level() -> Result<Option<Board>, String> {
+    let mut args = std::env::args().skip(1);
+    let Some(arg) = args.next() else {
+        return Ok(None);
+    };
+    let text = match arg.as_str() {
+        "--file" => {
+            let path = args.next().ok_or("expected a path after --file")?;
+            std::fs::read_to_string(&path).map_err(|err| format!("{}: {}", path, err))?
+        }
+        "--stdin" => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|err| format!("stdin: {}", err))?;
+            text
+        }
+        _ => arg,
+    };
+    parse_custom_level(&text).map(Some)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_web_custom_level() -> Result<Option<Board>, String> {
+    let window = web_sys::window().ok_or("no browser window")?;
+    let search = window
+        .location()
+        .search()
+        .map_err(|err| format!("{:?}", err))?;
+    let params =
+        web_sys::UrlSearchParams::new_with_str(&search).map_err(|err| format!("{:?}", err))?;
+    let Some(mut text) = params.get("level") else {
+        return Ok(None);
+    };
+    if text.trim().starts_with(":PBC1:") {
+        text = text.replace(' ', "+");
+    }
+    parse_custom_level(&text).map(Some)
+}
+
+fn parse_custom_level(text: &str) -> Result<Board, String> {
+    let code = text.trim();
+    let board = if code.starts_with(":PBC1:") {
+        Board::from_pbc1(code).map_err(|err| err.to_string())?
+    } else {
+        Board::from_ascii(text).map_err(|err| err.to_string())?
+    };
+    if board.manipulator_count() == 0 {
+        return Err("the board has no manipulators".to_string());
+    }
+    Ok(board)
+}
+
 fn play_menu_tune(mut ev_play_tune: EventWriter<PlayTune>) {
     ev_play_tune.send(PlayTune::Menu);
 }
@@ -158,23 +332,32 @@ fn start_level(
 
     let tune = metadata
         .id
-        .map(|idx| CLASSIC_CAMPAIGN_TUNES[idx])
+        .and_then(|idx| CLASSIC_CAMPAIGN_TUNES.get(idx).copied())
         .unwrap_or(PlayTune::Easy);
     ev_play_tune.send(tune);
 
-    next_state.set(GameState::Playing);
+    next_state.set(match metadata.briefing {
+        Some(_) => GameState::Briefing,
+        None => GameState::Playing,
+    });
 }
 
 fn setup_board(
     mut commands: Commands,
     mut level: ResMut<Level>,
+    play_area: Res<PlayArea>,
     assets: Res<GameAssets>,
     mut ev_retarget: EventWriter<ResetBeams>,
 ) {
-    level.spawn(PLAY_AREA_SIZE, &mut commands, &assets);
+    level.spawn(play_area.0, &mut commands, &assets);
     ev_retarget.send(ResetBeams);
 }
 
+// NOTE: The board is already on screen behind the briefing, so it's kept as is when play starts
+fn entered_from_briefing(ev_transition: EventReader<StateTransitionEvent<GameState>>) -> bool {
+    last_transition(ev_transition).is_some_and(|ev| ev.exited == Some(GameState::Briefing))
+}
+
 fn select_manipulator(
     focus: In<Focus>,
     mut ev_select_manipulator: EventReader<SelectManipulatorEvent>,
@@ -196,6 +379,7 @@ fn select_manipulator(
         .map(|coords| Focus::Selected(coords, level.present.compute_allowed_moves(coords)))
         .unwrap_or(Focus::None);
     if new_focus.is_selected() {
+        log_event!(coords = ?coords.unwrap(), "manipulator selected");
         ev_play_sfx.send(PlaySfx::Focus);
     }
     ev_update_focus.send(UpdateFocusEvent(new_focus));
@@ -219,7 +403,16 @@ fn move_manipulator(
 
     let direction = event.0;
 
-    let move_set = level.present.compute_move_set(leader, direction);
+    let Some(move_set) = level.present.compute_checked_move_set(leader, direction) else {
+        warn!("Refusing to move {:?} {:?}", leader, direction);
+        return;
+    };
+    log_event!(
+        coords = ?leader,
+        direction = ?direction,
+        move_set_size = move_set.len(),
+        "move issued"
+    );
     level.prepare_move(&move_set, direction);
 
     ev_start_animation.send(StartAnimation(
@@ -241,49 +434,69 @@ fn finish_animation(
     mut ev_update_focus: EventWriter<UpdateFocusEvent>,
     mut ev_collected: EventWriter<ParticleCollected>,
     mut ev_play_sfx: EventWriter<PlaySfx>,
+    mut ev_move_manipulator: EventWriter<MoveManipulatorEvent>,
+    mut move_buffer: ResMut<MoveBuffer>,
+    sandbox: Res<SandboxMode>,
+    auto_select: Res<AutoSelectMovable>,
+    assets: Res<GameAssets>,
     mut level: ResMut<Level>,
     mut commands: Commands,
 ) {
     let Some(AnimationFinished(animation, pieces)) = ev_animation_finished.read().last() else {
         return;
     };
+    log_event!(animation = ?animation, pieces = pieces.len(), "animation finished");
 
     level.update_present();
 
-    match animation {
+    let new_focus = match animation {
         Animation::Movement(direction) => {
+            let mut recolored = Vec::new();
             pieces.for_each(*direction, |from_coords| {
                 let to_coords = level.present.neighbor(from_coords, *direction).unwrap();
                 level.move_piece(from_coords, to_coords);
                 if let Some(Piece::Particle(_)) = level.present.pieces.get(to_coords) {
-                    if let Some(Tile {
-                        kind: TileKind::Collector,
-                        ..
-                    }) = level.present.tiles.get(to_coords)
-                    {
-                        ev_play_sfx.send(PlaySfx::Collect);
-                        ev_collected.send(ParticleCollected(
-                            level.pieces.get(to_coords).copied().unwrap(),
-                        ));
+                    match level.present.tiles.get(to_coords) {
+                        Some(Tile {
+                            kind: TileKind::Collector,
+                            ..
+                        }) => {
+                            log_event!(coords = ?to_coords, "particle collected");
+                            ev_play_sfx.send(PlaySfx::Collect);
+                            ev_collected.send(ParticleCollected(
+                                level.pieces.get(to_coords).copied().unwrap(),
+                            ));
+                        }
+                        Some(Tile {
+                            kind: TileKind::Recolor,
+                            ..
+                        }) => recolored.push(to_coords),
+                        _ => (),
                     }
                 }
             });
+            // NOTE: The board already gave the particle its new tint, so only its sprites are
+            // left to catch up
+            for coords in recolored {
+                level.respawn_piece(coords, &mut commands, &assets);
+            }
 
             let focus_coords = level
                 .present
                 .neighbor(focus.coords(true).unwrap(), *direction)
                 .unwrap();
 
-            let unsupported = level.present.unsupported_pieces();
+            // NOTE: In sandbox mode unsupported pieces simply stay on the board
+            let unsupported = match sandbox.0 {
+                false => level.present.unsupported_pieces(),
+                true => GridSet::like(&level.present.pieces),
+            };
             if unsupported.is_empty() {
-                ev_update_focus.send(UpdateFocusEvent(Focus::Selected(
-                    focus_coords,
-                    level.present.compute_allowed_moves(focus_coords),
-                )));
+                select_after_move(&level.present, focus_coords, auto_select.0)
             } else {
                 ev_play_sfx.send(PlaySfx::Fade);
-                ev_update_focus.send(UpdateFocusEvent(Focus::Busy(Some(focus_coords))));
                 ev_start_animation.send(StartAnimation(Animation::FadeOut, unsupported));
+                Focus::Busy(Some(focus_coords))
             }
         }
         Animation::FadeOut => {
@@ -292,31 +505,94 @@ fn finish_animation(
                 _ => None,
             };
             level.remove_pieces(pieces, &mut commands);
-            let new_focus = match focus_coords {
-                Some(coords) => {
-                    Focus::Selected(coords, level.present.compute_allowed_moves(coords))
-                }
+            match focus_coords {
+                Some(coords) => select_after_move(&level.present, coords, auto_select.0),
                 None => Focus::None,
-            };
-            ev_update_focus.send(UpdateFocusEvent(new_focus));
+            }
+        }
+    };
+
+    if let Focus::Selected(_, directions) = new_focus {
+        if let Some(direction) = move_buffer.take() {
+            if directions.contains(direction) && (sandbox.0 || level.progress.outcome.is_none()) {
+                ev_move_manipulator.send(MoveManipulatorEvent(direction));
+            }
         }
     }
+    ev_update_focus.send(UpdateFocusEvent(new_focus));
     ev_retarget.send(ResetBeams);
 }
 
+fn select_after_move(board: &Board, coords: BoardCoords, auto_select: bool) -> Focus {
+    let allowed = board.compute_allowed_moves(coords);
+    if !auto_select || !allowed.is_empty() {
+        return Focus::Selected(coords, allowed);
+    }
+    match board.next_movable_manipulator(coords) {
+        Some(next) => Focus::Selected(next, board.compute_allowed_moves(next)),
+        None => Focus::Selected(coords, allowed),
+    }
+}
+
 fn check_game_over(
-    level: Res<Level>,
+    focus: In<Focus>,
+    mut level: ResMut<Level>,
+    animation: Res<AnimationStateHolder>,
+    q_absorbing: Query<(), With<AbsorbAnimator>>,
+    time: Res<Time>,
+    sandbox: Res<SandboxMode>,
+    mut progress: ResMut<Progress>,
     mut next_state: ResMut<NextState<GameState>>,
     mut ev_play_sfx: EventWriter<PlaySfx>,
+    mut ev_update_focus: EventWriter<UpdateFocusEvent>,
+    mut delay: Local<Option<Duration>>,
 ) {
-    if let Some(outcome) = level.progress.outcome {
-        let effect = match outcome {
-            LevelOutcome::Victory => PlaySfx::Win,
-            _ => PlaySfx::Lose,
-        };
-        ev_play_sfx.send(effect);
-        next_state.set(GameState::GameOver);
+    let Some(outcome) = level.progress.outcome else {
+        *delay = None;
+        return;
+    };
+
+    if let Some(elapsed) = delay.as_mut() {
+        *elapsed += time.delta();
+        if *elapsed >= GAME_OVER_DELAY {
+            *delay = None;
+            next_state.set(GameState::GameOver);
+        }
+        return;
     }
+
+    if sandbox.0 {
+        return;
+    }
+    if let Focus::Busy(_) = *focus {
+        return;
+    }
+    if !animation.is_idle() || !q_absorbing.is_empty() {
+        return;
+    }
+
+    log_event!(outcome = ?outcome, moves = level.move_count(), "outcome reached");
+    let effect = match outcome {
+        LevelOutcome::Victory => {
+            if let Some(level_idx) = level.metadata.id {
+                let had_best = progress.best_moves(level_idx).is_some();
+                let solved = progress.mark_solved(level_idx);
+                let recorded = progress.record_moves(level_idx, level.move_count());
+                let rated = level.metadata.par.is_some_and(|par| {
+                    progress.record_stars(level_idx, star_rating(level.move_count(), par))
+                });
+                if solved || recorded || rated {
+                    progress.save();
+                }
+                level.new_best = had_best && recorded;
+            }
+            PlaySfx::Win
+        }
+        _ => PlaySfx::Lose,
+    };
+    ev_play_sfx.send(effect);
+    ev_update_focus.send(UpdateFocusEvent(Focus::Busy(None)));
+    *delay = Some(Duration::ZERO);
 }
 
 fn undo_moves(
@@ -325,59 +601,51 @@ fn undo_moves(
     mut commands: Commands,
     assets: Res<GameAssets>,
     mut ev_retarget: EventWriter<ResetBeams>,
+    mut ev_update_focus: EventWriter<UpdateFocusEvent>,
 ) {
     if ev_undo.is_empty() {
         return;
     }
+    let spawned = level.present.clone();
     for undo in ev_undo.read() {
         match undo {
             UndoMoves::Last => level.undo(),
             UndoMoves::All => level.reset(),
+            UndoMoves::ToCheckpoint => level.restore_checkpoint(),
+            UndoMoves::Redo => level.redo(),
         }
     }
-    level.spawn(PLAY_AREA_SIZE, &mut commands, &assets);
+    level.reconcile_pieces(&spawned, &mut commands, &assets);
+    ev_update_focus.send(UpdateFocusEvent(Focus::None));
     ev_retarget.send(ResetBeams);
 }
 
-fn remove_level(mut level: ResMut<Level>, mut commands: Commands) {
+// NOTE: Progress is saved as soon as it changes, so this is only a last chance to write it out in
+// case an earlier save failed.
+fn save_on_exit(mut ev_exit: EventReader<AppExit>, progress: Option<Res<Progress>>) {
+    if ev_exit.read().last().is_none() {
+        return;
+    }
+    if let Some(progress) = progress {
+        progress.save();
+    }
+}
+
+fn remove_level(
+    mut level: ResMut<Level>,
+    mut sandbox: ResMut<SandboxMode>,
+    mut commands: Commands,
+) {
+    sandbox.0 = false;
     level.despawn(&mut commands);
     commands.remove_resource::<Level>();
 }
 
-const PLAY_AREA_SIZE: Vec2 = Vec2::new(
-    (WINDOW_WIDTH - IN_GAME_PANEL_WIDTH) as f32,
-    WINDOW_HEIGHT as f32,
-);
-
-const CLASSIC_CAMPAIGN_DATA: CampaignData = &[
-    ("eASY", &[
-        ("Tutorial", ":PBC1:AapHrUCxAhxBEASxUBAEBQoMEARhjihQoEBQoECBI5BCEARBACAFAEFQokCBhYIgCAoER6AAsVAQBEHRIAiwUBAEABBisUMQFC5QugBBYKEgKBKELAbB/wE="),
-        ("Experiment", ":PBC1:AaocQRMEUaBAgQIpgGFYngmCFACwLIIgBQAsiyBIAQDLIghSAMCyCIZJAQDLIggeoUEGAFgWQZACwINhgyAFoG0es0Hwfw=="),
-        ("Teamwork", ":PBC1:AXpciRIlCIIgDsABSAEAAAyQAgAAwKMUBEEQBAAWCoIgCAIACwVBEAQBgIWCIAiCgQD8Hw=="),
-        ("Roundabout", ":PBC1:AaocUYIgCIIgiBQAAABSGAAAgMFSIAAAQAo4RAAApAAKGAbAowSUAgAgBQAAgBQAoBSGwELBQAAA4P8="),
-        ("Relay", ":PBC1:AZrcYShQoECBAgUKFEgBAAAgBQAAgBQAAACWIhiCIRiCGSDFEAzBEAyBFAAAAFIAAABYKAiCIAiCgfB/"),
-        ("Occlusion", ":PBC1:AVoHrMABKHEAChcoUKDAUggxQNEgCIKlgiAIiwZBMMxSCDFA0SAIggcoGCAcoGgQBMH/AQ=="),
-        ("Transfer", ":PBC1:AZlA4QIFChRgAWCKDhbwgIJszFjChCi+UBEWAVA8WGgoQ4MwUBzTYKGARQAUDRbicwgApmgGKH5QirBgAMWDICjCAh8="),
-    ]),
-    ("MedIUM", &[
-        ("Mmmm, pi!", ":PBC1:AaocQRAEQRAEkQIAAEBqsCAPgjwYDCkgAAIAKRUCIIAGKWAAYAAAKWAQYBAAKSAFUgApAAAApAAAAPB/"),
-        ("Milky Way", ":PBC1:AaqHrEQBgiAIgjgCKSAAAOQpAAEABCkACIAAKSAYZiAEQAoBBhsqAJAKgAAAsBAABACwFwAgAPAAAQAQpIP8Hw=="),
-        ("Maze", ":PBC1:AartChQoUKBAgQIFeixUpEiRIkGRIkWCBYsUPeJBkSJFihRZKAiKBEWKFClSdMGuRYoULVKkSBAsGBQJijQpUiQoulCRIkWKFi8SFQkWLFJkgCA4JEWKxMkiRZgiRZgiRZiFgoGCIAiCIPg/"),
-        ("Checkers", ":PBC1:AXdHjShAFCAOQCpAjsHwCCFAgCCVIkCAhTAIYgSpAAMhwEIIEGCYfw=="),
-        ("Crowded", ":PBC1:AaocQTRo0KAF0eMBpBZLEmRZliUbJQAyAMlGWZhlGYBkowxIgiRJko0yIMmyLMNGGZAAyPApZUCSJFmGjTbJsiwLM+ADSpIkSZJtsk3+Dw=="),
-        ("Juggle", ":PBC1:Aaq3rUCBAgUKFChQoEQqAAAgQCoAACBAKmAYhmGYAKkAgwDAMAM8QkMBGAQIkAoAAAiQChiGYRgmQCoAACDAXkEQBEEQBCv9Hw=="),
-        ("I Kill You", ":PBC1:AaocQRAEQRDH4CikAADAYR1mIRYAAAYLsQAAACkAAACkUKTOASxShAK2KxIMUigIAo5AHKIgKBQMkFMAolVQaIiAAwAEQfTiAAAB"),
-    ]),
-    ("HArd", &[
-        ("Lock", ":PBC1:AXqcBRYQhAUEQRApQAJIAGwFQABAM0wqz3PkOYAUgAAIgFQABAgCIDXkQEMOO9BwwwD/Bw=="),
-        ("Delicate", ":PBC1:AZnFihUoUKBwgQLFFhq0AM/UKTxgsFhQiAWKFiqwEM8MgQGYPkUXZAEAKLpQWwyCIYDiCxUpyALFCwaLDRnUBYoOV2ChQgWKFC9SICj0Pw=="),
-        ("Void", ":PBC1:AaqHjaAJgiAIwoMUwAIAkALAAgCTAgAgYJACAIABUgAOQDkASIEBQQBAigHABgCSAQCwALoEAAAL0f8B"),
-        ("Nautilus", ":PBC1:AapnrQBBEARBEAYsJAAABKMhhbECAIIAKQQCBKMBSAEAAgApAIAgAFJAIEAwDpACRgoACIJUIAAABOOkRgoAAMD/AQ=="),
-        ("Trapped", ":PBC1:AanlCIIoQBBEgYUABAAGepQAQQggWAgUOyxoKlgIFBuApYKFcIDYAAeUChYCxQZgqWAhUGwAlgoeJhAIBcAwCwEIAIT/Aw=="),
-        ("Quadruped", ":PBC1:AaqHjiAIgiAIgkgBAIABkQIQAABSAADQBJEaEgDADoAUgOEQHlAUXQgAAARIASGAAOxSAAAwTPAABQACAPg/"),
-        ("Rails", ":PBC1:AaoccRgIgiAIgkgBAAAgBQAAMEwKAAAAKRxwpg9ThgUeJTBHFAGKsEihOAZBgDZsCswRRYCARwoHHDFCHkiBYRiGwUHB/wE="),
-    ]),
-];
+#[cfg(not(target_arch = "wasm32"))]
+const CAMPAIGN_FILE: &str = "campaign.json";
+#[cfg(target_arch = "wasm32")]
+const WEB_CANVAS: &str = "#particlz";
+const GAME_OVER_DELAY: Duration = Duration::from_millis(750);
 
 const CLASSIC_CAMPAIGN_TUNES: &[PlayTune] = &[
     PlayTune::Easy,
@@ -1,25 +1,40 @@
 //! Engine-agnostic game data and logic
 
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 
 use enum_map::Enum;
 use enumset::EnumSetType;
+use strum::IntoEnumIterator;
 use strum_macros::{EnumCount, EnumIter, FromRepr};
 
+mod ascii;
 mod board;
+mod campaign;
 mod element;
+mod generate;
 mod grid;
 mod level;
 mod movement;
 mod pbc1;
+#[cfg(test)]
+mod playthrough;
+mod random;
+mod solver;
 mod support;
 
-pub use board::Board;
+pub use board::{Board, BoardBuilder};
+pub use campaign::{
+    CampaignProgress, LevelCampaign, CLASSIC_CAMPAIGN_BRIEFINGS, CLASSIC_CAMPAIGN_DATA,
+};
 pub use element::{
     BeamTarget, BeamTargetKind, Border, Emitters, Manipulator, Particle, Piece, Tile, TileKind,
 };
+pub use generate::generate;
 pub use grid::{GridMap, GridSet};
-pub use level::{CampaignData, LevelCampaign, LevelMetadata, LevelOutcome, LevelProgress};
+pub use level::{star_rating, LevelMetadata, LevelOutcome, LevelProgress, MAX_STARS};
+pub use movement::MoveBlockReason;
+pub use random::next_index;
+pub use solver::Move;
 
 pub const MAX_BOARD_ROWS: usize = 15;
 pub const MAX_BOARD_COLS: usize = 15;
@@ -59,6 +74,20 @@ pub struct BoardCoords {
     pub col: usize,
 }
 
+impl Tint {
+    pub fn particle_tints() -> impl Iterator<Item = Tint> {
+        Self::iter().filter(|tint| tint.is_particle_tint())
+    }
+
+    pub fn is_neutral(self) -> bool {
+        self == Self::White
+    }
+
+    pub fn is_particle_tint(self) -> bool {
+        !self.is_neutral()
+    }
+}
+
 impl Direction {
     pub fn orientation(self) -> Orientation {
         match self {
@@ -66,6 +95,34 @@ impl Direction {
             Self::Left | Self::Right => Orientation::Horizontal,
         }
     }
+
+    #[cfg(test)]
+    pub fn rotated_cw(self) -> Self {
+        match self {
+            Self::Up => Self::Right,
+            Self::Right => Self::Down,
+            Self::Down => Self::Left,
+            Self::Left => Self::Up,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn flipped_horizontal(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            _ => self,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn flipped_vertical(self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            _ => self,
+        }
+    }
 }
 
 impl Orientation {
@@ -82,6 +139,10 @@ impl Dimensions {
         Self { rows, cols }
     }
 
+    pub fn transpose(self) -> Self {
+        Self::new(self.cols, self.rows)
+    }
+
     pub fn contains(&self, coords: BoardCoords) -> bool {
         (coords.row < self.rows) && (coords.col < self.cols)
     }
@@ -94,8 +155,25 @@ impl Dimensions {
         BoardCoords::new(idx / self.cols, idx % self.cols)
     }
 
-    fn index(&self, coords: BoardCoords) -> usize {
-        coords.row * self.cols + coords.col
+    pub fn index_checked(&self, coords: BoardCoords) -> Option<usize> {
+        self.contains(coords)
+            .then(|| coords.row * self.cols + coords.col)
+    }
+
+    // NOTE: All grid access goes through here. Unchecked, a column past the edge silently wraps
+    // into the next row. With the `checked` feature, coordinates outside the grid have no index,
+    // so reading them finds nothing and writing them does nothing, rather than panicking.
+    fn index(&self, coords: BoardCoords) -> Option<usize> {
+        if cfg!(feature = "checked") {
+            return self.index_checked(coords);
+        }
+        Some(coords.row * self.cols + coords.col)
+    }
+}
+
+impl Display for Dimensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.rows, self.cols)
     }
 }
 
@@ -119,8 +197,44 @@ impl Debug for BoardCoords {
     }
 }
 
+impl Display for BoardCoords {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.row, self.col)
+    }
+}
+
 impl From<(usize, usize)> for BoardCoords {
     fn from(value: (usize, usize)) -> Self {
         Self::new(value.0, value.1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particle_tints() {
+        let tints: Vec<Tint> = Tint::particle_tints().collect();
+        assert_eq!(tints, vec![Tint::Green, Tint::Yellow, Tint::Red]);
+        assert!(Tint::White.is_neutral());
+        assert!(!Tint::White.is_particle_tint());
+    }
+
+    #[test]
+    fn index_checked() {
+        let dims = Dimensions::new(3, 4);
+        assert_eq!(dims.index_checked((0, 0).into()), Some(0));
+        assert_eq!(dims.index_checked((1, 2).into()), Some(6));
+        assert_eq!(dims.index_checked((2, 3).into()), Some(11));
+        assert_eq!(dims.index_checked((3, 0).into()), None);
+        assert_eq!(dims.index_checked((0, 4).into()), None);
+        assert_eq!(dims.index_checked((3, 4).into()), None);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Dimensions::new(3, 5).to_string(), "3x5");
+        assert_eq!(BoardCoords::new(3, 5).to_string(), "(3, 5)");
+    }
+}
@@ -0,0 +1,327 @@
+use thiserror::Error;
+
+use super::{
+    Board, BoardCoords, Border, Emitters, Manipulator, Particle, Piece, Tile, TileKind, Tint,
+    MAX_BOARD_COLS, MAX_BOARD_ROWS,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AsciiParseError {
+    #[error("expected an odd number of lines, at least 3, got {0}")]
+    LineCount(usize),
+
+    #[error("the first line has {0} characters, which doesn't fit a whole number of cells")]
+    Width(usize),
+
+    #[error("the board is {rows}x{cols}, which is larger than allowed")]
+    TooLarge { rows: usize, cols: usize },
+
+    #[error("line {line} is longer than the first line")]
+    LongLine { line: usize },
+
+    #[error("invalid glyph {glyph:?} at line {line}, column {column}")]
+    InvalidGlyph {
+        line: usize,
+        column: usize,
+        glyph: char,
+    },
+}
+
+// NOTE: Border lines alternate corners with three characters of horizontal border for each cell,
+// and cell lines alternate vertical borders with three characters for each cell: the tile kind,
+// the tile tint, and the piece.
+#[cfg(test)]
+pub fn encode(board: &Board) -> String {
+    let mut text = String::new();
+    for row in 0..=board.dims.rows {
+        for col in 0..=board.dims.cols {
+            let coords = BoardCoords::new(row, col);
+            text.push(glyph(
+                CORNER_GLYPHS,
+                board.corner_borders.get(coords).copied(),
+            ));
+            if col < board.dims.cols {
+                let border = glyph(HORZ_GLYPHS, board.horz_borders.get(coords).copied());
+                text.extend([border; CELL_WIDTH]);
+            }
+        }
+        text.push('\n');
+        if row == board.dims.rows {
+            break;
+        }
+
+        for col in 0..=board.dims.cols {
+            let coords = BoardCoords::new(row, col);
+            text.push(glyph(VERT_GLYPHS, board.vert_borders.get(coords).copied()));
+            if col == board.dims.cols {
+                break;
+            }
+            match board.tiles.get(coords) {
+                Some(tile) => {
+                    text.push(glyph(TILE_KIND_GLYPHS, tile.kind));
+                    text.push(glyph(TINT_GLYPHS, tile.tint));
+                }
+                None => text.push_str("  "),
+            }
+            text.push(match board.pieces.get(coords) {
+                None => ' ',
+                Some(Piece::Particle(particle)) => glyph(PARTICLE_GLYPHS, particle.tint),
+                Some(Piece::Manipulator(manipulator)) => {
+                    glyph(EMITTERS_GLYPHS, manipulator.emitters)
+                }
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+pub fn decode(text: &str) -> Result<Board, AsciiParseError> {
+    let mut lines: Vec<&str> = text.lines().collect();
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    if (lines.len() < 3) || lines.len().is_multiple_of(2) {
+        return Err(AsciiParseError::LineCount(lines.len()));
+    }
+    let width = lines[0].chars().count();
+    if (width < CELL_WIDTH + 2) || !(width - 1).is_multiple_of(CELL_WIDTH + 1) {
+        return Err(AsciiParseError::Width(width));
+    }
+    let rows = lines.len() / 2;
+    let cols = width / (CELL_WIDTH + 1);
+    if (rows > MAX_BOARD_ROWS) || (cols > MAX_BOARD_COLS) {
+        return Err(AsciiParseError::TooLarge { rows, cols });
+    }
+
+    // NOTE: Editors tend to strip trailing whitespace, so short lines are padded with blanks
+    let mut grid = Vec::with_capacity(lines.len());
+    for (line_idx, line) in lines.iter().enumerate() {
+        let mut chars: Vec<char> = line.chars().collect();
+        if chars.len() > width {
+            return Err(AsciiParseError::LongLine { line: line_idx + 1 });
+        }
+        chars.resize(width, ' ');
+        grid.push(chars);
+    }
+    let mut board = Board::new(rows, cols);
+    for row in 0..=rows {
+        let line = row * 2;
+        for col in 0..=cols {
+            let coords = BoardCoords::new(row, col);
+            let column = col * (CELL_WIDTH + 1);
+            let corner = parse_value(CORNER_GLYPHS, &grid, line, column)?;
+            board.corner_borders.set(coords, corner);
+            if col == cols {
+                break;
+            }
+            let border = parse_value(HORZ_GLYPHS, &grid, line, column + 1)?;
+            for column in (column + 2)..=(column + CELL_WIDTH) {
+                if grid[line][column] != grid[line][column - 1] {
+                    return Err(invalid_glyph(&grid, line, column));
+                }
+            }
+            board.horz_borders.set(coords, border);
+        }
+        if row == rows {
+            break;
+        }
+
+        let line = line + 1;
+        for col in 0..=cols {
+            let coords = BoardCoords::new(row, col);
+            let column = col * (CELL_WIDTH + 1);
+            let border = parse_value(VERT_GLYPHS, &grid, line, column)?;
+            board.vert_borders.set(coords, border);
+            if col == cols {
+                break;
+            }
+            let tile = match (grid[line][column + 1], grid[line][column + 2]) {
+                (' ', ' ') => None,
+                _ => {
+                    let kind = parse_value(TILE_KIND_GLYPHS, &grid, line, column + 1)?;
+                    let tint = parse_value(TINT_GLYPHS, &grid, line, column + 2)?;
+                    Some(Tile::new(kind, tint))
+                }
+            };
+            board.tiles.set(coords, tile);
+            let glyph = grid[line][column + 3];
+            let piece = if glyph == ' ' {
+                None
+            } else if let Some(tint) = value(PARTICLE_GLYPHS, glyph) {
+                Some(Piece::Particle(Particle::new(tint)))
+            } else {
+                let emitters = parse_value(EMITTERS_GLYPHS, &grid, line, column + 3)?;
+                Some(Piece::Manipulator(Manipulator::new(emitters)))
+            };
+            board.pieces.set(coords, piece);
+        }
+    }
+    board.retarget_beams();
+
+    Ok(board)
+}
+
+fn parse_value<T: Copy + PartialEq>(
+    table: &[(T, char)],
+    grid: &[Vec<char>],
+    line: usize,
+    column: usize,
+) -> Result<T, AsciiParseError> {
+    value(table, grid[line][column]).ok_or_else(|| invalid_glyph(grid, line, column))
+}
+
+fn invalid_glyph(grid: &[Vec<char>], line: usize, column: usize) -> AsciiParseError {
+    AsciiParseError::InvalidGlyph {
+        line: line + 1,
+        column: column + 1,
+        glyph: grid[line][column],
+    }
+}
+
+#[cfg(test)]
+fn glyph<T: Copy + PartialEq>(table: &[(T, char)], value: T) -> char {
+    table
+        .iter()
+        .find(|(entry, _)| *entry == value)
+        .map(|&(_, glyph)| glyph)
+        .unwrap()
+}
+
+fn value<T: Copy + PartialEq>(table: &[(T, char)], glyph: char) -> Option<T> {
+    table
+        .iter()
+        .find(|(_, entry)| *entry == glyph)
+        .map(|&(value, _)| value)
+}
+
+const CELL_WIDTH: usize = 3;
+
+const CORNER_GLYPHS: &[(Option<Border>, char)] = &[
+    (None, '+'),
+    (Some(Border::Wall), '#'),
+    (Some(Border::Window), ':'),
+];
+const HORZ_GLYPHS: &[(Option<Border>, char)] = &[
+    (None, ' '),
+    (Some(Border::Wall), '-'),
+    (Some(Border::Window), ':'),
+];
+const VERT_GLYPHS: &[(Option<Border>, char)] = &[
+    (None, ' '),
+    (Some(Border::Wall), '|'),
+    (Some(Border::Window), ':'),
+];
+const TILE_KIND_GLYPHS: &[(TileKind, char)] = &[
+    (TileKind::Platform, '.'),
+    (TileKind::Collector, 'O'),
+    (TileKind::Recolor, '*'),
+];
+const TINT_GLYPHS: &[(Tint, char)] = &[
+    (Tint::White, 'w'),
+    (Tint::Green, 'g'),
+    (Tint::Yellow, 'y'),
+    (Tint::Red, 'r'),
+];
+const PARTICLE_GLYPHS: &[(Tint, char)] =
+    &[(Tint::Green, 'G'), (Tint::Yellow, 'Y'), (Tint::Red, 'R')];
+const EMITTERS_GLYPHS: &[(Emitters, char)] = &[
+    (Emitters::Left, '<'),
+    (Emitters::Up, '^'),
+    (Emitters::Right, '>'),
+    (Emitters::Down, 'v'),
+    (Emitters::LeftUp, 'J'),
+    (Emitters::LeftDown, '7'),
+    (Emitters::RightUp, 'L'),
+    (Emitters::RightDown, 'F'),
+    (Emitters::LeftRight, '='),
+    (Emitters::UpDown, 'H'),
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Direction, CLASSIC_CAMPAIGN_DATA};
+
+    use super::*;
+
+    #[test]
+    fn campaign_round_trip() {
+        for (_, level_data) in CLASSIC_CAMPAIGN_DATA {
+            for (name, pbc) in *level_data {
+                let board = Board::from_pbc1(pbc).unwrap();
+                let text = board.to_ascii();
+                let decoded =
+                    Board::from_ascii(&text).unwrap_or_else(|err| panic!("{}: {}", name, err));
+                assert!(decoded.layout_eq(&board), "{}", name);
+                assert_eq!(decoded.to_ascii(), text, "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn decode_glyphs() {
+        let text = "\
++---+---+---#
+|.w> OgG:*r |
++   +:::+   +
+";
+        let board = decode(text).unwrap();
+        assert_eq!(board.dims.rows, 1);
+        assert_eq!(board.dims.cols, 3);
+
+        let Some(Piece::Manipulator(manipulator)) = board.pieces.get((0, 0).into()) else {
+            panic!("expected a manipulator");
+        };
+        assert_eq!(manipulator.emitters, Emitters::Right);
+        let target = manipulator.target(Direction::Right).unwrap();
+        assert_eq!(target.coords, (0, 1).into());
+        let Some(Piece::Particle(particle)) = board.pieces.get((0, 1).into()) else {
+            panic!("expected a particle");
+        };
+        assert_eq!(particle.tint, Tint::Green);
+        assert!(board.is_collected((0, 1).into()));
+        assert_eq!(
+            board.tiles.get((0, 2).into()).unwrap().kind,
+            TileKind::Recolor
+        );
+
+        assert_eq!(board.horz_borders.get((0, 0).into()), Some(&Border::Wall));
+        assert_eq!(board.horz_borders.get((1, 1).into()), Some(&Border::Window));
+        assert_eq!(board.horz_borders.get((1, 0).into()), None);
+        assert_eq!(board.vert_borders.get((0, 0).into()), Some(&Border::Wall));
+        assert_eq!(board.vert_borders.get((0, 2).into()), Some(&Border::Window));
+        assert_eq!(board.vert_borders.get((0, 3).into()), Some(&Border::Wall));
+        assert_eq!(board.corner_borders.get((0, 3).into()), Some(&Border::Wall));
+        assert_eq!(board.corner_borders.get((0, 0).into()), None);
+    }
+
+    #[test]
+    fn decode_errors() {
+        let error = |text: &str| decode(text).err();
+        assert_eq!(error("+---+\n"), Some(AsciiParseError::LineCount(1)));
+        assert_eq!(
+            error("+--+\n|   |\n+--+\n"),
+            Some(AsciiParseError::Width(4))
+        );
+        assert_eq!(
+            error("+---+\n|.w?|\n+---+\n"),
+            Some(AsciiParseError::InvalidGlyph {
+                line: 2,
+                column: 4,
+                glyph: '?'
+            })
+        );
+        assert_eq!(
+            error("+-:-+\n|   |\n+---+\n"),
+            Some(AsciiParseError::InvalidGlyph {
+                line: 1,
+                column: 3,
+                glyph: ':'
+            })
+        );
+        assert_eq!(
+            error("+---+\n|     |\n+---+\n"),
+            Some(AsciiParseError::LongLine { line: 2 })
+        );
+    }
+}
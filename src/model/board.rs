@@ -1,10 +1,18 @@
+use std::hash::Hasher;
+
+use enum_map::EnumMap;
 use enumset::EnumSet;
 use strum::IntoEnumIterator;
 
-use super::grid::{GridMap, GridSet};
-use super::movement::MoveSolver;
-use super::pbc1::Pbc1DecodeError;
-use super::{BeamTarget, BoardCoords, Border, Dimensions, Direction, Orientation, Piece, Tile};
+use super::ascii::AsciiParseError;
+use super::grid::{Grid, GridMap, GridSet};
+use super::movement::{MoveBlockReason, MoveSolver};
+use super::pbc1::{Pbc1DecodeError, Pbc1EncodeError};
+use super::solver::Move;
+use super::{
+    BeamTarget, BeamTargetKind, BoardCoords, Border, Dimensions, Direction, Emitters, Manipulator,
+    Orientation, Particle, Piece, Tile, TileKind,
+};
 
 #[derive(Clone)]
 pub struct Board {
@@ -12,16 +20,32 @@ pub struct Board {
     pub tiles: GridMap<Tile>,
     pub horz_borders: GridMap<Border>,
     pub vert_borders: GridMap<Border>,
+    pub corner_borders: GridMap<Border>,
     pub pieces: GridMap<Piece>,
 }
 
+pub struct BoardBuilder {
+    board: Board,
+}
+
+// NOTE: FNV-1a, rather than the standard library hasher, whose output may change between
+// releases
+struct StableHasher(u64);
+
+#[derive(Debug, Clone, Copy)]
+enum BoardTransform {
+    FlipHorizontal,
+    FlipVertical,
+    RotateCw,
+}
+
 impl Board {
-    #[cfg(test)]
     pub fn new(rows: usize, cols: usize) -> Self {
         let dims = Dimensions::new(rows, cols);
         let tiles = GridMap::new(rows, cols);
         let horz_borders = GridMap::new(rows + 1, cols);
         let vert_borders = GridMap::new(rows, cols + 1);
+        let corner_borders = GridMap::new(rows + 1, cols + 1);
         let pieces = GridMap::new(rows, cols);
 
         Self {
@@ -29,6 +53,7 @@ impl Board {
             tiles,
             horz_borders,
             vert_borders,
+            corner_borders,
             pieces,
         }
     }
@@ -37,6 +62,19 @@ impl Board {
         super::pbc1::decode(code)
     }
 
+    pub fn to_pbc1(&self) -> Result<String, Pbc1EncodeError> {
+        super::pbc1::encode(self)
+    }
+
+    pub fn from_ascii(text: &str) -> Result<Self, AsciiParseError> {
+        super::ascii::decode(text)
+    }
+
+    #[cfg(test)]
+    pub fn to_ascii(&self) -> String {
+        super::ascii::encode(self)
+    }
+
     pub fn copy_state_from(&mut self, other: &Self) {
         assert_eq!(self.dims.rows, other.dims.rows);
         assert_eq!(self.dims.cols, other.dims.cols);
@@ -44,6 +82,7 @@ impl Board {
         self.tiles.mirror(&other.tiles);
         self.horz_borders.mirror(&other.horz_borders);
         self.vert_borders.mirror(&other.vert_borders);
+        self.corner_borders.mirror(&other.corner_borders);
         self.pieces.mirror(&other.pieces);
     }
 
@@ -66,6 +105,14 @@ impl Board {
         }
     }
 
+    pub fn neighbors(
+        &self,
+        coords: BoardCoords,
+    ) -> impl Iterator<Item = (Direction, BoardCoords)> + '_ {
+        Direction::iter()
+            .filter_map(move |direction| Some((direction, self.neighbor(coords, direction)?)))
+    }
+
     pub fn borders(&self, orientation: Orientation) -> &GridMap<Border> {
         match orientation {
             Orientation::Horizontal => &self.horz_borders,
@@ -82,6 +129,7 @@ impl Board {
         move_set.for_each(direction, |from_coords| {
             let to_coords = self.neighbor(from_coords, direction).unwrap();
             self.move_piece(from_coords, to_coords);
+            self.recolor_particle(to_coords);
         });
     }
 
@@ -104,17 +152,101 @@ impl Board {
         }
     }
 
+    pub fn beam_length(&self, coords: BoardCoords, direction: Direction) -> Option<usize> {
+        let target = self
+            .pieces
+            .get(coords)?
+            .as_manipulator()?
+            .target(direction)?;
+        let distance = match direction.orientation() {
+            Orientation::Vertical => target.coords.row.abs_diff(coords.row),
+            Orientation::Horizontal => target.coords.col.abs_diff(coords.col),
+        };
+        // NOTE: The length counts the cells between the manipulator and its target. Border
+        // coordinates match the last crossed cell for Up and Left, but are one past it for Down
+        // and Right.
+        Some(match (target.kind, direction) {
+            (BeamTargetKind::Piece, _) => distance - 1,
+            (BeamTargetKind::Border, Direction::Up | Direction::Left) => distance,
+            (BeamTargetKind::Border, Direction::Down | Direction::Right) => distance - 1,
+        })
+    }
+
+    pub fn beam_path(&self, coords: BoardCoords, direction: Direction) -> Vec<BoardCoords> {
+        let length = self.beam_length(coords, direction).unwrap_or_default();
+        std::iter::successors(self.neighbor(coords, direction), |&coords| {
+            self.neighbor(coords, direction)
+        })
+        .take(length)
+        .collect()
+    }
+
+    // NOTE: Only the cells between a manipulator and its target are covered by its beam, so pieces
+    // never count as covered
+    pub fn beam_coverage(&self, orientation: Orientation) -> GridMap<u8> {
+        let mut coverage = GridMap::like(&self.pieces);
+        for (coords, manipulator) in self.manipulators() {
+            for direction in manipulator.emitters.directions() {
+                if direction.orientation() != orientation {
+                    continue;
+                }
+                for cell in self.beam_path(coords, direction) {
+                    *coverage.get_or_insert_with(cell, || 0) += 1;
+                }
+            }
+        }
+        coverage
+    }
+
     pub fn compute_allowed_moves(&self, coords: BoardCoords) -> EnumSet<Direction> {
-        let solver = MoveSolver::new(self, coords);
+        let mut solver = MoveSolver::new(self, coords);
         Direction::iter()
-            .filter(|&direction| solver.clone().can_move(direction))
+            .filter(|&direction| solver.can_move(direction))
             .collect()
     }
 
+    pub fn compute_allowed_moves_with_reasons(
+        &self,
+        coords: BoardCoords,
+    ) -> EnumMap<Direction, MoveBlockReason> {
+        let mut solver = MoveSolver::new(self, coords);
+        EnumMap::from_fn(|direction| solver.explain(direction))
+    }
+
+    pub fn all_allowed_moves(&self) -> GridMap<EnumSet<Direction>> {
+        let mut allowed = GridMap::like(&self.pieces);
+        for (coords, _) in self.manipulators() {
+            allowed.set(coords, self.compute_allowed_moves(coords));
+        }
+        allowed
+    }
+
+    pub fn any_move_available(&self) -> bool {
+        self.manipulators().any(|(coords, _)| {
+            let mut solver = MoveSolver::new(self, coords);
+            Direction::iter().any(|direction| solver.can_move(direction))
+        })
+    }
+
     pub fn compute_move_set(&self, piece_coords: BoardCoords, direction: Direction) -> GridSet {
         MoveSolver::new(self, piece_coords).drag(direction)
     }
 
+    // NOTE: Moves are only issued in allowed directions, but should the caller ever disagree with
+    // the board, this refuses the move instead of dragging a set the leader isn't part of.
+    pub fn compute_checked_move_set(
+        &self,
+        piece_coords: BoardCoords,
+        direction: Direction,
+    ) -> Option<GridSet> {
+        let mut solver = MoveSolver::new(self, piece_coords);
+        if !solver.can_move(direction) {
+            return None;
+        }
+        let move_set = solver.drag(direction);
+        move_set.contains(piece_coords).then_some(move_set)
+    }
+
     pub fn prev_manipulator(&self, coords: Option<BoardCoords>) -> Option<BoardCoords> {
         // NOTE: An active board should never have 0 manipulators
         let mut coords = coords.unwrap_or_default();
@@ -163,14 +295,246 @@ impl Board {
         None
     }
 
+    // NOTE: The manipulator at the given coords is only returned if no other one can move
+    pub fn next_movable_manipulator(&self, coords: BoardCoords) -> Option<BoardCoords> {
+        let mut next = coords;
+        for _ in 0..(self.dims.rows * self.dims.cols) {
+            next = self.next_manipulator(Some(next))?;
+            if !self.compute_allowed_moves(next).is_empty() {
+                return Some(next);
+            }
+            if next == coords {
+                break;
+            }
+        }
+        None
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        self.transform(BoardTransform::FlipHorizontal);
+    }
+
+    pub fn flip_vertical(&mut self) {
+        self.transform(BoardTransform::FlipVertical);
+    }
+
+    pub fn rotate_90(&mut self) {
+        self.transform(BoardTransform::RotateCw);
+    }
+
     pub fn unsupported_pieces(&self) -> GridSet {
         super::support::unsupported_pieces(self)
     }
 
+    pub fn pieces_supported_by(&self, coords: BoardCoords) -> GridSet {
+        let unsupported = self.unsupported_pieces();
+        let mut board = self.clone();
+        board.remove_piece(coords);
+        board.retarget_beams();
+
+        let mut supported = GridSet::like(&self.pieces);
+        for coords in board.unsupported_pieces().iter() {
+            if !unsupported.contains(coords) {
+                supported.insert(coords);
+            }
+        }
+        supported
+    }
+
     pub fn remove_piece(&mut self, coords: BoardCoords) {
         self.pieces.take(coords);
     }
 
+    // NOTE: Beam targets are derived from the rest of the board, so they are left out
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        hasher.write_usize(self.dims.rows);
+        hasher.write_usize(self.dims.cols);
+        for coords in self.dims.iter() {
+            hasher.write_u8(match self.tiles.get(coords) {
+                None => 0,
+                Some(tile) => 1 + ((tile.kind as u8) << 2) + tile.tint as u8,
+            });
+            hasher.write_u8(match self.pieces.get(coords) {
+                None => 0,
+                Some(Piece::Particle(particle)) => 1 + particle.tint as u8,
+                Some(Piece::Manipulator(manipulator)) => 16 + manipulator.emitters as u8,
+            });
+        }
+        for borders in [&self.horz_borders, &self.vert_borders, &self.corner_borders] {
+            for coords in borders.dims().iter() {
+                hasher.write_u8(match borders.get(coords) {
+                    None => 0,
+                    Some(Border::Wall) => 1,
+                    Some(Border::Window) => 2,
+                });
+            }
+        }
+        hasher.finish()
+    }
+
+    // NOTE: Like the content hash, this ignores beam targets, so a board that hasn't been
+    // retargeted yet still compares equal to one that has.
+    #[cfg(test)]
+    pub fn layout_eq(&self, other: &Self) -> bool {
+        if self.dims != other.dims {
+            return false;
+        }
+        let cells_eq = self.dims.iter().all(|coords| {
+            let tiles_eq = match (self.tiles.get(coords), other.tiles.get(coords)) {
+                (None, None) => true,
+                (Some(tile), Some(other)) => (tile.kind == other.kind) && (tile.tint == other.tint),
+                _ => false,
+            };
+            let pieces_eq = match (self.pieces.get(coords), other.pieces.get(coords)) {
+                (None, None) => true,
+                (Some(Piece::Particle(particle)), Some(Piece::Particle(other))) => {
+                    particle.tint == other.tint
+                }
+                (Some(Piece::Manipulator(manipulator)), Some(Piece::Manipulator(other))) => {
+                    manipulator.emitters == other.emitters
+                }
+                _ => false,
+            };
+            tiles_eq && pieces_eq
+        });
+        let borders_eq = [
+            (&self.horz_borders, &other.horz_borders),
+            (&self.vert_borders, &other.vert_borders),
+            (&self.corner_borders, &other.corner_borders),
+        ]
+        .into_iter()
+        .all(|(borders, other)| {
+            borders
+                .dims()
+                .iter()
+                .all(|coords| borders.get(coords) == other.get(coords))
+        });
+        cells_eq && borders_eq
+    }
+
+    pub fn manipulators(&self) -> impl Iterator<Item = (BoardCoords, &Manipulator)> {
+        self.pieces
+            .iter()
+            .filter_map(|(coords, piece)| Some((coords, piece.as_manipulator()?)))
+    }
+
+    pub fn particles(&self) -> impl Iterator<Item = (BoardCoords, &Particle)> {
+        self.pieces
+            .iter()
+            .filter_map(|(coords, piece)| Some((coords, piece.as_particle()?)))
+    }
+
+    pub fn manipulator_count(&self) -> usize {
+        self.manipulators().count()
+    }
+
+    // NOTE: A collected particle stays where it is for the rest of the level. It still stops beams
+    // and blocks other pieces, but can no longer be moved.
+    pub fn is_collected(&self, coords: BoardCoords) -> bool {
+        matches!(
+            (self.pieces.get(coords), self.tiles.get(coords)),
+            (
+                Some(Piece::Particle(_)),
+                Some(Tile {
+                    kind: TileKind::Collector,
+                    ..
+                })
+            )
+        )
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.pieces.iter().all(|(coords, piece)| match piece {
+            Piece::Particle(particle) => match self.tiles.get(coords) {
+                Some(Tile {
+                    kind: TileKind::Collector,
+                    tint,
+                    ..
+                }) => tint.is_neutral() || (*tint == particle.tint),
+                _ => false,
+            },
+            Piece::Manipulator(_) => true,
+        })
+    }
+
+    // NOTE: Pieces are removed once they are found to be unsupported, so this can only detect a
+    // lost particle before the removal happens.
+    pub fn has_lost(&self) -> bool {
+        if self.manipulator_count() == 0 {
+            return true;
+        }
+        self.unsupported_pieces()
+            .iter()
+            .any(|coords| matches!(self.pieces.get(coords), Some(Piece::Particle(_))))
+    }
+
+    pub fn solve(&self) -> Option<Vec<Move>> {
+        super::solver::solve(self)
+    }
+
+    fn transform(&mut self, transform: BoardTransform) {
+        let dims = transform.dims(self.dims);
+        let mut tiles = GridMap::new(dims.rows, dims.cols);
+        let mut horz_borders = GridMap::new(dims.rows + 1, dims.cols);
+        let mut vert_borders = GridMap::new(dims.rows, dims.cols + 1);
+        let mut corner_borders = GridMap::new(dims.rows + 1, dims.cols + 1);
+        let mut pieces = GridMap::new(dims.rows, dims.cols);
+
+        for (coords, tile) in self.tiles.iter() {
+            tiles.set(transform.coords(self.dims, coords), tile.clone());
+        }
+        for orientation in Orientation::iter() {
+            for (coords, &border) in self.borders(orientation).iter() {
+                let (orientation, coords) = transform.border_coords(self.dims, orientation, coords);
+                let borders = match orientation {
+                    Orientation::Horizontal => &mut horz_borders,
+                    Orientation::Vertical => &mut vert_borders,
+                };
+                borders.set(coords, border);
+            }
+        }
+        for (coords, &border) in self.corner_borders.iter() {
+            corner_borders.set(transform.corner_coords(self.dims, coords), border);
+        }
+        for (coords, piece) in self.pieces.iter() {
+            let piece = match piece {
+                Piece::Particle(particle) => Piece::Particle(particle.clone()),
+                Piece::Manipulator(manipulator) => {
+                    Piece::Manipulator(Manipulator::new(transform.emitters(manipulator.emitters)))
+                }
+            };
+            pieces.set(transform.coords(self.dims, coords), piece);
+        }
+
+        *self = Self {
+            dims,
+            tiles,
+            horz_borders,
+            vert_borders,
+            corner_borders,
+            pieces,
+        };
+        self.retarget_beams();
+    }
+
+    fn recolor_particle(&mut self, coords: BoardCoords) {
+        let Some(&Tile {
+            kind: TileKind::Recolor,
+            tint,
+            ..
+        }) = self.tiles.get(coords)
+        else {
+            return;
+        };
+        if tint.is_neutral() {
+            return;
+        }
+        if let Some(Piece::Particle(particle)) = self.pieces.get_mut(coords) {
+            particle.tint = tint;
+        }
+    }
+
     fn find_beam_target(&self, coords: BoardCoords, direction: Direction) -> BeamTarget {
         let mut piece_coords = coords;
         let border_orientation = direction.orientation().flip();
@@ -184,9 +548,716 @@ impl Board {
                 Some(neighbor) => neighbor,
                 None => return BeamTarget::border(border_coords),
             };
+            // NOTE: Corner borders only separate diagonal neighbors, so they never stop a beam.
+            // Collected particles are not transparent to beams, see `is_collected`.
             if self.pieces.get(piece_coords).is_some() {
                 return BeamTarget::piece(piece_coords);
             }
         }
     }
 }
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write(&(value as u64).to_le_bytes());
+    }
+}
+
+impl BoardBuilder {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            board: Board::new(rows, cols),
+        }
+    }
+
+    pub fn with_tile(mut self, coords: BoardCoords, tile: Tile) -> Self {
+        self.board.tiles.set(coords, tile);
+        self
+    }
+
+    pub fn with_piece<P: Into<Option<Piece>>>(mut self, coords: BoardCoords, piece: P) -> Self {
+        self.board.pieces.set(coords, piece);
+        self
+    }
+
+    pub fn with_horz_border(mut self, coords: BoardCoords, border: Border) -> Self {
+        self.board.horz_borders.set(coords, border);
+        self
+    }
+
+    pub fn with_vert_border(mut self, coords: BoardCoords, border: Border) -> Self {
+        self.board.vert_borders.set(coords, border);
+        self
+    }
+
+    pub fn build(mut self) -> Board {
+        self.board.retarget_beams();
+        self.board
+    }
+}
+
+impl BoardTransform {
+    fn dims(self, dims: Dimensions) -> Dimensions {
+        match self {
+            Self::FlipHorizontal | Self::FlipVertical => dims,
+            Self::RotateCw => dims.transpose(),
+        }
+    }
+
+    fn coords(self, dims: Dimensions, coords: BoardCoords) -> BoardCoords {
+        match self {
+            Self::FlipHorizontal => (coords.row, dims.cols - 1 - coords.col).into(),
+            Self::FlipVertical => (dims.rows - 1 - coords.row, coords.col).into(),
+            Self::RotateCw => (coords.col, dims.rows - 1 - coords.row).into(),
+        }
+    }
+
+    fn border_coords(
+        self,
+        dims: Dimensions,
+        orientation: Orientation,
+        coords: BoardCoords,
+    ) -> (Orientation, BoardCoords) {
+        // NOTE: Border grids are one cell larger than the board along the axis that crosses them,
+        // so their coordinates don't map the same way as tile coordinates.
+        match (self, orientation) {
+            (Self::FlipHorizontal, Orientation::Horizontal) => {
+                (orientation, (coords.row, dims.cols - 1 - coords.col).into())
+            }
+            (Self::FlipHorizontal, Orientation::Vertical) => {
+                (orientation, (coords.row, dims.cols - coords.col).into())
+            }
+            (Self::FlipVertical, Orientation::Horizontal) => {
+                (orientation, (dims.rows - coords.row, coords.col).into())
+            }
+            (Self::FlipVertical, Orientation::Vertical) => {
+                (orientation, (dims.rows - 1 - coords.row, coords.col).into())
+            }
+            (Self::RotateCw, Orientation::Horizontal) => (
+                Orientation::Vertical,
+                (coords.col, dims.rows - coords.row).into(),
+            ),
+            (Self::RotateCw, Orientation::Vertical) => (
+                Orientation::Horizontal,
+                (coords.col, dims.rows - 1 - coords.row).into(),
+            ),
+        }
+    }
+
+    // NOTE: Corner coordinates name the top left corner of the cell with the same coordinates, and
+    // the corner grid is one larger than the board along both axes.
+    fn corner_coords(self, dims: Dimensions, coords: BoardCoords) -> BoardCoords {
+        match self {
+            Self::FlipHorizontal => (coords.row, dims.cols - coords.col).into(),
+            Self::FlipVertical => (dims.rows - coords.row, coords.col).into(),
+            Self::RotateCw => (coords.col, dims.rows - coords.row).into(),
+        }
+    }
+
+    #[cfg(test)]
+    fn direction(self, direction: Direction) -> Direction {
+        match self {
+            Self::FlipHorizontal => direction.flipped_horizontal(),
+            Self::FlipVertical => direction.flipped_vertical(),
+            Self::RotateCw => direction.rotated_cw(),
+        }
+    }
+
+    fn emitters(self, emitters: Emitters) -> Emitters {
+        match self {
+            Self::FlipHorizontal => emitters.flipped_horizontal(),
+            Self::FlipVertical => emitters.flipped_vertical(),
+            Self::RotateCw => emitters.rotated_cw(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::model::{
+        next_index, BoardBuilder, Emitters, Manipulator, Particle, Tint, CLASSIC_CAMPAIGN_DATA,
+    };
+
+    use super::*;
+
+    #[test]
+    fn flip_horizontal() {
+        assert_beams_transformed(BoardTransform::FlipHorizontal, Board::flip_horizontal);
+    }
+
+    #[test]
+    fn flip_vertical() {
+        assert_beams_transformed(BoardTransform::FlipVertical, Board::flip_vertical);
+    }
+
+    #[test]
+    fn rotate_90() {
+        assert_beams_transformed(BoardTransform::RotateCw, Board::rotate_90);
+    }
+
+    #[test]
+    fn full_rotation() {
+        let original = sample_board();
+        let mut board = original.clone();
+        for _ in 0..4 {
+            board.rotate_90();
+        }
+        assert_eq!(board.dims, original.dims);
+        for coords in original.dims.iter() {
+            assert_eq!(
+                board.pieces.get(coords).map(piece_signature),
+                original.pieces.get(coords).map(piece_signature)
+            );
+        }
+    }
+
+    #[test]
+    fn transform_corner_borders() {
+        let corners = |board: &Board| {
+            board
+                .corner_borders
+                .iter()
+                .map(|(coords, &border)| (coords, border))
+                .collect::<Vec<_>>()
+        };
+        let mut board = Board::new(3, 4);
+        board.corner_borders.set((0, 4).into(), Border::Wall);
+        board.corner_borders.set((2, 1).into(), Border::Window);
+
+        let mut flipped = board.clone();
+        flipped.flip_horizontal();
+        assert_eq!(
+            corners(&flipped),
+            vec![
+                ((0, 0).into(), Border::Wall),
+                ((2, 3).into(), Border::Window)
+            ]
+        );
+
+        let mut flipped = board.clone();
+        flipped.flip_vertical();
+        assert_eq!(
+            corners(&flipped),
+            vec![
+                ((1, 1).into(), Border::Window),
+                ((3, 4).into(), Border::Wall)
+            ]
+        );
+
+        let mut rotated = board.clone();
+        rotated.rotate_90();
+        assert_eq!(
+            corners(&rotated),
+            vec![
+                ((1, 1).into(), Border::Window),
+                ((4, 3).into(), Border::Wall)
+            ]
+        );
+    }
+
+    #[test]
+    fn content_hash() {
+        let board = sample_board();
+        let mut untargeted = board.clone();
+        for coords in untargeted.dims.iter() {
+            if let Some(Piece::Manipulator(manipulator)) = untargeted.pieces.get(coords) {
+                let emitters = manipulator.emitters;
+                untargeted.pieces.set(coords, Manipulator::new(emitters));
+            }
+        }
+        assert_eq!(untargeted.content_hash(), board.content_hash());
+
+        let mut flipped = board.clone();
+        flipped.flip_horizontal();
+        assert_ne!(flipped.content_hash(), board.content_hash());
+
+        let mut cornered = board.clone();
+        cornered.corner_borders.set((1, 1).into(), Border::Wall);
+        assert_ne!(cornered.content_hash(), board.content_hash());
+    }
+
+    #[test]
+    fn layout_eq() {
+        let board = sample_board();
+        let mut untargeted = board.clone();
+        for coords in untargeted.dims.iter() {
+            if let Some(Piece::Manipulator(manipulator)) = untargeted.pieces.get(coords) {
+                let emitters = manipulator.emitters;
+                untargeted.pieces.set(coords, Manipulator::new(emitters));
+            }
+        }
+        assert!(untargeted.layout_eq(&board));
+
+        let mut flipped = board.clone();
+        flipped.flip_horizontal();
+        assert!(!flipped.layout_eq(&board));
+
+        let mut cornered = board.clone();
+        cornered.corner_borders.set((1, 1).into(), Border::Window);
+        assert!(!cornered.layout_eq(&board));
+
+        assert!(!Board::new(board.dims.cols, board.dims.rows).layout_eq(&board));
+    }
+
+    #[test]
+    fn no_duplicate_campaign_levels() {
+        let mut seen = HashMap::new();
+        for (_, level_data) in CLASSIC_CAMPAIGN_DATA {
+            for (name, pbc) in *level_data {
+                let hash = Board::from_pbc1(pbc).unwrap().content_hash();
+                if let Some(other) = seen.insert(hash, name) {
+                    panic!("{} duplicates {}", name, other);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighbors() {
+        let board = Board::new(3, 4);
+        let neighbors = |coords: BoardCoords| board.neighbors(coords).collect::<Vec<_>>();
+
+        assert_eq!(
+            neighbors((0, 0).into()),
+            vec![
+                (Direction::Down, (1, 0).into()),
+                (Direction::Right, (0, 1).into())
+            ]
+        );
+        assert_eq!(
+            neighbors((2, 3).into()),
+            vec![
+                (Direction::Up, (1, 3).into()),
+                (Direction::Left, (2, 2).into())
+            ]
+        );
+        assert_eq!(
+            neighbors((0, 2).into()),
+            vec![
+                (Direction::Left, (0, 1).into()),
+                (Direction::Down, (1, 2).into()),
+                (Direction::Right, (0, 3).into()),
+            ]
+        );
+        assert_eq!(
+            neighbors((1, 1).into()),
+            vec![
+                (Direction::Up, (0, 1).into()),
+                (Direction::Left, (1, 0).into()),
+                (Direction::Down, (2, 1).into()),
+                (Direction::Right, (1, 2).into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn builder() {
+        let board = BoardBuilder::new(1, 3)
+            .with_tile((0, 0).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_tile((0, 1).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_tile((0, 2).into(), Tile::new(TileKind::Collector, Tint::Green))
+            .with_piece((0, 0).into(), Manipulator::new(Emitters::Right))
+            .with_piece((0, 1).into(), Particle::new(Tint::Green))
+            .with_vert_border((0, 3).into(), Border::Wall)
+            .build();
+
+        assert_eq!(board.dims, Dimensions::new(1, 3));
+        assert!(board.vert_borders.get((0, 3).into()).is_some());
+        assert!(board.unsupported_pieces().is_empty());
+        assert_eq!(board.beam_length((0, 0).into(), Direction::Right), Some(0));
+        assert_eq!(board.solve().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn objectives() {
+        let builder = || {
+            BoardBuilder::new(2, 3)
+                .with_tile((0, 0).into(), Tile::new(TileKind::Platform, Tint::White))
+                .with_tile((0, 1).into(), Tile::new(TileKind::Platform, Tint::White))
+                .with_tile((0, 2).into(), Tile::new(TileKind::Collector, Tint::White))
+                .with_tile((1, 2).into(), Tile::new(TileKind::Collector, Tint::Yellow))
+        };
+
+        let mid = builder()
+            .with_piece((0, 0).into(), Manipulator::new(Emitters::Right))
+            .with_piece((0, 1).into(), Particle::new(Tint::Green))
+            .build();
+        assert_eq!(mid.manipulator_count(), 1);
+        assert!(!mid.is_solved());
+        assert!(!mid.has_lost());
+
+        let won = builder()
+            .with_piece((0, 1).into(), Manipulator::new(Emitters::Right))
+            .with_piece((0, 2).into(), Particle::new(Tint::Green))
+            .build();
+        assert!(won.is_solved());
+        assert!(!won.has_lost());
+
+        let wrong_collector = builder()
+            .with_piece((0, 0).into(), Manipulator::new(Emitters::Right))
+            .with_piece((1, 2).into(), Particle::new(Tint::Green))
+            .build();
+        assert!(!wrong_collector.is_solved());
+
+        let dropped = builder()
+            .with_piece((0, 0).into(), Manipulator::new(Emitters::Down))
+            .with_piece((1, 1).into(), Particle::new(Tint::Green))
+            .build();
+        assert!(dropped.has_lost());
+
+        let no_manipulators = builder()
+            .with_piece((0, 2).into(), Particle::new(Tint::Green))
+            .build();
+        assert_eq!(no_manipulators.manipulator_count(), 0);
+        assert!(no_manipulators.is_solved());
+        assert!(no_manipulators.has_lost());
+    }
+
+    #[test]
+    fn collected_particle_stops_beam() {
+        let mut board = BoardBuilder::new(1, 4)
+            .with_tile((0, 0).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_tile((0, 1).into(), Tile::new(TileKind::Collector, Tint::White))
+            .with_tile((0, 2).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_piece((0, 0).into(), Manipulator::new(Emitters::Right))
+            .with_piece((0, 1).into(), Particle::new(Tint::Green))
+            .with_piece((0, 2).into(), Particle::new(Tint::Green))
+            .build();
+        board.retarget_beams();
+
+        assert!(board.is_collected((0, 1).into()));
+        assert!(!board.is_collected((0, 2).into()));
+        assert!(!board.is_collected((0, 0).into()));
+        let manipulator = board.pieces.get((0, 0).into()).unwrap().as_manipulator();
+        let target = manipulator.unwrap().target(Direction::Right).unwrap();
+        assert_eq!(target.kind, BeamTargetKind::Piece);
+        assert_eq!(target.coords, (0, 1).into());
+    }
+
+    #[test]
+    fn all_allowed_moves() {
+        let board = BoardBuilder::new(1, 3)
+            .with_tile((0, 0).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_tile((0, 1).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_tile((0, 2).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_piece((0, 0).into(), Manipulator::new(Emitters::Up))
+            .with_piece((0, 1).into(), Manipulator::new(Emitters::Up))
+            .build();
+
+        let allowed = board.all_allowed_moves();
+        assert_eq!(allowed.get((0, 0).into()), Some(&EnumSet::empty()));
+        assert_eq!(
+            allowed.get((0, 1).into()),
+            Some(&EnumSet::only(Direction::Right))
+        );
+        assert_eq!(allowed.get((0, 2).into()), None);
+
+        assert_eq!(
+            board.next_movable_manipulator((0, 0).into()),
+            Some((0, 1).into())
+        );
+        assert_eq!(
+            board.next_movable_manipulator((0, 1).into()),
+            Some((0, 1).into())
+        );
+    }
+
+    #[test]
+    fn boxed_in() {
+        let mut board = BoardBuilder::new(1, 2)
+            .with_tile((0, 0).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_tile((0, 1).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_piece((0, 0).into(), Manipulator::new(Emitters::Right))
+            .with_vert_border((0, 1).into(), Border::Wall)
+            .build();
+        assert!(!board.any_move_available());
+
+        board.vert_borders.set((0, 1).into(), None);
+        assert!(board.any_move_available());
+    }
+
+    #[test]
+    fn checked_move_set() {
+        let board = BoardBuilder::new(1, 3)
+            .with_tile((0, 0).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_tile((0, 1).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_tile((0, 2).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_piece((0, 0).into(), Manipulator::new(Emitters::Up))
+            .with_piece((0, 1).into(), Manipulator::new(Emitters::Up))
+            .build();
+
+        let move_set = board
+            .compute_checked_move_set((0, 1).into(), Direction::Right)
+            .unwrap();
+        assert!(move_set.contains((0, 1).into()));
+        assert_eq!(move_set.len(), 1);
+
+        assert!(board
+            .compute_checked_move_set((0, 1).into(), Direction::Left)
+            .is_none());
+        assert!(board
+            .compute_checked_move_set((0, 0).into(), Direction::Right)
+            .is_none());
+    }
+
+    #[test]
+    fn beam_length() {
+        let mut board = Board::new(5, 6);
+        add_manipulator(&mut board, (1, 1).into(), Emitters::Right);
+        board.pieces.set((1, 2).into(), Particle::new(Tint::Green));
+        board.pieces.set((1, 3).into(), Particle::new(Tint::Green));
+        add_manipulator(&mut board, (2, 1).into(), Emitters::Up);
+        add_manipulator(&mut board, (2, 2).into(), Emitters::RightDown);
+        add_manipulator(&mut board, (2, 3).into(), Emitters::RightUp);
+        board.pieces.set((2, 4).into(), Particle::new(Tint::Green));
+        add_manipulator(&mut board, (3, 1).into(), Emitters::Up);
+        add_manipulator(&mut board, (3, 2).into(), Emitters::LeftRight);
+        add_manipulator(&mut board, (3, 4).into(), Emitters::Up);
+        add_manipulator(&mut board, (4, 0).into(), Emitters::RightUp);
+        board.horz_borders.set((1, 3).into(), Border::Wall);
+        board.horz_borders.set((3, 4).into(), Border::Window);
+        board.vert_borders.set((4, 4).into(), Border::Wall);
+        board.retarget_beams();
+
+        assert_eq!(board.beam_length((1, 1).into(), Direction::Right), Some(0));
+        assert_eq!(board.beam_length((1, 1).into(), Direction::Left), None);
+        assert_eq!(board.beam_length((1, 2).into(), Direction::Right), None);
+        assert_eq!(board.beam_length((2, 3).into(), Direction::Up), Some(0));
+        assert_eq!(board.beam_length((3, 2).into(), Direction::Left), Some(0));
+        assert_eq!(board.beam_length((3, 2).into(), Direction::Right), Some(1));
+        assert_eq!(board.beam_length((3, 4).into(), Direction::Up), Some(0));
+        assert_eq!(board.beam_length((4, 0).into(), Direction::Up), Some(4));
+        assert_eq!(board.beam_length((4, 0).into(), Direction::Right), Some(3));
+    }
+
+    #[test]
+    fn beam_path() {
+        let mut board = Board::new(5, 6);
+        add_manipulator(&mut board, (4, 0).into(), Emitters::RightUp);
+        add_manipulator(&mut board, (4, 3).into(), Emitters::Left);
+        board.pieces.set((3, 5).into(), Particle::new(Tint::Green));
+        board.horz_borders.set((2, 0).into(), Border::Window);
+        board.vert_borders.set((4, 2).into(), Border::Window);
+        board.retarget_beams();
+
+        assert_eq!(
+            board.beam_path((4, 0).into(), Direction::Up),
+            vec![(3, 0).into(), (2, 0).into(), (1, 0).into(), (0, 0).into()]
+        );
+        assert_eq!(
+            board.beam_path((4, 0).into(), Direction::Right),
+            vec![(4, 1).into(), (4, 2).into()]
+        );
+        assert_eq!(
+            board.beam_path((4, 3).into(), Direction::Left),
+            vec![(4, 2).into(), (4, 1).into()]
+        );
+        assert!(board.beam_path((4, 3).into(), Direction::Up).is_empty());
+    }
+
+    #[test]
+    fn beam_coverage() {
+        let mut board = Board::new(5, 6);
+        add_manipulator(&mut board, (4, 0).into(), Emitters::RightUp);
+        add_manipulator(&mut board, (4, 3).into(), Emitters::Left);
+        add_manipulator(&mut board, (3, 1).into(), Emitters::Down);
+        board.retarget_beams();
+
+        let horz = board.beam_coverage(Orientation::Horizontal);
+        assert_eq!(horz.get((4, 1).into()), Some(&2));
+        assert_eq!(horz.get((4, 2).into()), Some(&2));
+        assert_eq!(horz.iter().count(), 2);
+        let vert = board.beam_coverage(Orientation::Vertical);
+        assert_eq!(vert.get((4, 1).into()), Some(&1));
+        assert_eq!(vert.get((3, 0).into()), Some(&1));
+        assert_eq!(vert.get((4, 0).into()), None);
+        assert_eq!(vert.iter().count(), 5);
+    }
+
+    fn assert_beams_transformed(transform: BoardTransform, func: impl Fn(&mut Board)) {
+        let original = sample_board();
+        let mut board = original.clone();
+        func(&mut board);
+        assert_eq!(board.dims, transform.dims(original.dims));
+
+        for (coords, piece) in original.pieces.iter() {
+            let Piece::Manipulator(manipulator) = piece else {
+                continue;
+            };
+            let transformed = board
+                .pieces
+                .get(transform.coords(original.dims, coords))
+                .unwrap()
+                .as_manipulator()
+                .unwrap();
+            for direction in manipulator.emitters.directions() {
+                let target = manipulator.target(direction).unwrap();
+                let expected = match target.kind {
+                    BeamTargetKind::Piece => transform.coords(original.dims, target.coords),
+                    BeamTargetKind::Border => {
+                        let orientation = direction.orientation().flip();
+                        transform
+                            .border_coords(original.dims, orientation, target.coords)
+                            .1
+                    }
+                };
+                let actual = transformed.target(transform.direction(direction)).unwrap();
+                assert_eq!(actual.kind, target.kind);
+                assert_eq!(actual.coords, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn beam_targets() {
+        for (_, level_data) in CLASSIC_CAMPAIGN_DATA {
+            for (name, pbc) in *level_data {
+                assert_beam_targets(&Board::from_pbc1(pbc).unwrap(), name);
+            }
+        }
+
+        let emitters: Vec<Emitters> = Emitters::iter().collect();
+        let tints = [Tint::Green, Tint::Yellow, Tint::Red];
+        let mut seed = 0x2545f4914f6cdd1d;
+        for board_idx in 0..200 {
+            let rows = 2 + next_index(&mut seed, 7);
+            let cols = 2 + next_index(&mut seed, 7);
+            let mut board = Board::new(rows, cols);
+            for coords in board.dims.iter() {
+                board
+                    .tiles
+                    .set(coords, Tile::new(TileKind::Platform, Tint::White));
+                match next_index(&mut seed, 10) {
+                    0..=2 => {
+                        let tint = tints[next_index(&mut seed, tints.len())];
+                        board.pieces.set(coords, Particle::new(tint));
+                    }
+                    3..=4 => {
+                        let emitters = emitters[next_index(&mut seed, emitters.len())];
+                        board.pieces.set(coords, Manipulator::new(emitters));
+                    }
+                    _ => (),
+                }
+            }
+            for borders in [&mut board.horz_borders, &mut board.vert_borders] {
+                for coords in borders.dims().iter() {
+                    if next_index(&mut seed, 5) == 0 {
+                        borders.set(coords, Border::Wall);
+                    }
+                }
+            }
+            board.retarget_beams();
+            assert_beam_targets(&board, &format!("random board {}", board_idx));
+        }
+    }
+
+    // NOTE: This is the contract of `retarget_beams`: a beam only crosses empty cells, and ends
+    // either at the first piece in its way, or at the first wall or the edge of the board.
+    fn assert_beam_targets(board: &Board, name: &str) {
+        for (origin, piece) in board.pieces.iter() {
+            let Some(manipulator) = piece.as_manipulator() else {
+                continue;
+            };
+            for direction in manipulator.emitters.directions() {
+                let target = manipulator.target(direction).unwrap();
+                let context = format!("{}: beam from {} going {:?}", name, origin, direction);
+                let line: Vec<BoardCoords> = std::iter::successors(Some(origin), |&coords| {
+                    board.neighbor(coords, direction)
+                })
+                .collect();
+                let is_wall = |coords: BoardCoords| {
+                    let border_coords = coords.to_border_coords(direction);
+                    let borders = board.borders(direction.orientation().flip());
+                    borders.get(border_coords) == Some(&Border::Wall)
+                };
+
+                let (end, crossed_borders) = match target.kind {
+                    BeamTargetKind::Piece => {
+                        let idx = line.iter().position(|&coords| coords == target.coords);
+                        let idx = idx.unwrap_or_else(|| panic!("{} misses its target", context));
+                        assert!(idx > 0, "{} targets itself", context);
+                        assert!(
+                            board.pieces.get(target.coords).is_some(),
+                            "{} targets the empty cell {}",
+                            context,
+                            target.coords
+                        );
+                        (idx - 1, idx)
+                    }
+                    BeamTargetKind::Border => {
+                        let idx = line.iter().position(|&coords| {
+                            coords.to_border_coords(direction) == target.coords
+                        });
+                        let idx = idx.unwrap_or_else(|| panic!("{} misses its target", context));
+                        assert!(
+                            is_wall(line[idx]) || (idx == line.len() - 1),
+                            "{} stops at {} with neither a wall nor the edge",
+                            context,
+                            target.coords
+                        );
+                        (idx, idx)
+                    }
+                };
+                for &coords in &line[1..=end] {
+                    assert!(
+                        board.pieces.get(coords).is_none(),
+                        "{} passes through the piece at {}",
+                        context,
+                        coords
+                    );
+                }
+                for &coords in &line[..crossed_borders] {
+                    assert!(!is_wall(coords), "{} passes through a wall", context);
+                }
+            }
+        }
+    }
+
+    fn sample_board() -> Board {
+        let mut board = Board::new(3, 4);
+        for coords in board.dims.iter() {
+            board
+                .tiles
+                .set(coords, Tile::new(TileKind::Platform, Tint::White));
+        }
+        add_manipulator(&mut board, (0, 0).into(), Emitters::RightDown);
+        add_manipulator(&mut board, (0, 2).into(), Emitters::LeftDown);
+        board.pieces.set((2, 0).into(), Particle::new(Tint::Green));
+        add_manipulator(&mut board, (1, 3).into(), Emitters::UpDown);
+        add_manipulator(&mut board, (2, 2).into(), Emitters::LeftRight);
+        board.horz_borders.set((3, 2).into(), Border::Wall);
+        board.vert_borders.set((2, 4).into(), Border::Wall);
+        board.vert_borders.set((2, 2).into(), Border::Window);
+        board.retarget_beams();
+        board
+    }
+
+    fn piece_signature(piece: &Piece) -> Option<Emitters> {
+        piece
+            .as_manipulator()
+            .map(|manipulator| manipulator.emitters)
+    }
+
+    fn add_manipulator(board: &mut Board, coords: BoardCoords, emitters: Emitters) {
+        board.pieces.set(coords, Manipulator::new(emitters));
+    }
+}
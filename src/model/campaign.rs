@@ -0,0 +1,548 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::pbc1::Pbc1DecodeError;
+use super::{Board, BoardCoords, LevelMetadata, Tile, TileKind};
+
+pub struct LevelCampaign {
+    pub levels: Vec<CampaignLevel>,
+    pub tiers: Vec<CampaignTier>,
+}
+
+pub struct CampaignLevel {
+    pub name: String,
+    pub briefing: Option<String>,
+    pub goals: Vec<BoardCoords>,
+    pub par: Option<usize>,
+    pub board: Board,
+}
+
+pub struct CampaignTier {
+    pub name: String,
+    pub levels: Vec<usize>,
+}
+
+#[derive(Error, Debug)]
+pub enum CampaignLoadError {
+    #[error("failed to read campaign: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to parse campaign: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("invalid code for level {name:?}: {source}")]
+    InvalidLevel {
+        name: String,
+        source: Pbc1DecodeError,
+    },
+
+    #[error("goal {coords:?} of level {name:?} is not a collector")]
+    InvalidGoal { name: String, coords: BoardCoords },
+
+    #[error("variant {coords:?} of level {name:?} is not on a tile")]
+    InvalidVariant { name: String, coords: BoardCoords },
+
+    #[error("tier {tier:?} refers to level {level_idx}, but there are only {level_count} levels")]
+    LevelOutOfRange {
+        tier: String,
+        level_idx: usize,
+        level_count: usize,
+    },
+
+    #[error("tier {0:?} has no levels")]
+    EmptyTier(String),
+}
+
+#[derive(Deserialize)]
+struct CampaignFile {
+    tiers: Vec<TierEntry>,
+    levels: Vec<LevelEntry>,
+}
+
+#[derive(Deserialize)]
+struct TierEntry {
+    name: String,
+    levels: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+struct LevelEntry {
+    name: String,
+    code: String,
+    briefing: Option<String>,
+    #[serde(default)]
+    goals: Vec<(usize, usize)>,
+    par: Option<usize>,
+    // NOTE: PBC1 has no room for tile variants, so they are listed separately as row, column and
+    // variant
+    #[serde(default)]
+    variants: Vec<(usize, usize, u8)>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CampaignProgress {
+    solved: BTreeSet<usize>,
+    best_moves: BTreeMap<usize, usize>,
+    best_stars: BTreeMap<usize, u8>,
+}
+
+pub type CampaignData<'d> = &'d [(&'d str, &'d [(&'d str, &'d str)])];
+
+pub type BriefingData<'d> = &'d [(&'d str, &'d str)];
+
+impl LevelCampaign {
+    pub fn from_static(tier_data: CampaignData, briefings: BriefingData) -> Self {
+        let mut levels = vec![];
+        let mut tiers = Vec::with_capacity(tier_data.len());
+
+        for (name, level_data) in tier_data {
+            let mut tier_levels = Vec::with_capacity(level_data.len());
+            for (name, pbc) in *level_data {
+                let board = Board::from_pbc1(pbc).unwrap();
+                let briefing = briefings
+                    .iter()
+                    .find(|(level_name, _)| level_name == name)
+                    .map(|(_, briefing)| briefing.to_string());
+                tier_levels.push(levels.len());
+                levels.push(CampaignLevel {
+                    name: name.to_string(),
+                    briefing,
+                    goals: vec![],
+                    par: None,
+                    board,
+                });
+            }
+            tiers.push(CampaignTier {
+                name: name.to_string(),
+                levels: tier_levels,
+            });
+        }
+
+        Self { levels, tiers }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CampaignLoadError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, CampaignLoadError> {
+        let file: CampaignFile = serde_json::from_str(data)?;
+
+        let mut levels = Vec::with_capacity(file.levels.len());
+        for entry in file.levels {
+            let mut board = match Board::from_pbc1(&entry.code) {
+                Ok(board) => board,
+                Err(source) => {
+                    return Err(CampaignLoadError::InvalidLevel {
+                        name: entry.name,
+                        source,
+                    })
+                }
+            };
+            let goals: Vec<BoardCoords> = entry.goals.into_iter().map(Into::into).collect();
+            if let Some(&coords) = goals.iter().find(|&&coords| !is_collector(&board, coords)) {
+                return Err(CampaignLoadError::InvalidGoal {
+                    name: entry.name,
+                    coords,
+                });
+            }
+            for (row, col, variant) in entry.variants {
+                let coords = BoardCoords::new(row, col);
+                let tile = match board.dims.contains(coords) {
+                    false => None,
+                    true => board.tiles.get_mut(coords),
+                };
+                let Some(tile) = tile else {
+                    return Err(CampaignLoadError::InvalidVariant {
+                        name: entry.name,
+                        coords,
+                    });
+                };
+                tile.variant = variant;
+            }
+            levels.push(CampaignLevel {
+                name: entry.name,
+                briefing: entry.briefing,
+                goals,
+                par: entry.par,
+                board,
+            });
+        }
+
+        let mut tiers = Vec::with_capacity(file.tiers.len());
+        for entry in file.tiers {
+            if entry.levels.is_empty() {
+                return Err(CampaignLoadError::EmptyTier(entry.name));
+            }
+            if let Some(&level_idx) = entry.levels.iter().find(|&&idx| idx >= levels.len()) {
+                return Err(CampaignLoadError::LevelOutOfRange {
+                    tier: entry.name,
+                    level_idx,
+                    level_count: levels.len(),
+                });
+            }
+            tiers.push(CampaignTier {
+                name: entry.name,
+                levels: entry.levels,
+            });
+        }
+
+        Ok(Self { levels, tiers })
+    }
+
+    pub fn metadata(&self, level_idx: usize) -> LevelMetadata {
+        let next_idx = level_idx + 1;
+        LevelMetadata {
+            id: Some(level_idx),
+            name: Some(self.levels[level_idx].name.clone()),
+            briefing: self.levels[level_idx].briefing.clone(),
+            next: (next_idx < self.levels.len()).then_some(next_idx),
+            goals: self.levels[level_idx].goals.clone(),
+            par: self.levels[level_idx].par,
+        }
+    }
+}
+
+fn is_collector(board: &Board, coords: BoardCoords) -> bool {
+    board.dims.contains(coords)
+        && matches!(
+            board.tiles.get(coords),
+            Some(Tile {
+                kind: TileKind::Collector,
+                ..
+            })
+        )
+}
+
+impl CampaignProgress {
+    // NOTE: Each line holds a solved level, optionally followed by its best move count and then
+    // its best star rating, so progress saved before either was tracked still loads.
+    pub fn decode(data: &str) -> Self {
+        let mut progress = Self::default();
+        for line in data.lines() {
+            let mut fields = line.split_whitespace();
+            let Some(Ok(level_idx)) = fields.next().map(str::parse) else {
+                continue;
+            };
+            progress.solved.insert(level_idx);
+            let Some(Ok(moves)) = fields.next().map(str::parse) else {
+                continue;
+            };
+            progress.best_moves.insert(level_idx, moves);
+            if let Some(Ok(stars)) = fields.next().map(str::parse) {
+                progress.best_stars.insert(level_idx, stars);
+            }
+        }
+        progress
+    }
+
+    pub fn encode(&self) -> String {
+        self.solved
+            .iter()
+            .map(|level_idx| {
+                let moves = self.best_moves.get(level_idx);
+                match (moves, self.best_stars.get(level_idx)) {
+                    (Some(moves), Some(stars)) => format!("{} {} {}\n", level_idx, moves, stars),
+                    (Some(moves), None) => format!("{} {}\n", level_idx, moves),
+                    (None, _) => format!("{}\n", level_idx),
+                }
+            })
+            .collect()
+    }
+
+    pub fn is_solved(&self, level_idx: usize) -> bool {
+        self.solved.contains(&level_idx)
+    }
+
+    pub fn mark_solved(&mut self, level_idx: usize) -> bool {
+        self.solved.insert(level_idx)
+    }
+
+    pub fn best_moves(&self, level_idx: usize) -> Option<usize> {
+        self.best_moves.get(&level_idx).copied()
+    }
+
+    pub fn record_moves(&mut self, level_idx: usize, moves: usize) -> bool {
+        match self.best_moves(level_idx) {
+            Some(best) if best <= moves => false,
+            _ => {
+                self.best_moves.insert(level_idx, moves);
+                true
+            }
+        }
+    }
+
+    pub fn best_stars(&self, level_idx: usize) -> Option<u8> {
+        self.best_stars.get(&level_idx).copied()
+    }
+
+    pub fn record_stars(&mut self, level_idx: usize, stars: u8) -> bool {
+        match self.best_stars(level_idx) {
+            Some(best) if best >= stars => false,
+            _ => {
+                self.best_stars.insert(level_idx, stars);
+                true
+            }
+        }
+    }
+
+    pub fn next_unsolved(&self, level_idx: usize, level_count: usize) -> Option<usize> {
+        (level_idx + 1..level_count)
+            .chain(0..level_idx)
+            .find(|&idx| !self.is_solved(idx))
+    }
+
+    pub fn solved_count(&self, levels: impl IntoIterator<Item = usize>) -> usize {
+        levels
+            .into_iter()
+            .filter(|&level_idx| self.is_solved(level_idx))
+            .count()
+    }
+}
+
+pub const CLASSIC_CAMPAIGN_DATA: CampaignData = &[
+    ("eASY", &[
+        ("Tutorial", ":PBC1:AapHrUCxAhxBEASxUBAEBQoMEARhjihQoEBQoECBI5BCEARBACAFAEFQokCBhYIgCAoER6AAsVAQBEHRIAiwUBAEABBisUMQFC5QugBBYKEgKBKELAbB/wE="),
+        ("Experiment", ":PBC1:AaocQRMEUaBAgQIpgGFYngmCFACwLIIgBQAsiyBIAQDLIghSAMCyCIZJAQDLIggeoUEGAFgWQZACwINhgyAFoG0es0Hwfw=="),
+        ("Teamwork", ":PBC1:AXpciRIlCIIgDsABSAEAAAyQAgAAwKMUBEEQBAAWCoIgCAIACwVBEAQBgIWCIAiCgQD8Hw=="),
+        ("Roundabout", ":PBC1:AaocUYIgCIIgiBQAAABSGAAAgMFSIAAAQAo4RAAApAAKGAbAowSUAgAgBQAAgBQAoBSGwELBQAAA4P8="),
+        ("Relay", ":PBC1:AZrcYShQoECBAgUKFEgBAAAgBQAAgBQAAACWIhiCIRiCGSDFEAzBEAyBFAAAAFIAAABYKAiCIAiCgfB/"),
+        ("Occlusion", ":PBC1:AVoHrMABKHEAChcoUKDAUggxQNEgCIKlgiAIiwZBMMxSCDFA0SAIggcoGCAcoGgQBMH/AQ=="),
+        ("Transfer", ":PBC1:AZlA4QIFChRgAWCKDhbwgIJszFjChCi+UBEWAVA8WGgoQ4MwUBzTYKGARQAUDRbicwgApmgGKH5QirBgAMWDICjCAh8="),
+    ]),
+    ("MedIUM", &[
+        ("Mmmm, pi!", ":PBC1:AaocQRAEQRAEkQIAAEBqsCAPgjwYDCkgAAIAKRUCIIAGKWAAYAAAKWAQYBAAKSAFUgApAAAApAAAAPB/"),
+        ("Milky Way", ":PBC1:AaqHrEQBgiAIgjgCKSAAAOQpAAEABCkACIAAKSAYZiAEQAoBBhsqAJAKgAAAsBAABACwFwAgAPAAAQAQpIP8Hw=="),
+        ("Maze", ":PBC1:AartChQoUKBAgQIFeixUpEiRIkGRIkWCBYsUPeJBkSJFihRZKAiKBEWKFClSdMGuRYoULVKkSBAsGBQJijQpUiQoulCRIkWKFi8SFQkWLFJkgCA4JEWKxMkiRZgiRZgiRZiFgoGCIAiCIPg/"),
+        ("Checkers", ":PBC1:AXdHjShAFCAOQCpAjsHwCCFAgCCVIkCAhTAIYgSpAAMhwEIIEGCYfw=="),
+        ("Crowded", ":PBC1:AaocQTRo0KAF0eMBpBZLEmRZliUbJQAyAMlGWZhlGYBkowxIgiRJko0yIMmyLMNGGZAAyPApZUCSJFmGjTbJsiwLM+ADSpIkSZJtsk3+Dw=="),
+        ("Juggle", ":PBC1:Aaq3rUCBAgUKFChQoEQqAAAgQCoAACBAKmAYhmGYAKkAgwDAMAM8QkMBGAQIkAoAAAiQChiGYRgmQCoAACDAXkEQBEEQBCv9Hw=="),
+        ("I Kill You", ":PBC1:AaocQRAEQRDH4CikAADAYR1mIRYAAAYLsQAAACkAAACkUKTOASxShAK2KxIMUigIAo5AHKIgKBQMkFMAolVQaIiAAwAEQfTiAAAB"),
+    ]),
+    ("HArd", &[
+        ("Lock", ":PBC1:AXqcBRYQhAUEQRApQAJIAGwFQABAM0wqz3PkOYAUgAAIgFQABAgCIDXkQEMOO9BwwwD/Bw=="),
+        ("Delicate", ":PBC1:AZnFihUoUKBwgQLFFhq0AM/UKTxgsFhQiAWKFiqwEM8MgQGYPkUXZAEAKLpQWwyCIYDiCxUpyALFCwaLDRnUBYoOV2ChQgWKFC9SICj0Pw=="),
+        ("Void", ":PBC1:AaqHjaAJgiAIwoMUwAIAkALAAgCTAgAgYJACAIABUgAOQDkASIEBQQBAigHABgCSAQCwALoEAAAL0f8B"),
+        ("Nautilus", ":PBC1:AapnrQBBEARBEAYsJAAABKMhhbECAIIAKQQCBKMBSAEAAgApAIAgAFJAIEAwDpACRgoACIJUIAAABOOkRgoAAMD/AQ=="),
+        ("Trapped", ":PBC1:AanlCIIoQBBEgYUABAAGepQAQQggWAgUOyxoKlgIFBuApYKFcIDYAAeUChYCxQZgqWAhUGwAlgoeJhAIBcAwCwEIAIT/Aw=="),
+        ("Quadruped", ":PBC1:AaqHjiAIgiAIgkgBAIABkQIQAABSAADQBJEaEgDADoAUgOEQHlAUXQgAAARIASGAAOxSAAAwTPAABQACAPg/"),
+        ("Rails", ":PBC1:AaoccRgIgiAIgkgBAAAgBQAAMEwKAAAAKRxwpg9ThgUeJTBHFAGKsEihOAZBgDZsCswRRYCARwoHHDFCHkiBYRiGwUHB/wE="),
+    ]),
+];
+
+pub const CLASSIC_CAMPAIGN_BRIEFINGS: BriefingData = &[
+    (
+        "Tutorial",
+        "Guide every particle onto a collector. Select a manipulator and move it with the arrows.",
+    ),
+    (
+        "Experiment",
+        "A manipulator drags along every piece its beams are attached to.",
+    ),
+    (
+        "Teamwork",
+        "Pieces that drift off the platforms only stay afloat while a beam holds them.",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_campaign_matches_static() {
+        let data = include_str!("../../assets/campaign.json");
+        let bundled = LevelCampaign::from_json(data).unwrap();
+        let classic = LevelCampaign::from_static(CLASSIC_CAMPAIGN_DATA, CLASSIC_CAMPAIGN_BRIEFINGS);
+
+        assert_eq!(bundled.levels.len(), classic.levels.len());
+        for (bundled, classic) in bundled.levels.iter().zip(classic.levels.iter()) {
+            assert_eq!(bundled.name, classic.name);
+            assert_eq!(bundled.briefing, classic.briefing);
+        }
+        assert_eq!(bundled.tiers.len(), classic.tiers.len());
+        for (bundled, classic) in bundled.tiers.iter().zip(classic.tiers.iter()) {
+            assert_eq!(bundled.name, classic.name);
+            assert_eq!(bundled.levels, classic.levels);
+        }
+
+        let file: CampaignFile = serde_json::from_str(data).unwrap();
+        let codes = CLASSIC_CAMPAIGN_DATA
+            .iter()
+            .flat_map(|(_, levels)| levels.iter().map(|(_, code)| *code));
+        for (entry, code) in file.levels.iter().zip(codes) {
+            assert_eq!(entry.code, code, "{}", entry.name);
+        }
+    }
+
+    #[test]
+    fn campaign_goals() {
+        let code = CLASSIC_CAMPAIGN_DATA[0].1[0].1;
+        let board = Board::from_pbc1(code).unwrap();
+        let goal = board
+            .dims
+            .iter()
+            .find(|&coords| is_collector(&board, coords));
+        let goal = goal.unwrap();
+        let data = format!(
+            r#"{{
+                "tiers": [],
+                "levels": [{{"name": "Goal", "code": "{}", "goals": [[{}, {}]], "par": 5}}]
+            }}"#,
+            code, goal.row, goal.col
+        );
+
+        let campaign = LevelCampaign::from_json(&data).unwrap();
+        assert_eq!(campaign.metadata(0).goals, vec![goal]);
+        assert_eq!(campaign.metadata(0).par, Some(5));
+    }
+
+    #[test]
+    fn tile_variants() {
+        let code = CLASSIC_CAMPAIGN_DATA[0].1[0].1;
+        let board = Board::from_pbc1(code).unwrap();
+        let coords = board
+            .dims
+            .iter()
+            .find(|&coords| board.tiles.get(coords).is_some())
+            .unwrap();
+        let data = format!(
+            r#"{{
+                "tiers": [],
+                "levels": [{{"name": "Variant", "code": "{}", "variants": [[{}, {}, 2]]}}]
+            }}"#,
+            code, coords.row, coords.col
+        );
+
+        let campaign = LevelCampaign::from_json(&data).unwrap();
+        let varied = &campaign.levels[0].board;
+        assert_eq!(varied.tiles.get(coords).unwrap().variant, 2);
+        assert!(varied.layout_eq(&board));
+        assert_eq!(varied.content_hash(), board.content_hash());
+    }
+
+    #[test]
+    fn invalid_campaign() {
+        let out_of_range = r#"{
+            "tiers": [{"name": "Only", "levels": [0, 1]}],
+            "levels": [{"name": "Tutorial", "code": ":PBC1:ERFZAA=="}]
+        }"#;
+        assert!(matches!(
+            LevelCampaign::from_json(out_of_range),
+            Err(CampaignLoadError::LevelOutOfRange { level_idx: 1, .. })
+        ));
+
+        let empty_tier = r#"{
+            "tiers": [{"name": "Empty", "levels": []}],
+            "levels": [{"name": "Tutorial", "code": ":PBC1:ERFZAA=="}]
+        }"#;
+        assert!(matches!(
+            LevelCampaign::from_json(empty_tier),
+            Err(CampaignLoadError::EmptyTier(_))
+        ));
+
+        let bad_code = r#"{"tiers": [], "levels": [{"name": "Broken", "code": "nope"}]}"#;
+        assert!(matches!(
+            LevelCampaign::from_json(bad_code),
+            Err(CampaignLoadError::InvalidLevel { .. })
+        ));
+
+        let bad_goal = r#"{
+            "tiers": [],
+            "levels": [{"name": "Goal", "code": ":PBC1:ERFZAA==", "goals": [[0, 0]]}]
+        }"#;
+        assert!(matches!(
+            LevelCampaign::from_json(bad_goal),
+            Err(CampaignLoadError::InvalidGoal { .. })
+        ));
+
+        let bad_variant = r#"{
+            "tiers": [],
+            "levels": [{"name": "Variant", "code": ":PBC1:ERFZAA==", "variants": [[20, 20, 1]]}]
+        }"#;
+        assert!(matches!(
+            LevelCampaign::from_json(bad_variant),
+            Err(CampaignLoadError::InvalidVariant { .. })
+        ));
+
+        assert!(matches!(
+            LevelCampaign::from_json("{"),
+            Err(CampaignLoadError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn progress_round_trip() {
+        let mut progress = CampaignProgress::default();
+        assert_eq!(progress.solved_count(0..5), 0);
+        assert!(progress.mark_solved(3));
+        assert!(progress.mark_solved(0));
+        assert!(!progress.mark_solved(3));
+
+        let progress = CampaignProgress::decode(&progress.encode());
+        assert!(progress.is_solved(0));
+        assert!(!progress.is_solved(1));
+        assert!(progress.is_solved(3));
+        assert_eq!(progress.solved_count(0..5), 2);
+        assert_eq!(progress.solved_count([1, 2, 3]), 1);
+    }
+
+    #[test]
+    fn next_unsolved() {
+        let mut progress = CampaignProgress::default();
+        progress.mark_solved(1);
+        progress.mark_solved(2);
+        assert_eq!(progress.next_unsolved(1, 5), Some(3));
+
+        progress.mark_solved(3);
+        progress.mark_solved(4);
+        assert_eq!(progress.next_unsolved(2, 5), Some(0));
+
+        progress.mark_solved(0);
+        assert_eq!(progress.next_unsolved(2, 5), None);
+    }
+
+    #[test]
+    fn progress_ignores_garbage() {
+        let progress = CampaignProgress::decode("2\nbogus\n\n 5 \n7 many\n");
+        assert_eq!(progress.solved_count(0..10), 3);
+        assert_eq!(progress.best_moves(7), None);
+    }
+
+    #[test]
+    fn best_moves() {
+        let mut progress = CampaignProgress::decode("1\n2 14\n");
+        assert_eq!(progress.best_moves(1), None);
+        assert_eq!(progress.best_moves(2), Some(14));
+
+        assert!(progress.record_moves(1, 20));
+        assert!(!progress.record_moves(2, 14));
+        assert!(!progress.record_moves(2, 15));
+        assert!(progress.record_moves(2, 9));
+
+        let progress = CampaignProgress::decode(&progress.encode());
+        assert_eq!(progress.best_moves(1), Some(20));
+        assert_eq!(progress.best_moves(2), Some(9));
+        assert_eq!(progress.solved_count(0..5), 2);
+    }
+
+    #[test]
+    fn best_stars() {
+        let mut progress = CampaignProgress::decode("1\n2 14\n3 9 2\n");
+        assert_eq!(progress.best_stars(2), None);
+        assert_eq!(progress.best_stars(3), Some(2));
+
+        assert!(progress.record_stars(2, 1));
+        assert!(!progress.record_stars(3, 1));
+        assert!(progress.record_stars(3, 3));
+
+        let progress = CampaignProgress::decode(&progress.encode());
+        assert_eq!(progress.best_stars(1), None);
+        assert_eq!(progress.best_stars(2), Some(1));
+        assert_eq!(progress.best_stars(3), Some(3));
+        assert_eq!(progress.best_moves(3), Some(9));
+    }
+}
@@ -4,10 +4,12 @@ use strum_macros::{EnumIter, FromRepr};
 
 use super::{BoardCoords, Direction, Tint};
 
+// NOTE: The variant only picks alternate artwork for the tile. It has no effect on gameplay.
 #[derive(Debug, Clone)]
 pub struct Tile {
     pub kind: TileKind,
     pub tint: Tint,
+    pub variant: u8,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Enum, EnumIter, FromRepr)]
@@ -15,6 +17,7 @@ pub struct Tile {
 pub enum TileKind {
     Platform,
     Collector,
+    Recolor,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
@@ -69,7 +72,11 @@ pub enum BeamTargetKind {
 
 impl Tile {
     pub fn new(kind: TileKind, tint: Tint) -> Self {
-        Self { kind, tint }
+        Self {
+            kind,
+            tint,
+            variant: 0,
+        }
     }
 }
 
@@ -89,11 +96,19 @@ impl Piece {
             None
         }
     }
+
+    pub fn as_particle(&self) -> Option<&Particle> {
+        if let Self::Particle(particle) = self {
+            Some(particle)
+        } else {
+            None
+        }
+    }
 }
 
 impl Particle {
     pub fn new(tint: Tint) -> Self {
-        assert!(tint != Tint::White);
+        assert!(tint.is_particle_tint());
         Self { tint }
     }
 }
@@ -137,6 +152,67 @@ impl Emitters {
             Self::UpDown => enum_set!(Direction::Up | Direction::Down),
         }
     }
+
+    #[cfg(test)]
+    pub fn from_directions(directions: EnumSet<Direction>) -> Option<Self> {
+        <Self as strum::IntoEnumIterator>::iter()
+            .find(|emitters| emitters.directions() == directions)
+    }
+
+    pub fn rotated_cw(self) -> Self {
+        match self {
+            Self::Left => Self::Up,
+            Self::Up => Self::Right,
+            Self::Right => Self::Down,
+            Self::Down => Self::Left,
+            Self::LeftUp => Self::RightUp,
+            Self::LeftDown => Self::LeftUp,
+            Self::RightUp => Self::RightDown,
+            Self::RightDown => Self::LeftDown,
+            Self::LeftRight => Self::UpDown,
+            Self::UpDown => Self::LeftRight,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn rotated_ccw(self) -> Self {
+        match self {
+            Self::Left => Self::Down,
+            Self::Up => Self::Left,
+            Self::Right => Self::Up,
+            Self::Down => Self::Right,
+            Self::LeftUp => Self::LeftDown,
+            Self::LeftDown => Self::RightDown,
+            Self::RightUp => Self::LeftUp,
+            Self::RightDown => Self::RightUp,
+            Self::LeftRight => Self::UpDown,
+            Self::UpDown => Self::LeftRight,
+        }
+    }
+
+    pub fn flipped_horizontal(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::LeftUp => Self::RightUp,
+            Self::LeftDown => Self::RightDown,
+            Self::RightUp => Self::LeftUp,
+            Self::RightDown => Self::LeftDown,
+            Self::Up | Self::Down | Self::LeftRight | Self::UpDown => self,
+        }
+    }
+
+    pub fn flipped_vertical(self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::LeftUp => Self::LeftDown,
+            Self::LeftDown => Self::LeftUp,
+            Self::RightUp => Self::RightDown,
+            Self::RightDown => Self::RightUp,
+            Self::Left | Self::Right | Self::LeftRight | Self::UpDown => self,
+        }
+    }
 }
 
 impl BeamTarget {
@@ -166,3 +242,61 @@ impl Into<Option<Piece>> for Manipulator {
         Some(Piece::Manipulator(self))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    #[test]
+    fn emitter_transforms() {
+        for emitters in Emitters::iter() {
+            assert_eq!(emitters.rotated_cw().rotated_ccw(), emitters);
+            assert_eq!(emitters.rotated_ccw().rotated_cw(), emitters);
+            assert_eq!(emitters.flipped_horizontal().flipped_horizontal(), emitters);
+            assert_eq!(emitters.flipped_vertical().flipped_vertical(), emitters);
+            assert_eq!(
+                emitters.rotated_cw().rotated_cw().rotated_cw().rotated_cw(),
+                emitters
+            );
+
+            assert_directions(emitters, emitters.rotated_cw(), Direction::rotated_cw);
+            assert_directions(
+                emitters,
+                emitters.flipped_horizontal(),
+                Direction::flipped_horizontal,
+            );
+            assert_directions(
+                emitters,
+                emitters.flipped_vertical(),
+                Direction::flipped_vertical,
+            );
+        }
+    }
+
+    #[test]
+    fn emitters_from_directions() {
+        for emitters in Emitters::iter() {
+            assert_eq!(
+                Emitters::from_directions(emitters.directions()),
+                Some(emitters)
+            );
+        }
+        assert_eq!(Emitters::from_directions(EnumSet::empty()), None);
+        assert_eq!(
+            Emitters::from_directions(EnumSet::all() - Direction::Down),
+            None
+        );
+        assert_eq!(Emitters::from_directions(EnumSet::all()), None);
+    }
+
+    fn assert_directions(
+        original: Emitters,
+        transformed: Emitters,
+        func: impl Fn(Direction) -> Direction,
+    ) {
+        let expected: EnumSet<Direction> = original.directions().iter().map(func).collect();
+        assert_eq!(transformed.directions(), expected);
+    }
+}
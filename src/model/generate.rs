@@ -0,0 +1,153 @@
+use strum::IntoEnumIterator;
+
+use super::random::{mix_seed, next_index};
+use super::solver::solve_within;
+use super::{
+    Board, BoardBuilder, BoardCoords, Border, Emitters, Manipulator, Particle, Tile, TileKind, Tint,
+};
+
+// NOTE: Boards are kept small, and the solver gets a much tighter budget than usual, so each
+// candidate is settled quickly. Candidates it gives up on are discarded like unsolvable ones.
+const ROWS: usize = 5;
+const COLS: usize = 6;
+const HOLE_CHANCE: usize = 6;
+const MIN_MANIPULATORS: usize = 2;
+const MAX_MANIPULATORS: usize = 3;
+const MIN_PARTICLES: usize = 1;
+const MAX_PARTICLES: usize = 2;
+const MAX_WALLS: usize = 3;
+const MIN_MOVES: usize = 5;
+const MAX_STATES: usize = 20_000;
+
+// NOTE: The same seed always produces the same board. Candidates are drawn from a single random
+// stream until one is solvable and takes enough moves to be worth playing.
+pub fn generate(seed: u64) -> Board {
+    let mut state = mix_seed(seed);
+    loop {
+        if let Some(mut board) = candidate(&mut state).filter(is_worth_playing) {
+            reorient(&mut state, &mut board);
+            return board;
+        }
+    }
+}
+
+// NOTE: Candidates are always built wider than they are tall, so the accepted board is turned
+// and mirrored at random to vary the shape of the puzzle.
+fn reorient(state: &mut u64, board: &mut Board) {
+    if next_index(state, 2) == 0 {
+        board.rotate_90();
+    }
+    if next_index(state, 2) == 0 {
+        board.flip_horizontal();
+    }
+    if next_index(state, 2) == 0 {
+        board.flip_vertical();
+    }
+}
+
+// NOTE: Holes can leave too few free cells for all the pieces, in which case the candidate is
+// rejected outright.
+fn candidate(state: &mut u64) -> Option<Board> {
+    let mut builder = BoardBuilder::new(ROWS, COLS);
+    let mut free = Vec::new();
+    for row in 0..ROWS {
+        for col in 0..COLS {
+            if next_index(state, HOLE_CHANCE) == 0 {
+                continue;
+            }
+            let coords = BoardCoords::new(row, col);
+            builder = builder.with_tile(coords, Tile::new(TileKind::Platform, Tint::White));
+            free.push(coords);
+        }
+    }
+
+    let particle_count = next_range(state, MIN_PARTICLES, MAX_PARTICLES);
+    let manipulator_count = next_range(state, MIN_MANIPULATORS, MAX_MANIPULATORS);
+    for _ in 0..particle_count {
+        let tint = pick(state, Tint::particle_tints());
+        let collector_tint = match next_index(state, 4) {
+            0 => Tint::White,
+            _ => tint,
+        };
+        let collector = take_free(state, &mut free)?;
+        builder = builder.with_tile(collector, Tile::new(TileKind::Collector, collector_tint));
+        let coords = take_free(state, &mut free)?;
+        builder = builder.with_piece(coords, Particle::new(tint));
+    }
+    for _ in 0..manipulator_count {
+        let emitters = pick(state, Emitters::iter());
+        let coords = take_free(state, &mut free)?;
+        builder = builder.with_piece(coords, Manipulator::new(emitters));
+    }
+
+    for _ in 0..next_index(state, MAX_WALLS + 1) {
+        builder = match next_index(state, 2) {
+            0 => {
+                let coords =
+                    BoardCoords::new(next_range(state, 1, ROWS - 1), next_index(state, COLS));
+                builder.with_horz_border(coords, Border::Wall)
+            }
+            _ => {
+                let coords =
+                    BoardCoords::new(next_index(state, ROWS), next_range(state, 1, COLS - 1));
+                builder.with_vert_border(coords, Border::Wall)
+            }
+        };
+    }
+
+    Some(builder.build())
+}
+
+fn is_worth_playing(board: &Board) -> bool {
+    if board.is_solved() || board.has_lost() || !board.any_move_available() {
+        return false;
+    }
+    solve_within(board, MAX_STATES).is_some_and(|solution| solution.len() >= MIN_MOVES)
+}
+
+fn take_free(state: &mut u64, free: &mut Vec<BoardCoords>) -> Option<BoardCoords> {
+    if free.is_empty() {
+        return None;
+    }
+    let idx = next_index(state, free.len());
+    Some(free.swap_remove(idx))
+}
+
+fn pick<T>(state: &mut u64, items: impl Iterator<Item = T>) -> T {
+    let mut items: Vec<T> = items.collect();
+    let idx = next_index(state, items.len());
+    items.swap_remove(idx)
+}
+
+fn next_range(state: &mut u64, min: usize, max: usize) -> usize {
+    min + next_index(state, max - min + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_board() {
+        let board = generate(1);
+        assert_eq!(board.content_hash(), generate(1).content_hash());
+        assert_ne!(board.content_hash(), generate(2).content_hash());
+    }
+
+    #[test]
+    fn generated_boards_are_solvable() {
+        for seed in 0..3 {
+            let board = generate(seed);
+            assert!(!board.is_solved());
+            assert!(board.unsupported_pieces().is_empty());
+            let solution = board.solve().unwrap();
+            assert!(solution.len() >= MIN_MOVES);
+        }
+    }
+
+    #[test]
+    fn take_free_from_empty() {
+        let mut state = mix_seed(0);
+        assert_eq!(take_free(&mut state, &mut vec![]), None);
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt::Debug;
-use std::ops::{Deref, DerefMut};
 
 use smallvec::{smallvec, SmallVec};
+use thiserror::Error;
 
 use super::{BoardCoords, Dimensions, Direction, MAX_BOARD_COLS, MAX_BOARD_ROWS};
 
@@ -23,11 +23,19 @@ pub struct GridSet {
     masks: SmallVec<[u8; MAX_CAPACITY / 8]>,
 }
 
-pub struct ScopedInsert<'s> {
-    set: &'s mut GridSet,
-    coords: BoardCoords,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("{coords} is outside the {dims} grid")]
+pub struct OutOfBounds {
+    pub coords: BoardCoords,
+    pub dims: Dimensions,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("the queue is full")]
+pub struct QueueFull;
+
+struct SetBits(u8);
+
 pub struct GridQueue {
     buffer: SmallVec<[BoardCoords; MAX_CAPACITY]>,
     push_idx: usize,
@@ -46,31 +54,93 @@ impl<T: Clone> GridMap<T> {
     }
 
     pub fn get(&self, coords: BoardCoords) -> Option<&T> {
-        self.cells[self.dims.index(coords)].as_ref()
+        self.dims
+            .index(coords)
+            .and_then(|idx| self.cells[idx].as_ref())
+    }
+
+    pub fn get_checked(&self, coords: BoardCoords) -> Result<Option<&T>, OutOfBounds> {
+        let idx = self.checked_index(coords)?;
+        Ok(self.cells[idx].as_ref())
     }
 
     pub fn get_mut(&mut self, coords: BoardCoords) -> Option<&mut T> {
-        self.cells[self.dims.index(coords)].as_mut()
+        self.dims
+            .index(coords)
+            .and_then(|idx| self.cells[idx].as_mut())
     }
 
     pub fn set<V: Into<Option<T>>>(&mut self, coords: BoardCoords, value: V) {
-        self.cells[self.dims.index(coords)] = value.into();
+        if let Some(idx) = self.dims.index(coords) {
+            self.cells[idx] = value.into();
+        }
+    }
+
+    pub fn set_checked<V: Into<Option<T>>>(
+        &mut self,
+        coords: BoardCoords,
+        value: V,
+    ) -> Result<(), OutOfBounds> {
+        let idx = self.checked_index(coords)?;
+        self.cells[idx] = value.into();
+        Ok(())
+    }
+
+    pub fn get_or_insert_with(&mut self, coords: BoardCoords, f: impl FnOnce() -> T) -> &mut T {
+        let idx = self
+            .dims
+            .index(coords)
+            .unwrap_or_else(|| panic!("{} is outside the {} grid", coords, self.dims));
+        self.cells[idx].get_or_insert_with(f)
     }
 
     pub fn take(&mut self, coords: BoardCoords) -> Option<T> {
-        self.cells[self.dims.index(coords)].take()
+        self.dims
+            .index(coords)
+            .and_then(|idx| self.cells[idx].take())
+    }
+
+    #[cfg(test)]
+    pub fn swap(&mut self, a: BoardCoords, b: BoardCoords) {
+        let a = self.dims.index(a).unwrap();
+        let b = self.dims.index(b).unwrap();
+        self.cells.swap(a, b);
     }
 
     pub fn clear(&mut self) {
         self.cells.iter_mut().for_each(|cell| *cell = None);
     }
 
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.cells.iter().filter(|cell| cell.is_some()).count()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(|cell| cell.is_none())
+    }
+
     pub fn mirror(&mut self, other: &Self) {
         assert_eq!(self.dims, other.dims);
         self.cells.clear();
         self.cells.extend(other.cells.iter().cloned());
     }
 
+    #[cfg(test)]
+    pub fn map<U: Clone>(&self, f: impl Fn(BoardCoords, &T) -> U) -> GridMap<U> {
+        let cells = self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(idx, opt)| opt.as_ref().map(|value| f(self.dims.coords(idx), value)))
+            .collect();
+        GridMap {
+            dims: self.dims,
+            cells,
+        }
+    }
+
     pub fn iter(&self) -> impl DoubleEndedIterator<Item = (BoardCoords, &T)> {
         self.cells
             .iter()
@@ -78,6 +148,13 @@ impl<T: Clone> GridMap<T> {
             .filter_map(|(idx, opt)| Some((idx, opt.as_ref()?)))
             .map(|(idx, value)| (self.dims.coords(idx), value))
     }
+
+    fn checked_index(&self, coords: BoardCoords) -> Result<usize, OutOfBounds> {
+        self.dims.index_checked(coords).ok_or(OutOfBounds {
+            coords,
+            dims: self.dims,
+        })
+    }
 }
 
 impl<T: Clone> Grid for GridMap<T> {
@@ -98,37 +175,65 @@ impl GridSet {
     }
 
     pub fn contains(&self, coords: BoardCoords) -> bool {
-        let idx = self.dims.index(coords);
-        self.masks[idx / 8] & (1 << (idx % 8)) != 0
+        self.dims
+            .index(coords)
+            .is_some_and(|idx| self.masks[idx / 8] & (1 << (idx % 8)) != 0)
     }
 
     pub fn is_empty(&self) -> bool {
         self.masks.iter().all(|mask| *mask == 0)
     }
 
+    pub fn len(&self) -> usize {
+        self.masks
+            .iter()
+            .map(|mask| mask.count_ones() as usize)
+            .sum()
+    }
+
     pub fn insert(&mut self, coords: BoardCoords) {
-        let idx = self.dims.index(coords);
-        self.masks[idx / 8] |= 1 << (idx % 8);
+        if let Some(idx) = self.dims.index(coords) {
+            self.masks[idx / 8] |= 1 << (idx % 8);
+        }
     }
 
     pub fn remove(&mut self, coords: BoardCoords) {
-        let idx = self.dims.index(coords);
-        self.masks[idx / 8] &= !(1 << (idx % 8));
+        if let Some(idx) = self.dims.index(coords) {
+            self.masks[idx / 8] &= !(1 << (idx % 8));
+        }
     }
 
-    pub fn scoped_insert(&mut self, coords: BoardCoords) -> ScopedInsert {
-        ScopedInsert::new(self, coords)
+    // NOTE: Move sets are usually sparse, so whole mask bytes are skipped at a time instead of
+    // testing every cell. Bits are stored in row-major order, which the iteration preserves.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = BoardCoords> + '_ {
+        self.masks
+            .iter()
+            .enumerate()
+            .flat_map(|(byte_idx, &mask)| SetBits(mask).map(move |bit| byte_idx * 8 + bit))
+            .map(|idx| self.dims.coords(idx))
     }
 
-    pub fn iter(&self) -> impl DoubleEndedIterator<Item = BoardCoords> + '_ {
-        self.dims.iter().filter(|&coords| self.contains(coords))
+    // NOTE: Coordinates closest to the edge of the board in the given direction come first, so
+    // moving the pieces in this order never overwrites a piece that hasn't been moved yet.
+    pub fn move_order(&self, direction: Direction) -> impl Iterator<Item = BoardCoords> + '_ {
+        let count = self.dims.rows * self.dims.cols;
+        let reverse = match direction {
+            Direction::Up | Direction::Left => false,
+            Direction::Down | Direction::Right => true,
+        };
+        (0..count)
+            .map(move |idx| if reverse { count - 1 - idx } else { idx })
+            .map(|idx| self.dims.coords(idx))
+            .filter(|&coords| self.contains(coords))
     }
 
     pub fn for_each(&self, direction: Direction, func: impl FnMut(BoardCoords)) {
-        match direction {
-            Direction::Up | Direction::Left => self.iter().for_each(func),
-            Direction::Down | Direction::Right => self.iter().rev().for_each(func),
-        }
+        self.move_order(direction).for_each(func)
+    }
+
+    #[cfg(test)]
+    pub fn to_vec(&self, direction: Direction) -> Vec<BoardCoords> {
+        self.move_order(direction).collect()
     }
 }
 
@@ -138,6 +243,12 @@ impl Grid for GridSet {
     }
 }
 
+impl<T: Clone + Debug> Debug for GridMap<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
 impl Debug for GridSet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{{")?;
@@ -152,29 +263,27 @@ impl Debug for GridSet {
     }
 }
 
-impl<'s> ScopedInsert<'s> {
-    fn new(set: &'s mut GridSet, coords: BoardCoords) -> Self {
-        set.insert(coords);
-        Self { set, coords }
-    }
-}
-
-impl<'s> Drop for ScopedInsert<'s> {
-    fn drop(&mut self) {
-        self.set.remove(self.coords);
-    }
-}
+impl Iterator for SetBits {
+    type Item = usize;
 
-impl<'s> Deref for ScopedInsert<'s> {
-    type Target = GridSet;
-    fn deref(&self) -> &Self::Target {
-        self.set
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let bit = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(bit)
     }
 }
 
-impl<'s> DerefMut for ScopedInsert<'s> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.set
+impl DoubleEndedIterator for SetBits {
+    fn next_back(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let bit = 7 - self.0.leading_zeros() as usize;
+        self.0 &= !(1 << bit);
+        Some(bit)
     }
 }
 
@@ -188,13 +297,25 @@ impl GridQueue {
         }
     }
 
+    // NOTE: The queue starts out with room for every cell of the grid, which is enough as long as
+    // each cell is queued at most once. Pushing beyond that grows the buffer.
     pub fn push(&mut self, coords: BoardCoords) {
-        assert!(self.pop_idx != Some(self.push_idx));
+        if self.try_push(coords).is_err() {
+            self.grow();
+            self.try_push(coords).unwrap();
+        }
+    }
+
+    pub fn try_push(&mut self, coords: BoardCoords) -> Result<(), QueueFull> {
+        if self.is_full() {
+            return Err(QueueFull);
+        }
         self.buffer[self.push_idx] = coords;
         if self.pop_idx.is_none() {
             self.pop_idx = Some(self.push_idx);
         }
         self.push_idx = self.wrap_inc(self.push_idx);
+        Ok(())
     }
 
     pub fn pop(&mut self) -> Option<BoardCoords> {
@@ -203,11 +324,42 @@ impl GridQueue {
         };
         let result = self.buffer[pop_idx];
         let pop_idx = self.wrap_inc(pop_idx);
-        self.pop_idx = (pop_idx != self.push_idx).then(|| pop_idx);
+        self.pop_idx = (pop_idx != self.push_idx).then_some(pop_idx);
 
         Some(result)
     }
 
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        match self.pop_idx {
+            None => 0,
+            Some(pop_idx) if pop_idx < self.push_idx => self.push_idx - pop_idx,
+            Some(pop_idx) => self.buffer.len() - pop_idx + self.push_idx,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.pop_idx.is_none()
+    }
+
+    fn is_full(&self) -> bool {
+        self.buffer.is_empty() || (self.pop_idx == Some(self.push_idx))
+    }
+
+    fn grow(&mut self) {
+        // NOTE: Only called when full, so the queued coordinates take up the whole buffer, starting
+        // at the pop index
+        let len = self.buffer.len();
+        if let Some(pop_idx) = self.pop_idx {
+            self.buffer.rotate_left(pop_idx);
+            self.pop_idx = Some(0);
+        }
+        self.push_idx = len;
+        self.buffer
+            .resize(std::cmp::max(len * 2, 1), BoardCoords::default());
+    }
+
     fn wrap_inc(&self, mut idx: usize) -> usize {
         idx += 1;
         if idx == self.buffer.len() {
@@ -216,3 +368,273 @@ impl GridQueue {
         idx
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    #[test]
+    fn move_order() {
+        for direction in Direction::iter() {
+            let mut grid = GridMap::new(7, 7);
+            let mut set = GridSet::new(7, 7);
+            let mut coords = BoardCoords::new(3, 3);
+            let mut chain = Vec::new();
+            for value in 0..3 {
+                grid.set(coords, value);
+                set.insert(coords);
+                chain.push(coords);
+                coords = step(coords, direction.rotated_cw().rotated_cw());
+            }
+
+            for from_coords in set.move_order(direction) {
+                let to_coords = step(from_coords, direction);
+                assert!(
+                    grid.get(to_coords).is_none(),
+                    "{:?}: {:?}",
+                    direction,
+                    to_coords
+                );
+                let value = grid.take(from_coords);
+                grid.set(to_coords, value);
+            }
+
+            for (value, coords) in chain.into_iter().enumerate() {
+                assert_eq!(grid.get(step(coords, direction)), Some(&value));
+            }
+        }
+    }
+
+    #[test]
+    fn to_vec() {
+        let mut set = GridSet::new(3, 3);
+        set.insert((0, 1).into());
+        set.insert((2, 1).into());
+        set.insert((1, 2).into());
+        assert_eq!(set.len(), 3);
+
+        let coords = |list: &[(usize, usize)]| -> Vec<BoardCoords> {
+            list.iter().map(|&coords| coords.into()).collect()
+        };
+        assert_eq!(set.to_vec(Direction::Up), coords(&[(0, 1), (1, 2), (2, 1)]));
+        assert_eq!(
+            set.to_vec(Direction::Down),
+            coords(&[(2, 1), (1, 2), (0, 1)])
+        );
+    }
+
+    fn step(coords: BoardCoords, direction: Direction) -> BoardCoords {
+        match direction {
+            Direction::Up => (coords.row - 1, coords.col).into(),
+            Direction::Left => (coords.row, coords.col - 1).into(),
+            Direction::Down => (coords.row + 1, coords.col).into(),
+            Direction::Right => (coords.row, coords.col + 1).into(),
+        }
+    }
+
+    #[test]
+    fn len() {
+        let mut grid = GridMap::new(2, 3);
+        assert_eq!(grid.len(), 0);
+        assert!(grid.is_empty());
+
+        grid.set((0, 1).into(), 1);
+        grid.set((1, 2).into(), 2);
+        grid.set((1, 2).into(), 3);
+        assert_eq!(grid.len(), 2);
+        assert!(!grid.is_empty());
+
+        grid.take((0, 1).into());
+        grid.take((0, 2).into());
+        assert_eq!(grid.len(), 1);
+
+        grid.set((1, 2).into(), None);
+        assert_eq!(grid.len(), 0);
+        assert!(grid.is_empty());
+    }
+
+    #[test]
+    fn checked_access() {
+        let mut grid = GridMap::new(2, 3);
+        assert_eq!(grid.set_checked((1, 2).into(), 4), Ok(()));
+        assert_eq!(grid.get_checked((1, 2).into()), Ok(Some(&4)));
+        assert_eq!(grid.get_checked((0, 0).into()), Ok(None));
+
+        let err = grid.set_checked((0, 3).into(), 5).unwrap_err();
+        assert_eq!(err.coords, (0, 3).into());
+        assert_eq!(err.to_string(), "(0, 3) is outside the 2x3 grid");
+        assert!(grid.get_checked((2, 0).into()).is_err());
+        assert!(grid.get_checked((2, 3).into()).is_err());
+        assert_eq!(grid.get((1, 0).into()), None);
+    }
+
+    #[test]
+    #[cfg(feature = "checked")]
+    fn out_of_bounds_access() {
+        let mut grid = GridMap::new(2, 3);
+        grid.set((0, 3).into(), 5);
+        assert_eq!(grid.get((0, 3).into()), None);
+        assert_eq!(grid.get((1, 0).into()), None);
+        assert_eq!(grid.take((2, 0).into()), None);
+
+        let mut set = GridSet::new(2, 3);
+        set.insert((0, 3).into());
+        assert!(!set.contains((0, 3).into()));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn get_or_insert_with() {
+        let mut grid = GridMap::new(2, 2);
+        *grid.get_or_insert_with((1, 0).into(), || 1) += 1;
+        assert_eq!(grid.get((1, 0).into()), Some(&2));
+
+        *grid.get_or_insert_with((1, 0).into(), || unreachable!()) += 1;
+        assert_eq!(grid.get((1, 0).into()), Some(&3));
+        assert_eq!(grid.iter().count(), 1);
+    }
+
+    #[test]
+    fn map_debug() {
+        let mut grid = GridMap::new(2, 3);
+        assert_eq!(format!("{:?}", grid), "{}");
+        grid.set((1, 2).into(), 'b');
+        grid.set((0, 1).into(), 'a');
+        assert_eq!(format!("{:?}", grid), "{(0, 1): 'a', (1, 2): 'b'}");
+    }
+
+    #[test]
+    fn swap() {
+        let mut grid = GridMap::new(2, 2);
+        grid.swap((0, 0).into(), (1, 1).into());
+        assert_eq!(grid.iter().count(), 0);
+
+        grid.set((0, 0).into(), 1);
+        grid.swap((0, 0).into(), (1, 1).into());
+        assert_eq!(grid.get((0, 0).into()), None);
+        assert_eq!(grid.get((1, 1).into()), Some(&1));
+        grid.swap((0, 0).into(), (1, 1).into());
+        assert_eq!(grid.get((0, 0).into()), Some(&1));
+        assert_eq!(grid.get((1, 1).into()), None);
+
+        grid.set((0, 1).into(), 2);
+        grid.swap((0, 0).into(), (0, 1).into());
+        assert_eq!(grid.get((0, 0).into()), Some(&2));
+        assert_eq!(grid.get((0, 1).into()), Some(&1));
+
+        grid.swap((0, 1).into(), (0, 1).into());
+        assert_eq!(grid.get((0, 1).into()), Some(&1));
+        assert_eq!(grid.iter().count(), 2);
+    }
+
+    #[test]
+    fn map() {
+        let mut grid = GridMap::new(2, 3);
+        grid.set((0, 1).into(), 5);
+        grid.set((1, 2).into(), 7);
+
+        let mapped = grid.map(|coords, value| format!("{:?}={}", coords, value));
+        assert_eq!(mapped.dims(), grid.dims());
+        assert_eq!(mapped.get((0, 1).into()).unwrap(), "(0, 1)=5");
+        assert_eq!(mapped.get((1, 2).into()).unwrap(), "(1, 2)=7");
+        assert_eq!(mapped.iter().count(), 2);
+        for coords in grid.dims().iter() {
+            assert_eq!(mapped.get(coords).is_some(), grid.get(coords).is_some());
+        }
+    }
+
+    #[test]
+    fn set_iter() {
+        for (rows, cols) in [(1, 1), (3, 5), (7, 7), (MAX_BOARD_ROWS, MAX_BOARD_COLS)] {
+            let mut set = GridSet::new(rows, cols);
+            assert_eq!(set.iter().count(), 0);
+            for (idx, coords) in set.dims.iter().enumerate() {
+                if (idx % 3 == 0) || (idx % 7 == 5) {
+                    set.insert(coords);
+                }
+            }
+            let expected: Vec<_> = set
+                .dims
+                .iter()
+                .filter(|&coords| set.contains(coords))
+                .collect();
+            assert_eq!(set.iter().collect::<Vec<_>>(), expected);
+
+            let mut reversed = expected.clone();
+            reversed.reverse();
+            assert_eq!(set.iter().rev().collect::<Vec<_>>(), reversed);
+        }
+    }
+
+    #[test]
+    fn queue_wrap_and_grow() {
+        let grid = GridSet::new(1, 3);
+        let mut queue = GridQueue::for_grid(&grid);
+        let coords: Vec<BoardCoords> = (0..7).map(|col| BoardCoords::new(0, col)).collect();
+
+        for &coords in &coords[..3] {
+            assert_eq!(queue.try_push(coords), Ok(()));
+        }
+        assert_eq!(queue.try_push(coords[3]), Err(QueueFull));
+        assert_eq!(queue.pop(), Some(coords[0]));
+        assert_eq!(queue.pop(), Some(coords[1]));
+        assert_eq!(queue.try_push(coords[3]), Ok(()));
+        assert_eq!(queue.try_push(coords[4]), Ok(()));
+        assert_eq!(queue.len(), 3);
+
+        queue.push(coords[5]);
+        queue.push(coords[6]);
+        assert_eq!(queue.len(), 5);
+        for &coords in &coords[2..] {
+            assert_eq!(queue.pop(), Some(coords));
+        }
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+
+        let mut queue = GridQueue::for_grid(&GridSet::new(0, 0));
+        queue.push(coords[0]);
+        assert_eq!(queue.pop(), Some(coords[0]));
+    }
+}
+
+// NOTE: Run with `cargo test --release -- --ignored --nocapture` to compare the timings
+#[cfg(test)]
+mod bench {
+    use std::hint::black_box;
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    #[ignore]
+    fn set_iter() {
+        let mut set = GridSet::new(MAX_BOARD_ROWS, MAX_BOARD_COLS);
+        for coords in [(0, 3), (4, 4), (4, 5), (9, 12), (14, 14)] {
+            set.insert(coords.into());
+        }
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            black_box(black_box(&set).iter().count());
+        }
+        let by_mask = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            let set = black_box(&set);
+            black_box(
+                set.dims
+                    .iter()
+                    .filter(|&coords| set.contains(coords))
+                    .count(),
+            );
+        }
+        let by_cell = start.elapsed();
+
+        println!("by mask: {:?}, by cell: {:?}", by_mask, by_cell);
+    }
+
+    const ITERATIONS: usize = 100_000;
+}
@@ -1,9 +1,11 @@
-use super::{Board, Piece, Tile, TileKind};
+use super::{Board, BoardCoords, Piece};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LevelProgress {
     manipulators_left: usize,
     uncollected_particles: usize,
+    collected: Vec<BoardCoords>,
+    goals_left: Option<Vec<BoardCoords>>,
     pub outcome: Option<LevelOutcome>,
 }
 
@@ -18,54 +20,91 @@ pub enum LevelOutcome {
 pub struct LevelMetadata {
     pub id: Option<usize>,
     pub name: Option<String>,
+    pub briefing: Option<String>,
     pub next: Option<usize>,
+    pub goals: Vec<BoardCoords>,
+    pub par: Option<usize>,
 }
 
-pub struct LevelCampaign {
-    pub levels: Vec<CampaignLevel>,
-    pub tiers: Vec<CampaignTier>,
-}
-
-pub struct CampaignLevel {
-    pub name: String,
-    pub board: Board,
-}
+pub const MAX_STARS: u8 = 3;
 
-pub struct CampaignTier {
-    pub name: String,
-    pub levels: Vec<usize>,
+// NOTE: Finishing within a quarter of par over it, rounded up, still earns two stars
+pub fn star_rating(moves: usize, par: usize) -> u8 {
+    if moves <= par {
+        MAX_STARS
+    } else if moves <= par + par.div_ceil(4) {
+        2
+    } else {
+        1
+    }
 }
 
-pub type CampaignData<'d> = &'d [(&'d str, &'d [(&'d str, &'d str)])];
-
 impl LevelProgress {
     pub fn new(board: &Board) -> Self {
-        let mut manipulators_left = 0;
+        Self::with_goals(board, &[])
+    }
+
+    // NOTE: Without goals, the level is won once every particle is collected. With goals, only
+    // the collectors they name need to hold a particle, and any other particle may stay put.
+    pub fn with_goals(board: &Board, goals: &[BoardCoords]) -> Self {
+        let manipulators_left = board.manipulator_count();
         let mut uncollected_particles = 0;
-        for (coords, piece) in board.pieces.iter() {
-            match piece {
-                Piece::Particle(_) => {
-                    match board.tiles.get(coords) {
-                        Some(Tile {
-                            kind: TileKind::Collector,
-                            ..
-                        }) => (),
-                        _ => uncollected_particles += 1,
-                    };
-                }
-                Piece::Manipulator(_) => manipulators_left += 1,
+        let mut collected = Vec::new();
+        for (coords, _) in board.particles() {
+            if board.is_collected(coords) {
+                collected.push(coords);
+            } else {
+                uncollected_particles += 1;
             }
         }
+        let goals_left = (!goals.is_empty()).then(|| {
+            goals
+                .iter()
+                .copied()
+                .filter(|&coords| !board.is_collected(coords))
+                .collect()
+        });
         Self {
             manipulators_left,
             uncollected_particles,
+            collected,
+            goals_left,
             outcome: None,
         }
     }
 
-    pub fn particle_collected(&mut self) {
-        self.uncollected_particles -= 1;
-        if self.uncollected_particles == 0 {
+    pub fn manipulators_left(&self) -> usize {
+        self.manipulators_left
+    }
+
+    pub fn uncollected_particles(&self) -> usize {
+        self.uncollected_particles
+    }
+
+    pub fn goals_left(&self) -> &[BoardCoords] {
+        self.goals_left.as_deref().unwrap_or_default()
+    }
+
+    // NOTE: A collected particle never leaves its collector, so its coordinates identify it and
+    // collecting it again is ignored.
+    pub fn particle_collected(&mut self, coords: BoardCoords) {
+        if self.collected.contains(&coords) {
+            return;
+        }
+        self.collected.push(coords);
+        debug_assert!(
+            self.uncollected_particles > 0,
+            "no particles left to collect"
+        );
+        self.uncollected_particles = self.uncollected_particles.saturating_sub(1);
+        let won = match self.goals_left.as_mut() {
+            Some(goals_left) => {
+                goals_left.retain(|&goal| goal != coords);
+                goals_left.is_empty()
+            }
+            None => self.uncollected_particles == 0,
+        };
+        if won {
             self.update_outcome(LevelOutcome::Victory);
         }
     }
@@ -73,7 +112,10 @@ impl LevelProgress {
     pub fn piece_lost(&mut self, piece: &Piece) {
         match piece {
             Piece::Particle(_) => self.update_outcome(LevelOutcome::ParticleLost),
-            Piece::Manipulator(_) => self.manipulators_left -= 1,
+            Piece::Manipulator(_) => {
+                debug_assert!(self.manipulators_left > 0, "no manipulators left to lose");
+                self.manipulators_left = self.manipulators_left.saturating_sub(1);
+            }
         }
         if self.manipulators_left == 0 {
             self.update_outcome(LevelOutcome::NoManipulatorsLeft);
@@ -85,36 +127,124 @@ impl LevelProgress {
     }
 }
 
-impl LevelCampaign {
-    pub fn from_static(tier_data: CampaignData) -> Self {
-        let mut levels = vec![];
-        let mut tiers = Vec::with_capacity(tier_data.len());
+#[cfg(test)]
+mod tests {
+    use crate::model::{Particle, Tile, TileKind, Tint, CLASSIC_CAMPAIGN_DATA};
+
+    use super::*;
 
-        for (name, level_data) in tier_data {
-            let mut tier_levels = Vec::with_capacity(level_data.len());
+    // NOTE: These levels exceed the solver's state budget, or take too long to solve in a debug
+    // build, so their solvability is not checked.
+    const UNSOLVED_LEVELS: &[&str] = &[
+        "Teamwork",
+        "Occlusion",
+        "Transfer",
+        "Mmmm, pi!",
+        "Milky Way",
+        "Maze",
+        "Checkers",
+        "Crowded",
+        "Juggle",
+        "I Kill You",
+        "Lock",
+        "Delicate",
+        "Nautilus",
+        "Trapped",
+        "Quadruped",
+        "Rails",
+    ];
+
+    #[test]
+    fn classic_campaign_levels() {
+        for (_, level_data) in CLASSIC_CAMPAIGN_DATA {
             for (name, pbc) in *level_data {
-                let board = Board::from_pbc1(pbc).unwrap();
-                tier_levels.push(levels.len());
-                levels.push(CampaignLevel {
-                    name: name.to_string(),
-                    board,
-                });
+                let board = Board::from_pbc1(pbc).unwrap_or_else(|err| panic!("{}: {}", name, err));
+                let progress = LevelProgress::new(&board);
+                assert!(progress.manipulators_left > 0, "{}: no manipulators", name);
+                assert!(progress.uncollected_particles > 0, "{}: no particles", name);
+                assert!(
+                    board.unsupported_pieces().is_empty(),
+                    "{}: unsupported pieces",
+                    name
+                );
+                if !UNSOLVED_LEVELS.contains(name) {
+                    assert!(board.solve().is_some(), "{}: unsolvable", name);
+                }
             }
-            tiers.push(CampaignTier {
-                name: name.to_string(),
-                levels: tier_levels,
-            });
         }
+    }
 
-        Self { levels, tiers }
+    #[test]
+    fn goals() {
+        let mut board = Board::new(2, 4);
+        for coords in board.dims.iter() {
+            board
+                .tiles
+                .set(coords, Tile::new(TileKind::Platform, Tint::White));
+        }
+        for col in [0, 2, 3] {
+            board
+                .tiles
+                .set((0, col).into(), Tile::new(TileKind::Collector, Tint::White));
+        }
+        for coords in [(0, 0), (1, 0), (1, 1), (1, 2)] {
+            board.pieces.set(coords.into(), Particle::new(Tint::Green));
+        }
+        let goals = [(0, 0).into(), (0, 2).into(), (0, 3).into()];
+
+        let mut progress = LevelProgress::with_goals(&board, &goals);
+        assert_eq!(progress.uncollected_particles(), 3);
+        assert_eq!(progress.goals_left(), &goals[1..]);
+        progress.particle_collected((0, 3).into());
+        assert_eq!(progress.goals_left(), &goals[1..2]);
+        assert_eq!(progress.outcome, None);
+        progress.particle_collected((0, 2).into());
+        assert_eq!(progress.uncollected_particles(), 1);
+        assert_eq!(progress.outcome, Some(LevelOutcome::Victory));
+
+        let progress = LevelProgress::new(&board);
+        assert!(progress.goals_left().is_empty());
     }
 
-    pub fn metadata(&self, level_idx: usize) -> LevelMetadata {
-        let next_idx = level_idx + 1;
-        LevelMetadata {
-            id: Some(level_idx),
-            name: Some(self.levels[level_idx].name.clone()),
-            next: (next_idx < self.levels.len()).then_some(next_idx),
+    #[test]
+    fn duplicate_collection() {
+        let mut board = Board::new(1, 3);
+        for coords in board.dims.iter() {
+            board
+                .tiles
+                .set(coords, Tile::new(TileKind::Platform, Tint::White));
         }
+        board
+            .tiles
+            .set((0, 0).into(), Tile::new(TileKind::Collector, Tint::White));
+        board.pieces.set((0, 0).into(), Particle::new(Tint::Green));
+        board.pieces.set((0, 1).into(), Particle::new(Tint::Red));
+        board.pieces.set((0, 2).into(), Particle::new(Tint::Yellow));
+
+        let mut progress = LevelProgress::new(&board);
+        assert_eq!(progress.uncollected_particles(), 2);
+        progress.particle_collected((0, 0).into());
+        assert_eq!(progress.uncollected_particles(), 2);
+
+        progress.particle_collected((0, 1).into());
+        progress.particle_collected((0, 1).into());
+        assert_eq!(progress.uncollected_particles(), 1);
+        assert_eq!(progress.outcome, None);
+
+        progress.particle_collected((0, 2).into());
+        progress.particle_collected((0, 2).into());
+        assert_eq!(progress.uncollected_particles(), 0);
+        assert_eq!(progress.outcome, Some(LevelOutcome::Victory));
+    }
+
+    #[test]
+    fn star_ratings() {
+        assert_eq!(star_rating(8, 10), 3);
+        assert_eq!(star_rating(10, 10), 3);
+        assert_eq!(star_rating(11, 10), 2);
+        assert_eq!(star_rating(13, 10), 2);
+        assert_eq!(star_rating(14, 10), 1);
+        assert_eq!(star_rating(2, 1), 2);
+        assert_eq!(star_rating(3, 1), 1);
     }
 }
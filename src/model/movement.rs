@@ -1,33 +1,68 @@
 use super::grid::Grid;
 use super::{
     BeamTargetKind, Board, BoardCoords, Border, Direction, GridMap, GridSet, Manipulator, Piece,
-    Tile, TileKind, Tint,
+    TileKind,
 };
 
 #[derive(Clone)]
 pub struct MoveSolver<'b> {
     board: &'b Board,
     leader: BoardCoords,
+    gathered: GridMap<u8>,
     graph: GridMap<u8>,
+    reasons: GridMap<MoveBlockReason>,
 }
 
+// NOTE: A piece that can't move because the piece in front of it can't move either is blocked for
+// the same reason as that piece, so a tint mismatch further down the line is reported as such.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveBlockReason {
+    Allowed,
+    BlockedByWall,
+    BlockedByBoard,
+    TintMismatch(BoardCoords),
+    CollectorAnchored(BoardCoords),
+    UnsupportedChain,
+}
+
+#[derive(Clone, Copy)]
+enum GatherStep {
+    Enter(BoardCoords),
+    Leave(BoardCoords),
+}
+
+// NOTE: Gathering the pieces attached to the leader doesn't depend on the direction, so it's done
+// once, and each query only prunes a fresh copy of the gathered graph.
 impl<'b> MoveSolver<'b> {
     pub fn new(board: &'b Board, leader: BoardCoords) -> Self {
-        let mut graph = GridMap::like(&board.pieces);
-        gather(board, leader, &mut graph, &mut GridSet::like(&board.pieces));
+        let mut gathered = GridMap::like(&board.pieces);
+        gather(board, leader, &mut gathered);
+        let graph = gathered.clone();
+        let reasons = GridMap::like(&board.pieces);
         Self {
             board,
             leader,
+            gathered,
             graph,
+            reasons,
         }
     }
 
-    pub fn can_move(mut self, direction: Direction) -> bool {
+    pub fn can_move(&mut self, direction: Direction) -> bool {
+        self.explain(direction) == MoveBlockReason::Allowed
+    }
+
+    pub fn explain(&mut self, direction: Direction) -> MoveBlockReason {
+        self.reset();
         self.prune(direction, Some(self.leader));
-        self.graph.get(self.leader).is_some()
+        match self.graph.get(self.leader) {
+            Some(_) => MoveBlockReason::Allowed,
+            None => self.pruned_reason(self.leader),
+        }
     }
 
-    pub fn drag(mut self, direction: Direction) -> GridSet {
+    pub fn drag(&mut self, direction: Direction) -> GridSet {
+        self.reset();
         self.prune(direction, None);
 
         let mut result = GridSet::like(&self.graph);
@@ -37,6 +72,11 @@ impl<'b> MoveSolver<'b> {
         result
     }
 
+    fn reset(&mut self) {
+        self.graph.mirror(&self.gathered);
+        self.reasons.clear();
+    }
+
     fn prune(&mut self, drag_direction: Direction, stop_coords: Option<BoardCoords>) {
         let mut pruned = true;
         while pruned {
@@ -45,8 +85,13 @@ impl<'b> MoveSolver<'b> {
                 let Some(&ref_count) = self.graph.get(coords) else {
                     continue;
                 };
-                if (ref_count == 0) || self.should_prune(coords, drag_direction) {
+                let reason = match ref_count {
+                    0 => MoveBlockReason::UnsupportedChain,
+                    _ => self.reason_at(coords, drag_direction),
+                };
+                if reason != MoveBlockReason::Allowed {
                     self.graph.set(coords, None);
+                    self.reasons.set(coords, reason);
                     if stop_coords == Some(coords) {
                         return;
                     }
@@ -65,31 +110,43 @@ impl<'b> MoveSolver<'b> {
         }
     }
 
-    fn should_prune(&self, coords: BoardCoords, drag_direction: Direction) -> bool {
+    fn reason_at(&self, coords: BoardCoords, drag_direction: Direction) -> MoveBlockReason {
         if self.get_border(coords, drag_direction).is_some() {
-            return true;
+            return MoveBlockReason::BlockedByWall;
         }
         let Some(neighbor) = self.board.neighbor(coords, drag_direction) else {
-            return true;
+            return MoveBlockReason::BlockedByBoard;
         };
         if let Some(Piece::Particle(particle)) = self.board.pieces.get(coords) {
+            // NOTE: Pieces only move a single cell at a time, so a particle that was recolored
+            // already has its new tint by the time it is checked against the next tile.
             if let Some(tile) = self.board.tiles.get(neighbor) {
-                if (tile.tint != Tint::White) && (tile.tint != particle.tint) {
-                    return true;
+                if (tile.kind != TileKind::Recolor)
+                    && !tile.tint.is_neutral()
+                    && (tile.tint != particle.tint)
+                {
+                    return MoveBlockReason::TintMismatch(neighbor);
                 }
             }
-            if let Some(Tile {
-                kind: TileKind::Collector,
-                ..
-            }) = self.board.tiles.get(coords)
-            {
-                return true;
-            }
+        }
+        if self.board.is_collected(coords) {
+            return MoveBlockReason::CollectorAnchored(coords);
         }
         if self.board.pieces.get(neighbor).is_none() {
-            return false;
+            return MoveBlockReason::Allowed;
+        }
+        match self.graph.get(neighbor) {
+            Some(_) => MoveBlockReason::Allowed,
+            None => self.pruned_reason(neighbor),
         }
-        self.graph.get(neighbor).is_none()
+    }
+
+    // NOTE: A piece that was never part of the dragged graph can't be pushed out of the way
+    fn pruned_reason(&self, coords: BoardCoords) -> MoveBlockReason {
+        self.reasons
+            .get(coords)
+            .copied()
+            .unwrap_or(MoveBlockReason::UnsupportedChain)
     }
 
     fn get_border(&self, piece_coords: BoardCoords, direction: Direction) -> Option<&Border> {
@@ -99,22 +156,32 @@ impl<'b> MoveSolver<'b> {
     }
 }
 
-fn gather(board: &Board, coords: BoardCoords, graph: &mut GridMap<u8>, visited: &mut GridSet) {
-    if let Some(ref_count) = graph.get_mut(coords) {
-        *ref_count += 1;
-    } else {
-        graph.set(coords, 1);
-    }
-
-    if visited.contains(coords) {
-        return;
-    }
-    let mut visited = visited.scoped_insert(coords);
+// NOTE: The visited set only guards the current path, like the call stack of a recursive walk
+// would, so a piece is counted once for every beam reaching it along a path from the leader. The
+// path is kept on an explicit stack, where leaving a piece is queued behind all of its targets.
+fn gather(board: &Board, leader: BoardCoords, graph: &mut GridMap<u8>) {
+    let mut path = GridSet::like(graph);
+    let mut stack = vec![GatherStep::Enter(leader)];
+    while let Some(step) = stack.pop() {
+        let coords = match step {
+            GatherStep::Enter(coords) => coords,
+            GatherStep::Leave(coords) => {
+                path.remove(coords);
+                continue;
+            }
+        };
+        *graph.get_or_insert_with(coords, || 0) += 1;
+        if path.contains(coords) {
+            continue;
+        }
+        path.insert(coords);
+        stack.push(GatherStep::Leave(coords));
 
-    if let Some(manipulator) = get_manipulator(board, coords) {
-        for target in manipulator.iter_targets() {
-            if target.kind == BeamTargetKind::Piece {
-                gather(board, target.coords, graph, &mut visited);
+        if let Some(manipulator) = get_manipulator(board, coords) {
+            for target in manipulator.iter_targets() {
+                if target.kind == BeamTargetKind::Piece {
+                    stack.push(GatherStep::Enter(target.coords));
+                }
             }
         }
     }
@@ -129,7 +196,12 @@ fn get_manipulator(board: &Board, coords: BoardCoords) -> Option<&Manipulator> {
 
 #[cfg(test)]
 mod tests {
-    use crate::model::{Emitters, Particle, Tile, TileKind, Tint};
+    use strum::IntoEnumIterator;
+
+    use crate::model::{
+        Emitters, Particle, Tile, TileKind, Tint, CLASSIC_CAMPAIGN_DATA, MAX_BOARD_COLS,
+        MAX_BOARD_ROWS,
+    };
 
     use super::*;
 
@@ -142,11 +214,35 @@ mod tests {
         add_manipulator(&mut board, (2, 2).into(), Emitters::LeftUp);
         board.retarget_beams();
 
-        let solver = MoveSolver::new(&board, (1, 1).into());
-        assert!(solver.clone().can_move(Direction::Up));
-        assert!(solver.clone().can_move(Direction::Left));
-        assert!(solver.clone().can_move(Direction::Down));
-        assert!(solver.clone().can_move(Direction::Right));
+        let mut solver = MoveSolver::new(&board, (1, 1).into());
+        assert!(solver.can_move(Direction::Up));
+        assert!(solver.can_move(Direction::Left));
+        assert!(solver.can_move(Direction::Down));
+        assert!(solver.can_move(Direction::Right));
+    }
+
+    #[test]
+    fn reused_solver() {
+        for (_, levels) in CLASSIC_CAMPAIGN_DATA {
+            for (_, code) in *levels {
+                let board = Board::from_pbc1(code).unwrap();
+                for coords in board.dims.iter() {
+                    if get_manipulator(&board, coords).is_none() {
+                        continue;
+                    }
+                    let mut solver = MoveSolver::new(&board, coords);
+                    for direction in Direction::iter().chain(Direction::iter().rev()) {
+                        let can_move = MoveSolver::new(&board, coords).can_move(direction);
+                        assert_eq!(solver.can_move(direction), can_move);
+                        let move_set = board.compute_move_set(coords, direction);
+                        assert_eq!(
+                            solver.drag(direction).to_vec(direction),
+                            move_set.to_vec(direction)
+                        );
+                    }
+                }
+            }
+        }
     }
 
     #[test]
@@ -157,7 +253,63 @@ mod tests {
         add_tile(&mut board, (0, 2).into(), TileKind::Platform, Tint::Red);
         board.retarget_beams();
 
-        assert!(!MoveSolver::new(&board, (0, 0).into()).can_move(Direction::Right));
+        let mut solver = MoveSolver::new(&board, (0, 0).into());
+        assert!(!solver.can_move(Direction::Right));
+        assert_eq!(
+            solver.explain(Direction::Right),
+            MoveBlockReason::TintMismatch((0, 2).into())
+        );
+    }
+
+    #[test]
+    fn tint_blocker() {
+        let mut board = empty_board(2, 4);
+        add_manipulator(&mut board, (0, 0).into(), Emitters::Right);
+        add_manipulator(&mut board, (0, 1).into(), Emitters::Right);
+        board.pieces.set((0, 2).into(), Particle::new(Tint::Green));
+        add_tile(&mut board, (0, 3).into(), TileKind::Platform, Tint::Red);
+        board.retarget_beams();
+
+        let mut solver = MoveSolver::new(&board, (0, 0).into());
+        assert_eq!(
+            solver.explain(Direction::Right),
+            MoveBlockReason::TintMismatch((0, 3).into())
+        );
+        assert_eq!(
+            solver.explain(Direction::Left),
+            MoveBlockReason::BlockedByBoard
+        );
+        assert_eq!(solver.explain(Direction::Down), MoveBlockReason::Allowed);
+
+        let reasons = board.compute_allowed_moves_with_reasons((0, 1).into());
+        assert_eq!(
+            reasons[Direction::Right],
+            MoveBlockReason::TintMismatch((0, 3).into())
+        );
+        assert_eq!(reasons[Direction::Left], MoveBlockReason::UnsupportedChain);
+        assert_eq!(reasons[Direction::Up], MoveBlockReason::BlockedByBoard);
+        assert_eq!(reasons[Direction::Down], MoveBlockReason::Allowed);
+    }
+
+    #[test]
+    fn recolor() {
+        let mut board = empty_board(1, 4);
+        add_manipulator(&mut board, (0, 0).into(), Emitters::Right);
+        board.pieces.set((0, 1).into(), Particle::new(Tint::Green));
+        add_tile(&mut board, (0, 2).into(), TileKind::Recolor, Tint::Red);
+        add_tile(&mut board, (0, 3).into(), TileKind::Platform, Tint::Red);
+        board.retarget_beams();
+
+        let set = MoveSolver::new(&board, (0, 0).into()).drag(Direction::Right);
+        assert!(set.contains((0, 1).into()));
+        board.move_pieces(&set, Direction::Right);
+        board.retarget_beams();
+
+        let Some(Piece::Particle(particle)) = board.pieces.get((0, 2).into()) else {
+            panic!("particle not moved");
+        };
+        assert_eq!(particle.tint, Tint::Red);
+        assert!(MoveSolver::new(&board, (0, 1).into()).can_move(Direction::Right));
     }
 
     #[test]
@@ -168,7 +320,48 @@ mod tests {
         add_tile(&mut board, (0, 1).into(), TileKind::Collector, Tint::White);
         board.retarget_beams();
 
+        let mut solver = MoveSolver::new(&board, (0, 0).into());
+        assert!(!solver.can_move(Direction::Right));
+        assert_eq!(
+            solver.explain(Direction::Right),
+            MoveBlockReason::CollectorAnchored((0, 1).into())
+        );
+    }
+
+    #[test]
+    fn collected_particle_blocks_movement() {
+        let mut board = empty_board(1, 3);
+        add_manipulator(&mut board, (0, 0).into(), Emitters::Up);
+        board.pieces.set((0, 1).into(), Particle::new(Tint::Green));
+        add_tile(&mut board, (0, 1).into(), TileKind::Collector, Tint::White);
+        add_manipulator(&mut board, (0, 2).into(), Emitters::Up);
+        board.retarget_beams();
+
         assert!(!MoveSolver::new(&board, (0, 0).into()).can_move(Direction::Right));
+        assert!(!MoveSolver::new(&board, (0, 2).into()).can_move(Direction::Left));
+    }
+
+    #[test]
+    fn corner_borders() {
+        let mut board = empty_board(2, 2);
+        add_manipulator(&mut board, (0, 0).into(), Emitters::Up);
+        board.corner_borders.set((1, 1).into(), Border::Wall);
+        board.retarget_beams();
+
+        let mut solver = MoveSolver::new(&board, (0, 0).into());
+        assert!(solver.can_move(Direction::Right));
+        assert!(solver.can_move(Direction::Down));
+
+        board.horz_borders.set((0, 0).into(), Border::Wall);
+        let mut solver = MoveSolver::new(&board, (0, 0).into());
+        assert_eq!(
+            solver.explain(Direction::Up),
+            MoveBlockReason::BlockedByWall
+        );
+        assert_eq!(
+            solver.explain(Direction::Left),
+            MoveBlockReason::BlockedByBoard
+        );
     }
 
     #[test]
@@ -187,6 +380,10 @@ mod tests {
         board.horz_borders.set((1, 3).into(), Border::Wall);
         board.horz_borders.set((3, 4).into(), Border::Window);
         board.retarget_beams();
+        assert!(board.any_move_available());
+        assert_eq!(board.manipulators().count(), 7);
+        let particles: Vec<BoardCoords> = board.particles().map(|(coords, _)| coords).collect();
+        assert_eq!(particles, vec![(1, 2).into(), (1, 3).into(), (2, 4).into()]);
 
         let set = MoveSolver::new(&board, (2, 2).into()).drag(Direction::Up);
         assert!(set.contains((1, 1).into()));
@@ -201,6 +398,93 @@ mod tests {
         assert!(!set.contains((3, 4).into()));
     }
 
+    #[test]
+    fn gather_long_chain() {
+        // NOTE: The beams snake through every cell of the largest board, and the last manipulator
+        // points back along the chain, so the walk is as deep as it gets and closes a cycle.
+        let mut board = empty_board(MAX_BOARD_ROWS, MAX_BOARD_COLS);
+        for row in 0..MAX_BOARD_ROWS {
+            let (forward, last_col) = match row % 2 {
+                0 => (Emitters::Right, MAX_BOARD_COLS - 1),
+                _ => (Emitters::Left, 0),
+            };
+            for col in 0..MAX_BOARD_COLS {
+                let emitters = match (col == last_col, row == MAX_BOARD_ROWS - 1) {
+                    (false, _) => forward,
+                    (true, false) => Emitters::Down,
+                    (true, true) => Emitters::LeftUp,
+                };
+                add_manipulator(&mut board, (row, col).into(), emitters);
+            }
+        }
+        board.retarget_beams();
+
+        for leader in [(0, 0), (7, 7), (14, 14)] {
+            let leader = leader.into();
+            let mut graph = GridMap::like(&board.pieces);
+            gather(&board, leader, &mut graph);
+            let mut expected = GridMap::like(&board.pieces);
+            gather_recursive(
+                &board,
+                leader,
+                &mut expected,
+                &mut GridSet::like(&board.pieces),
+            );
+            assert_eq!(
+                graph.iter().collect::<Vec<_>>(),
+                expected.iter().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn gather_campaign() {
+        for (_, levels) in CLASSIC_CAMPAIGN_DATA {
+            for (name, code) in *levels {
+                let board = Board::from_pbc1(code).unwrap();
+                for (coords, _) in board.pieces.iter() {
+                    let mut graph = GridMap::like(&board.pieces);
+                    gather(&board, coords, &mut graph);
+                    let mut expected = GridMap::like(&board.pieces);
+                    gather_recursive(
+                        &board,
+                        coords,
+                        &mut expected,
+                        &mut GridSet::like(&board.pieces),
+                    );
+                    assert_eq!(
+                        graph.iter().collect::<Vec<_>>(),
+                        expected.iter().collect::<Vec<_>>(),
+                        "{} at {}",
+                        name,
+                        coords
+                    );
+                }
+            }
+        }
+    }
+
+    fn gather_recursive(
+        board: &Board,
+        coords: BoardCoords,
+        graph: &mut GridMap<u8>,
+        visited: &mut GridSet,
+    ) {
+        *graph.get_or_insert_with(coords, || 0) += 1;
+        if visited.contains(coords) {
+            return;
+        }
+        visited.insert(coords);
+        if let Some(manipulator) = get_manipulator(board, coords) {
+            for target in manipulator.iter_targets() {
+                if target.kind == BeamTargetKind::Piece {
+                    gather_recursive(board, target.coords, graph, visited);
+                }
+            }
+        }
+        visited.remove(coords);
+    }
+
     fn empty_board(rows: usize, cols: usize) -> Board {
         let mut board = Board::new(rows, cols);
         for coords in board.dims.iter() {
@@ -2,10 +2,10 @@ use base64::Engine;
 use bitter::{BitReader, LittleEndianReader};
 use thiserror::Error;
 
-use super::grid::GridMap;
+use super::grid::{Grid, GridMap};
 use super::{
     Board, BoardCoords, Border, Dimensions, Emitters, Manipulator, Particle, Piece, Tile, TileKind,
-    Tint,
+    Tint, MAX_BOARD_COLS, MAX_BOARD_ROWS,
 };
 
 #[derive(Error, Debug)]
@@ -22,6 +22,9 @@ pub enum Pbc1DecodeError {
     #[error("invalid version {0}, expected 1")]
     Version(u8),
 
+    #[error("invalid tile value {0}")]
+    InvalidTile(u8),
+
     #[error("invalid piece value {0}")]
     InvalidPiece(u8),
 
@@ -29,6 +32,15 @@ pub enum Pbc1DecodeError {
     InvalidBorder(u8),
 }
 
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum Pbc1EncodeError {
+    #[error("the board is {rows}x{cols}, which is larger than allowed")]
+    TooLarge { rows: usize, cols: usize },
+
+    #[error("the border at {coords:?} can only be a wall")]
+    UnsupportedBorder { coords: BoardCoords },
+}
+
 pub fn decode(code: &str) -> Result<Board, Pbc1DecodeError> {
     if !code.starts_with(":PBC1:") {
         return Err(Pbc1DecodeError::Signature);
@@ -42,7 +54,13 @@ pub fn decode(code: &str) -> Result<Board, Pbc1DecodeError> {
         return Err(Pbc1DecodeError::Version(version));
     }
 
-    let _flags = bits.read_bits(4).ok_or(Pbc1DecodeError::UnexpectedEnd)? as u8;
+    let flags = bits.read_bits(4).ok_or(Pbc1DecodeError::UnexpectedEnd)? as u8;
+    // NOTE: Tile kinds beyond platforms and collectors don't fit into the original encoding, so
+    // boards using them set a flag that widens each tile by a bit.
+    let tile_bits = match flags & EXTENDED_TILES_FLAG {
+        0 => 3,
+        _ => 4,
+    };
     let cols = bits.read_bits(4).ok_or(Pbc1DecodeError::UnexpectedEnd)? as usize;
     let rows = bits.read_bits(4).ok_or(Pbc1DecodeError::UnexpectedEnd)? as usize;
 
@@ -50,6 +68,7 @@ pub fn decode(code: &str) -> Result<Board, Pbc1DecodeError> {
     let mut tiles = GridMap::new(rows, cols);
     let mut horz_borders = GridMap::new(rows + 1, cols);
     let mut vert_borders = GridMap::new(rows, cols + 1);
+    let mut corner_borders = GridMap::new(rows + 1, cols + 1);
     let mut pieces = GridMap::new(rows, cols);
 
     for row in 0..rows {
@@ -58,23 +77,25 @@ pub fn decode(code: &str) -> Result<Board, Pbc1DecodeError> {
             let flags = bits.read_bits(3).ok_or(Pbc1DecodeError::UnexpectedEnd)? as u8;
 
             if (flags & 1) != 0 {
-                let tile = bits.read_bits(3).ok_or(Pbc1DecodeError::UnexpectedEnd)? as u8;
-                let kind = TileKind::from_repr(tile >> 2).unwrap();
-                let tint = Tint::from_repr(tile & 3).unwrap();
+                let tile = bits
+                    .read_bits(tile_bits)
+                    .ok_or(Pbc1DecodeError::UnexpectedEnd)? as u8;
+                let kind =
+                    TileKind::from_repr(tile >> 2).ok_or(Pbc1DecodeError::InvalidTile(tile))?;
+                let tint = Tint::from_repr(tile & 3).ok_or(Pbc1DecodeError::InvalidTile(tile))?;
                 tiles.set(coords, Tile::new(kind, tint));
             }
 
             if (flags & 2) != 0 {
                 let piece = bits.read_bits(4).ok_or(Pbc1DecodeError::UnexpectedEnd)? as u8;
-                if piece < 3 {
-                    let tint = Tint::from_repr(piece + 1).unwrap();
-                    pieces.set(coords, Piece::Particle(Particle::new(tint)));
-                } else if piece < 13 {
-                    let emitters = Emitters::from_repr(piece - 3).unwrap();
-                    pieces.set(coords, Piece::Manipulator(Manipulator::new(emitters)));
-                } else {
-                    return Err(Pbc1DecodeError::InvalidPiece(piece));
-                }
+                let decoded = match piece {
+                    0..=2 => {
+                        Tint::from_repr(piece + 1).map(|tint| Piece::Particle(Particle::new(tint)))
+                    }
+                    _ => Emitters::from_repr(piece - 3)
+                        .map(|emitters| Piece::Manipulator(Manipulator::new(emitters))),
+                };
+                pieces.set(coords, decoded.ok_or(Pbc1DecodeError::InvalidPiece(piece))?);
             }
 
             if (flags & 4) != 0 {
@@ -104,15 +125,308 @@ pub fn decode(code: &str) -> Result<Board, Pbc1DecodeError> {
             horz_borders.set((rows, col).into(), Border::Wall);
         }
     }
+    // NOTE: Corner borders are rare, so rather than growing every cell, boards that have them set
+    // a flag and append two bits per corner after the rest of the board.
+    if (flags & CORNER_BORDERS_FLAG) != 0 {
+        for coords in corner_borders.dims().iter() {
+            let border = bits.read_bits(2).ok_or(Pbc1DecodeError::UnexpectedEnd)? as u8;
+            let border = match border {
+                0 => None,
+                1 => Some(Border::Wall),
+                2 => Some(Border::Window),
+                _ => return Err(Pbc1DecodeError::InvalidBorder(border)),
+            };
+            corner_borders.set(coords, border);
+        }
+    }
 
     let mut board = Board {
         dims,
         tiles,
         horz_borders,
         vert_borders,
+        corner_borders,
         pieces,
     };
     board.retarget_beams();
 
     Ok(board)
 }
+
+// NOTE: The top and left edges of the board are covered by the borders of the cells along them,
+// but the bottom and right edges only get a bit per cell, so they can only hold walls.
+pub fn encode(board: &Board) -> Result<String, Pbc1EncodeError> {
+    let Dimensions { rows, cols } = board.dims;
+    if (rows > MAX_BOARD_ROWS) || (cols > MAX_BOARD_COLS) {
+        return Err(Pbc1EncodeError::TooLarge { rows, cols });
+    }
+
+    let extended_tiles = board
+        .tiles
+        .iter()
+        .any(|(_, tile)| (tile.kind as u8) > (TileKind::Collector as u8));
+    let has_corners = board.corner_borders.iter().next().is_some();
+    let mut flags = 0;
+    if extended_tiles {
+        flags |= EXTENDED_TILES_FLAG;
+    }
+    if has_corners {
+        flags |= CORNER_BORDERS_FLAG;
+    }
+    let tile_bits = match extended_tiles {
+        false => 3,
+        true => 4,
+    };
+
+    let mut bits = BitWriter::default();
+    bits.write(1, 4);
+    bits.write(flags as u64, 4);
+    bits.write(cols as u64, 4);
+    bits.write(rows as u64, 4);
+
+    for row in 0..rows {
+        for col in 0..cols {
+            let coords = BoardCoords::new(row, col);
+            let tile = board.tiles.get(coords);
+            let piece = board.pieces.get(coords);
+            let borders = border_code(board.horz_borders.get(coords))
+                + 3 * border_code(board.vert_borders.get(coords));
+
+            let mut cell_flags = 0;
+            if tile.is_some() {
+                cell_flags |= 1;
+            }
+            if piece.is_some() {
+                cell_flags |= 2;
+            }
+            if borders != 0 {
+                cell_flags |= 4;
+            }
+            bits.write(cell_flags, 3);
+
+            if let Some(tile) = tile {
+                bits.write(
+                    (((tile.kind as u8) << 2) | tile.tint as u8) as u64,
+                    tile_bits,
+                );
+            }
+            match piece {
+                None => (),
+                Some(Piece::Particle(particle)) => bits.write(particle.tint as u64 - 1, 4),
+                Some(Piece::Manipulator(manipulator)) => {
+                    bits.write(manipulator.emitters as u64 + 3, 4)
+                }
+            }
+            if borders != 0 {
+                bits.write(borders - 1, 3);
+            }
+        }
+        let coords = BoardCoords::new(row, cols);
+        bits.write_bit(outer_wall(board.vert_borders.get(coords), coords)?);
+    }
+    for col in 0..cols {
+        let coords = BoardCoords::new(rows, col);
+        bits.write_bit(outer_wall(board.horz_borders.get(coords), coords)?);
+    }
+    if has_corners {
+        for coords in board.corner_borders.dims().iter() {
+            bits.write(border_code(board.corner_borders.get(coords)), 2);
+        }
+    }
+
+    let data = base64::engine::general_purpose::STANDARD.encode(bits.bytes);
+    Ok(format!(":PBC1:{}", data))
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, count: u32) {
+        for bit in 0..count {
+            self.write_bit(((value >> bit) & 1) != 0);
+        }
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        if self.len.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 1 << (self.len % 8);
+        }
+        self.len += 1;
+    }
+}
+
+fn border_code(border: Option<&Border>) -> u64 {
+    match border {
+        None => 0,
+        Some(Border::Wall) => 1,
+        Some(Border::Window) => 2,
+    }
+}
+
+fn outer_wall(border: Option<&Border>, coords: BoardCoords) -> Result<bool, Pbc1EncodeError> {
+    match border {
+        None => Ok(false),
+        Some(Border::Wall) => Ok(true),
+        Some(Border::Window) => Err(Pbc1EncodeError::UnsupportedBorder { coords }),
+    }
+}
+
+const EXTENDED_TILES_FLAG: u8 = 1;
+const CORNER_BORDERS_FLAG: u8 = 2;
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{BoardBuilder, CLASSIC_CAMPAIGN_DATA};
+
+    use super::*;
+
+    #[test]
+    fn campaign_round_trip() {
+        for (_, level_data) in CLASSIC_CAMPAIGN_DATA {
+            for (name, pbc) in *level_data {
+                let board = decode(pbc).unwrap();
+                let encoded = board
+                    .to_pbc1()
+                    .unwrap_or_else(|err| panic!("{}: {}", name, err));
+                assert!(decode(&encoded).unwrap().layout_eq(&board), "{}", name);
+                assert_eq!(&encoded, pbc, "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn outer_walls_round_trip() {
+        let mut builder = BoardBuilder::new(2, 3);
+        for col in 0..3 {
+            builder = builder
+                .with_horz_border((0, col).into(), Border::Wall)
+                .with_horz_border((2, col).into(), Border::Wall);
+        }
+        for row in 0..2 {
+            builder = builder
+                .with_vert_border((row, 0).into(), Border::Wall)
+                .with_vert_border((row, 3).into(), Border::Wall);
+        }
+        let board = builder.build();
+
+        let decoded = decode(&encode(&board).unwrap()).unwrap();
+        assert!(decoded.layout_eq(&board));
+        for col in 0..3 {
+            assert_eq!(
+                decoded.horz_borders.get((0, col).into()),
+                Some(&Border::Wall)
+            );
+            assert_eq!(
+                decoded.horz_borders.get((2, col).into()),
+                Some(&Border::Wall)
+            );
+        }
+        for row in 0..2 {
+            assert_eq!(
+                decoded.vert_borders.get((row, 0).into()),
+                Some(&Border::Wall)
+            );
+            assert_eq!(
+                decoded.vert_borders.get((row, 3).into()),
+                Some(&Border::Wall)
+            );
+        }
+
+        let mut windowed = board.clone();
+        windowed.horz_borders.set((0, 1).into(), Border::Window);
+        windowed.vert_borders.set((1, 0).into(), Border::Window);
+        let decoded = decode(&encode(&windowed).unwrap()).unwrap();
+        assert!(decoded.layout_eq(&windowed));
+
+        windowed.vert_borders.set((1, 3).into(), Border::Window);
+        assert_eq!(
+            encode(&windowed).err(),
+            Some(Pbc1EncodeError::UnsupportedBorder {
+                coords: (1, 3).into()
+            })
+        );
+    }
+
+    #[test]
+    fn extended_tiles() {
+        let board = decode(":PBC1:ERFZAA==").unwrap();
+        let tile = board.tiles.get((0, 0).into()).unwrap();
+        assert_eq!(tile.kind, TileKind::Recolor);
+        assert_eq!(tile.tint, Tint::Red);
+    }
+
+    #[test]
+    fn corrupt_codes() {
+        let error = |code: &str| decode(code).err().unwrap();
+        assert!(matches!(error("PBC1:ERFZAA=="), Pbc1DecodeError::Signature));
+        assert!(matches!(error(":PBC1:E?FZ"), Pbc1DecodeError::Base64(_)));
+        assert!(matches!(error(":PBC1:"), Pbc1DecodeError::UnexpectedEnd));
+
+        let (_, pbc) = CLASSIC_CAMPAIGN_DATA[0].1[0];
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(&pbc[6..])
+            .unwrap();
+        let truncated = base64::engine::general_purpose::STANDARD.encode(&bytes[..bytes.len() / 2]);
+        assert!(matches!(
+            error(&format!(":PBC1:{}", truncated)),
+            Pbc1DecodeError::UnexpectedEnd
+        ));
+
+        // NOTE: Each of these is a 1x1 board, so its header is followed by a single cell, the two
+        // outer edge bits, and the corners, if it has any
+        let code = |version: u64, flags: u64, fields: &[(u64, u32)]| {
+            let mut bits = BitWriter::default();
+            bits.write(version, 4);
+            bits.write(flags, 4);
+            bits.write(1, 4);
+            bits.write(1, 4);
+            for &(value, count) in fields {
+                bits.write(value, count);
+            }
+            let data = base64::engine::general_purpose::STANDARD.encode(bits.bytes);
+            format!(":PBC1:{}", data)
+        };
+        assert!(matches!(
+            error(&code(2, 0, &[(0, 3), (0, 2)])),
+            Pbc1DecodeError::Version(2)
+        ));
+        assert!(matches!(
+            error(&code(1, 0, &[(2, 3), (13, 4), (0, 2)])),
+            Pbc1DecodeError::InvalidPiece(13)
+        ));
+        assert!(matches!(
+            error(&code(1, 0, &[(2, 3), (15, 4), (0, 2)])),
+            Pbc1DecodeError::InvalidPiece(15)
+        ));
+        let extended = EXTENDED_TILES_FLAG as u64;
+        assert!(matches!(
+            error(&code(1, extended, &[(1, 3), (0b1101, 4), (0, 2)])),
+            Pbc1DecodeError::InvalidTile(0b1101)
+        ));
+        let corners = CORNER_BORDERS_FLAG as u64;
+        assert!(matches!(
+            error(&code(1, corners, &[(0, 3), (0, 2), (3, 2), (0, 6)])),
+            Pbc1DecodeError::InvalidBorder(3)
+        ));
+        assert!(decode(&code(1, corners, &[(0, 3), (1, 2), (2, 8)])).is_ok());
+    }
+
+    #[test]
+    fn corner_borders() {
+        let board = decode(":PBC1:IREBgQ==").unwrap();
+        assert_eq!(board.corner_borders.get((0, 0).into()), Some(&Border::Wall));
+        assert_eq!(board.corner_borders.get((0, 1).into()), None);
+        assert_eq!(board.corner_borders.get((1, 0).into()), None);
+        assert_eq!(
+            board.corner_borders.get((1, 1).into()),
+            Some(&Border::Window)
+        );
+    }
+}
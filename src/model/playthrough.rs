@@ -0,0 +1,97 @@
+//! Scripted runs through campaign levels, played move by move without the engine
+
+use super::solver::{play_move, Move};
+use super::{
+    Board, BoardCoords, Direction, LevelOutcome, LevelProgress, Piece, CLASSIC_CAMPAIGN_DATA,
+};
+
+struct Playthrough {
+    board: Board,
+    progress: LevelProgress,
+}
+
+impl Playthrough {
+    fn campaign_level(name: &str) -> Self {
+        let pbc = CLASSIC_CAMPAIGN_DATA
+            .iter()
+            .flat_map(|(_, level_data)| level_data.iter())
+            .find(|(level_name, _)| *level_name == name)
+            .map(|(_, pbc)| *pbc)
+            .unwrap_or_else(|| panic!("no campaign level named {}", name));
+        let mut board = Board::from_pbc1(pbc).unwrap();
+        board.retarget_beams();
+        let progress = LevelProgress::new(&board);
+        Self { board, progress }
+    }
+
+    // NOTE: The engine only lets the player move a selected manipulator in one of the directions
+    // its focus allows, and stops taking moves once the level is over.
+    fn play(&mut self, coords: BoardCoords, direction: Direction) {
+        assert_eq!(
+            self.progress.outcome, None,
+            "{:?} played after the end",
+            coords
+        );
+        let Some(Piece::Manipulator(_)) = self.board.pieces.get(coords) else {
+            panic!("no manipulator at {:?}", coords);
+        };
+        let allowed = self.board.compute_allowed_moves(coords);
+        assert!(
+            allowed.contains(direction),
+            "{:?} can't move {:?}",
+            coords,
+            direction
+        );
+        play_move(
+            &mut self.board,
+            &mut self.progress,
+            Move::new(coords, direction),
+        );
+    }
+
+    // NOTE: Each letter moves the same manipulator one step further, following it as it goes
+    fn play_script(&mut self, start: (usize, usize), script: &str) {
+        let mut coords = BoardCoords::from(start);
+        for letter in script.chars() {
+            let direction = match letter {
+                'U' => Direction::Up,
+                'L' => Direction::Left,
+                'D' => Direction::Down,
+                'R' => Direction::Right,
+                _ => panic!("invalid script letter {:?}", letter),
+            };
+            self.play(coords, direction);
+            coords = self.board.neighbor(coords, direction).unwrap();
+        }
+    }
+}
+
+#[test]
+fn tutorial_victory() {
+    let mut playthrough = Playthrough::campaign_level("Tutorial");
+    playthrough.play_script((0, 0), "DRRRRRDRRRLLLDDRDLLDLLLLDRRRLDDLLUDRRRRUL");
+    assert_eq!(playthrough.progress.outcome, Some(LevelOutcome::Victory));
+    assert_eq!(playthrough.progress.uncollected_particles(), 0);
+}
+
+#[test]
+fn tutorial_particle_lost() {
+    let mut playthrough = Playthrough::campaign_level("Tutorial");
+    playthrough.play_script((0, 0), "D");
+    assert_eq!(playthrough.progress.outcome, None);
+    playthrough.play_script((1, 0), "U");
+    assert_eq!(
+        playthrough.progress.outcome,
+        Some(LevelOutcome::ParticleLost)
+    );
+}
+
+#[test]
+fn experiment_no_manipulators_left() {
+    let mut playthrough = Playthrough::campaign_level("Experiment");
+    playthrough.play_script((5, 9), "LL");
+    assert_eq!(
+        playthrough.progress.outcome,
+        Some(LevelOutcome::NoManipulatorsLeft)
+    );
+}
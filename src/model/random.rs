@@ -0,0 +1,22 @@
+// NOTE: A plain xorshift is plenty for picking levels and laying out boards, and unlike the
+// standard library's hashers it produces the same sequence everywhere. The state must never be
+// zero, or it stays zero forever.
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+pub fn next_index(state: &mut u64, len: usize) -> usize {
+    (next_random(state) % len as u64) as usize
+}
+
+// NOTE: Neighboring seeds, like consecutive days, would otherwise start out with nearly the same
+// state, so the seed is scrambled first.
+pub fn mix_seed(seed: u64) -> u64 {
+    let mut mixed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (mixed ^ (mixed >> 31)) | 1
+}
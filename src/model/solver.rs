@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use strum::IntoEnumIterator;
+
+use super::{Board, BoardCoords, Direction, LevelOutcome, LevelProgress};
+
+// NOTE: Larger levels can have far more reachable states than this. Giving up keeps the search
+// bounded, at the cost of reporting such levels as unsolvable.
+const MAX_STATES: usize = 250_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub coords: BoardCoords,
+    pub direction: Direction,
+}
+
+struct Node {
+    parent: Option<usize>,
+    step: Option<Move>,
+}
+
+impl Move {
+    pub fn new(coords: BoardCoords, direction: Direction) -> Self {
+        Self { coords, direction }
+    }
+}
+
+pub fn solve(board: &Board) -> Option<Vec<Move>> {
+    solve_within(board, MAX_STATES)
+}
+
+pub fn solve_within(board: &Board, max_states: usize) -> Option<Vec<Move>> {
+    let mut board = board.clone();
+    board.retarget_beams();
+    let progress = LevelProgress::new(&board);
+
+    let mut nodes = vec![Node {
+        parent: None,
+        step: None,
+    }];
+    // NOTE: States are told apart by their content hash alone. A collision could hide a state,
+    // but it is far less likely than running out of the state budget.
+    let mut visited = HashSet::new();
+    visited.insert(board.content_hash());
+
+    // NOTE: Only the current depth is kept in memory, since cloning every visited board would
+    // make the search much more expensive than bookkeeping the path to it.
+    let mut frontier = vec![(0, board, progress)];
+    while !frontier.is_empty() {
+        let mut next_frontier = Vec::new();
+        for (node_idx, board, progress) in frontier {
+            for (coords, _) in board.manipulators() {
+                let allowed = board.compute_allowed_moves(coords);
+                for direction in Direction::iter().filter(|&dir| allowed.contains(dir)) {
+                    let step = Move::new(coords, direction);
+                    let mut board = board.clone();
+                    let mut progress = progress.clone();
+                    play_move(&mut board, &mut progress, step);
+
+                    if !visited.insert(board.content_hash()) {
+                        continue;
+                    }
+                    let child_idx = nodes.len();
+                    nodes.push(Node {
+                        parent: Some(node_idx),
+                        step: Some(step),
+                    });
+
+                    match progress.outcome {
+                        Some(LevelOutcome::Victory) => return Some(trace_path(&nodes, child_idx)),
+                        Some(_) => continue,
+                        None => (),
+                    }
+                    if nodes.len() >= max_states {
+                        return None;
+                    }
+                    next_frontier.push((child_idx, board, progress));
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+    None
+}
+
+// NOTE: This follows the same steps as the engine does for a move, from issuing it until the
+// animations finish: moving the pieces, collecting particles, and dropping unsupported pieces.
+pub fn play_move(board: &mut Board, progress: &mut LevelProgress, step: Move) {
+    let move_set = board.compute_move_set(step.coords, step.direction);
+    board.move_pieces(&move_set, step.direction);
+    for coords in move_set.iter() {
+        let coords = board.neighbor(coords, step.direction).unwrap();
+        if board.is_collected(coords) {
+            progress.particle_collected(coords);
+        }
+    }
+    board.retarget_beams();
+
+    let unsupported = board.unsupported_pieces();
+    if unsupported.is_empty() {
+        return;
+    }
+    for coords in unsupported.iter() {
+        progress.piece_lost(board.pieces.get(coords).unwrap());
+        board.remove_piece(coords);
+    }
+    board.retarget_beams();
+}
+
+fn trace_path(nodes: &[Node], mut idx: usize) -> Vec<Move> {
+    let mut path = Vec::new();
+    while let Some(step) = nodes[idx].step {
+        path.push(step);
+        idx = nodes[idx].parent.unwrap();
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{BoardBuilder, Emitters, Manipulator, Particle, Tile, TileKind, Tint};
+
+    use super::*;
+
+    #[test]
+    fn solve_single_push() {
+        let mut board = Board::new(1, 4);
+        for coords in board.dims.iter() {
+            board
+                .tiles
+                .set(coords, Tile::new(TileKind::Platform, Tint::White));
+        }
+        board
+            .tiles
+            .set((0, 3).into(), Tile::new(TileKind::Collector, Tint::Green));
+        board
+            .pieces
+            .set((0, 0).into(), Manipulator::new(Emitters::Right));
+        board.pieces.set((0, 2).into(), Particle::new(Tint::Green));
+        board.retarget_beams();
+
+        let solution = board.solve().unwrap();
+        assert_eq!(solution, vec![Move::new((0, 0).into(), Direction::Right)]);
+    }
+
+    #[test]
+    fn solve_through_recolor() {
+        let board = BoardBuilder::new(1, 4)
+            .with_tile((0, 0).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_tile((0, 1).into(), Tile::new(TileKind::Platform, Tint::White))
+            .with_tile((0, 2).into(), Tile::new(TileKind::Recolor, Tint::Red))
+            .with_tile((0, 3).into(), Tile::new(TileKind::Collector, Tint::Red))
+            .with_piece((0, 0).into(), Manipulator::new(Emitters::Right))
+            .with_piece((0, 1).into(), Particle::new(Tint::Green))
+            .build();
+
+        let step = Move::new((0, 0).into(), Direction::Right);
+        let step_after = Move::new((0, 1).into(), Direction::Right);
+        assert_eq!(board.solve().unwrap(), vec![step, step_after]);
+    }
+
+    #[test]
+    fn solution_wins_campaign_level() {
+        let board = Board::from_pbc1(crate::model::CLASSIC_CAMPAIGN_DATA[0].1[0].1).unwrap();
+        let solution = board.solve().unwrap();
+
+        let mut board = board.clone();
+        board.retarget_beams();
+        let mut progress = LevelProgress::new(&board);
+        for step in solution {
+            assert_eq!(progress.outcome, None);
+            play_move(&mut board, &mut progress, step);
+        }
+        assert_eq!(progress.outcome, Some(LevelOutcome::Victory));
+    }
+}
@@ -2,6 +2,9 @@ use crate::model::grid::GridQueue;
 
 use super::{BeamTargetKind, Board, GridSet, Piece};
 
+// NOTE: The order in which pieces are queued only affects how soon each one is reached, not
+// whether it is. The result is a set, iterated in row-major order like any other, and all of the
+// unsupported pieces fade out together, so callers never observe the queue order.
 pub fn unsupported_pieces(board: &Board) -> GridSet {
     let mut unsupported = GridSet::like(&board.pieces);
     let mut support_queue = GridQueue::for_grid(&unsupported);
@@ -64,6 +67,65 @@ mod tests {
         assert!(!set.contains((2, 1).into()));
     }
 
+    #[test]
+    fn branching_support() {
+        let mut board = Board::new(4, 4);
+        add_tile(&mut board, (0, 0).into(), TileKind::Platform, Tint::White);
+        add_manipulator(&mut board, (0, 0).into(), Emitters::RightDown);
+        add_manipulator(&mut board, (0, 1).into(), Emitters::RightDown);
+        board.pieces.set((0, 2).into(), Particle::new(Tint::Red));
+        add_manipulator(&mut board, (1, 0).into(), Emitters::Down);
+        board.pieces.set((1, 1).into(), Particle::new(Tint::Green));
+        board.pieces.set((2, 0).into(), Particle::new(Tint::Yellow));
+        add_manipulator(&mut board, (2, 2).into(), Emitters::Right);
+        board.pieces.set((2, 3).into(), Particle::new(Tint::Green));
+        add_manipulator(&mut board, (3, 2).into(), Emitters::Up);
+        board.pieces.set((3, 3).into(), Particle::new(Tint::Red));
+        board.retarget_beams();
+
+        let expected: Vec<BoardCoords> =
+            vec![(2, 2).into(), (2, 3).into(), (3, 2).into(), (3, 3).into()];
+        let set = unsupported_pieces(&board);
+        assert_eq!(set.iter().collect::<Vec<_>>(), expected);
+        assert_eq!(
+            unsupported_pieces(&board).iter().collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn pieces_supported_by() {
+        let mut board = Board::new(3, 2);
+        add_tile(&mut board, (1, 1).into(), TileKind::Platform, Tint::White);
+        add_manipulator(&mut board, (0, 0).into(), Emitters::RightDown);
+        board.pieces.set((0, 1).into(), Particle::new(Tint::Red));
+        add_manipulator(&mut board, (1, 0).into(), Emitters::UpDown);
+        add_manipulator(&mut board, (2, 0).into(), Emitters::RightUp);
+        board.pieces.set((2, 1).into(), Particle::new(Tint::Green));
+        add_manipulator(&mut board, (1, 1).into(), Emitters::Left);
+        board.retarget_beams();
+
+        let others: Vec<BoardCoords> =
+            vec![(0, 0).into(), (0, 1).into(), (2, 0).into(), (2, 1).into()];
+        let mut keystone = others.clone();
+        keystone.insert(2, (1, 0).into());
+        assert_eq!(
+            board
+                .pieces_supported_by((1, 1).into())
+                .iter()
+                .collect::<Vec<_>>(),
+            keystone
+        );
+        assert_eq!(
+            board
+                .pieces_supported_by((1, 0).into())
+                .iter()
+                .collect::<Vec<_>>(),
+            others
+        );
+        assert!(board.pieces_supported_by((0, 1).into()).is_empty());
+    }
+
     fn add_tile(board: &mut Board, coords: BoardCoords, kind: TileKind, tint: Tint) {
         board.tiles.set(coords, Tile::new(kind, tint));
     }